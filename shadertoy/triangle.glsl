@@ -0,0 +1,32 @@
+// The triangle pattern as a plain Shadertoy shader, run it with `--shadertoy shadertoy/triangle.glsl`.
+// Dragging with the left mouse button moves the triangle.
+
+vec3 palette(float t) {
+    vec3 a = vec3(0.5, 0.5, 0.5);
+    vec3 b = vec3(0.42, 0.42, 0.42);
+    vec3 c = vec3(0.76, 0.76, 0.76);
+    vec3 d = vec3(1.588, 1.922, 2.255);
+    return a + b * cos(6.28318 * (c * t + d));
+}
+
+float sdEquilateralTriangle(vec2 p, float r) {
+    const float k = sqrt(3.0);
+    p.x = abs(p.x) - r;
+    p.y = p.y + r / k;
+    if (p.x + k * p.y > 0.0) p = vec2(p.x - k * p.y, -k * p.x - p.y) / 2.0;
+    p.x -= clamp(p.x, -2.0 * r, 0.0);
+    return -length(p) * sign(p.y);
+}
+
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 uv = (fragCoord * 2.0 - iResolution.xy) / iResolution.y;
+    if (iMouse.z > 0.0) {
+        uv -= (iMouse.xy * 2.0 - iResolution.xy) / iResolution.y;
+    }
+
+    vec3 colour = palette(length(uv) + iTime);
+    float d = sdEquilateralTriangle(uv, 1.0);
+    colour *= 0.015 / abs(sin(d * 8.0 + iTime) / 8.0);
+
+    fragColor = vec4(colour, 1.0);
+}
@@ -2,10 +2,15 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
 use vulkano::descriptor_set::allocator::{StandardDescriptorSetAllocator};
+use vulkano::device::physical::PhysicalDevice;
+use vulkano::device::{DeviceExtensions, QueueFlags};
+use vulkano::instance::{Instance, InstanceCreateInfo};
+use vulkano::VulkanLibrary;
 use vulkano_util::context::{VulkanoConfig, VulkanoContext};
 use vulkano_util::window::{VulkanoWindows, WindowDescriptor};
 use winit::event_loop::EventLoop;
 use winit::window::WindowId;
+use crate::scene::Scene;
 use crate::signed_distance_function_renderer::{SimpleVulkanRendererRenderPipeline};
 
 
@@ -23,6 +28,7 @@ impl Application {
         &mut self,
         event_loop: &EventLoop<()>,
         window_descriptor: WindowDescriptor,
+        scene: &Scene,
     ) {
         let new_window = self.windows.create_window(
             event_loop,
@@ -36,17 +42,29 @@ impl Application {
             new_window,
             SimpleVulkanRendererRenderPipeline::new(
                 self,
+                event_loop,
                 self.context.graphics_queue().clone(),
                 self.context.graphics_queue().clone(),
                 [window_descriptor.width as u32, window_descriptor.height as u32],
-                self.windows.get_primary_renderer().unwrap().swapchain_format()
+                self.windows.get_primary_renderer().unwrap().swapchain_format(),
+                scene,
             ));
     }
-}
 
-impl Default for Application {
-    fn default() -> Self {
-        let context = VulkanoContext::new(VulkanoConfig::default());
+    /// Creates only the Vulkan device, for rendering without a window. No swapchain support
+    /// is required so software implementations such as lavapipe can be used. Fails when
+    /// there is no Vulkan loader or no usable device, where `VulkanoContext` would panic.
+    pub fn headless() -> Result<Application, String> {
+        find_headless_device()?;
+        Ok(Application::with_config(VulkanoConfig {
+            device_extensions: DeviceExtensions::empty(),
+            device_filter_fn: Arc::new(has_graphics_queue),
+            ..Default::default()
+        }))
+    }
+
+    fn with_config(config: VulkanoConfig) -> Application {
+        let context = VulkanoContext::new(config);
         let standard_command_buffer_allocator = StandardCommandBufferAllocator::new(context.device().clone(), Default::default());
         let command_buffer_allocator = Arc::new(standard_command_buffer_allocator);
 
@@ -61,4 +79,34 @@ impl Default for Application {
             pipelines: HashMap::new(),
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for Application {
+    fn default() -> Self {
+        Application::with_config(VulkanoConfig::default())
+    }
+}
+
+/// `VulkanoContext` wants a graphics queue even without windows, compute work runs on it
+/// when the device has no separate compute queue.
+fn has_graphics_queue(device: &PhysicalDevice) -> bool {
+    device.queue_family_properties().iter().any(|family| family.queue_flags.intersects(QueueFlags::GRAPHICS))
+}
+
+/// Goes through the steps `VulkanoContext::new` takes up to picking a device, returning
+/// the first failure instead of panicking.
+fn find_headless_device() -> Result<(), String> {
+    let library = VulkanLibrary::new().map_err(|e| format!("failed to load the Vulkan library: {e}"))?;
+    let defaults = VulkanoConfig::default().instance_create_info;
+    let create_info = InstanceCreateInfo {
+        enabled_extensions: vulkano_win::required_extensions(&library).union(&defaults.enabled_extensions),
+        ..defaults
+    };
+    let instance = Instance::new(library, create_info).map_err(|e| format!("failed to create a Vulkan instance: {e}"))?;
+    let mut devices = instance.enumerate_physical_devices().map_err(|e| format!("failed to list Vulkan devices: {e}"))?;
+    if devices.any(|device| has_graphics_queue(&device)) {
+        Ok(())
+    } else {
+        Err("no Vulkan device with a graphics queue was found".to_string())
+    }
+}
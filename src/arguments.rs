@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+use clap::Parser;
+use crate::frame_export::{FrameFormat, MIN_FRAME_RATE};
+use crate::signed_distance_function_renderer::{RenderMode, DEFAULT_SAMPLE_TARGET};
+
+/// Command line options.
+#[derive(Parser, Debug)]
+#[command(about = "Renders signed distance function scenes with a Vulkan compute shader.")]
+pub struct Arguments {
+
+    /// Scene file (`.ron` or `.json`) to render instead of the default triangle.
+    #[arg(long, value_name = "PATH")]
+    pub scene: Option<PathBuf>,
+
+    /// Writes the active scene to a `.ron` or `.json` file before opening the window.
+    #[arg(long, value_name = "PATH")]
+    pub save_scene: Option<PathBuf>,
+
+    /// Key bindings file (`.ron`) that overrides the default keys of some or all actions.
+    #[arg(long, value_name = "PATH")]
+    pub bindings: Option<PathBuf>,
+
+    /// Palette file (`.ron`) with the presets to cycle through instead of the built in ones.
+    #[arg(long, value_name = "PATH")]
+    pub palettes: Option<PathBuf>,
+
+    /// Palette preset to start with, by name. `[` and `]` cycle through them in the window.
+    #[arg(long, value_name = "NAME")]
+    pub palette: Option<String>,
+
+    /// Shader parameter file (`.ron`) with values for annotated shader uniforms. Parameters it
+    /// leaves out keep the defaults annotated in the shader.
+    #[arg(long, value_name = "PATH")]
+    pub parameters: Option<PathBuf>,
+
+    /// Writes every annotated parameter of the shader with its value to a `.ron` file before
+    /// opening the window.
+    #[arg(long, value_name = "PATH")]
+    pub save_parameters: Option<PathBuf>,
+
+    /// Draws the scene as a 2D distance field, raymarched in 3D or path traced in 3D.
+    /// `Tab` cycles through them in the window.
+    #[arg(long, value_enum, default_value_t = RenderMode::Flat, conflicts_with = "shadertoy")]
+    pub mode: RenderMode,
+
+    /// Samples per pixel the path tracer adds up before it stops. Each `--output` frame
+    /// takes all of them.
+    #[arg(long, default_value_t = DEFAULT_SAMPLE_TARGET, value_parser = clap::value_parser!(u32).range(1..))]
+    pub samples: u32,
+
+    /// GLSL file with a Shadertoy `mainImage` function, or a Shadertoy `.json` export with
+    /// its buffer passes, to run instead of the scene shader.
+    #[arg(long, value_name = "PATH", conflicts_with = "scene")]
+    pub shadertoy: Option<PathBuf>,
+
+    /// Renders without opening a window. PNG sequences number the files, replacing
+    /// `{frame}` in the path if present. Raw formats write to a single file, or stdout with `-`.
+    #[arg(long, value_name = "PATH", conflicts_with = "hot_reload")]
+    pub output: Option<PathBuf>,
+
+    /// Format of the `--output` frames.
+    #[arg(long, value_enum, default_value_t = FrameFormat::Png, requires = "output")]
+    pub format: FrameFormat,
+
+    /// Number of frames to render with `--output`.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..), requires = "output")]
+    pub frames: u32,
+
+    /// Frames per second of the `--output` animation, frame `n` is rendered at `n / fps` seconds.
+    #[arg(long, default_value_t = 60.0, value_parser = parse_frame_rate, requires = "output")]
+    pub fps: f64,
+
+    /// Renders the `--output` frames with the CPU reference evaluator instead of Vulkan.
+    #[arg(long, requires = "output", conflicts_with = "shadertoy")]
+    pub cpu: bool,
+
+    /// Width of the window or rendered image in pixels.
+    #[arg(long, default_value_t = 1024, value_parser = clap::value_parser!(u32).range(1..))]
+    pub width: u32,
+
+    /// Height of the window or rendered image in pixels.
+    #[arg(long, default_value_t = 1024, value_parser = clap::value_parser!(u32).range(1..))]
+    pub height: u32,
+
+    /// Renders the window at this fraction of its resolution, `0.5` computes a quarter of
+    /// the pixels and stretches them over the window.
+    #[arg(long, default_value_t = 1.0, value_parser = parse_render_scale, conflicts_with = "output")]
+    pub render_scale: f32,
+
+    /// Compute workgroup size as `WIDTHxHEIGHT`, the best value depends on the device.
+    #[arg(long, value_name = "SIZE", default_value = "8x8", value_parser = parse_workgroup_size)]
+    pub workgroup_size: [u32; 2],
+
+    /// Watches `src/shaders` and recompiles the compute shader whenever a file changes.
+    /// A `--shadertoy` file is watched as well.
+    #[arg(long)]
+    pub hot_reload: bool,
+}
+
+fn parse_frame_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(frame_rate) if frame_rate.is_finite() && frame_rate >= MIN_FRAME_RATE => Ok(frame_rate),
+        Ok(_) => Err(format!("must be at least {MIN_FRAME_RATE}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_render_scale(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(scale) if scale > 0.0 && scale <= 4.0 => Ok(scale),
+        Ok(_) => Err("must be greater than 0 and at most 4".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_workgroup_size(value: &str) -> Result<[u32; 2], String> {
+    let (width, height) = value.split_once('x').ok_or("expected `WIDTHxHEIGHT`, such as `16x8`")?;
+    let size = [width, height].map(|length| length.trim().parse::<u32>());
+    match size {
+        [Ok(width), Ok(height)] if width > 0 && height > 0 => Ok([width, height]),
+        [Ok(_), Ok(_)] => Err("both sides must be at least 1".to_string()),
+        [Err(e), _] | [_, Err(e)] => Err(e.to_string()),
+    }
+}
@@ -0,0 +1,204 @@
+use winit::event::MouseButton;
+use crate::input_bindings::Action;
+use crate::user_io::{MouseState, UserIO};
+
+/// Each scrolled line zooms by this factor.
+const ZOOM_PER_SCROLL_LINE: f32 = 1.1;
+const MIN_ZOOM: f32 = 1e-3;
+const MAX_ZOOM: f32 = 1e4;
+
+/// Rotation while a rotate action is held, in radians per second.
+const ROTATION_SPEED: f32 = std::f32::consts::FRAC_PI_2;
+
+/// Letting go of a rotate action snaps the rotation to a multiple of this angle.
+const ROTATION_SNAP: f32 = std::f32::consts::PI / 12.0;
+
+/// View of the 2D scene. Screen coordinates are centred and span -1 to 1 along the shorter
+/// side of the image, with y up. They map to scene coordinates as
+/// `rotate(screen / zoom, rotation) + offset`, the same as `screen_to_scene` in `shapes_cs.glsl`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera2D {
+    pub offset: [f32; 2],
+    pub zoom: f32,
+    pub rotation: f32,
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Camera2D {
+            offset: [0.0, 0.0],
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+impl Camera2D {
+    /// Applies this frame's input. Dragging with the left button pans, the wheel zooms around
+    /// the cursor and the view actions rotate and reset it. `delta_time` is in seconds.
+    pub fn update(
+        &mut self,
+        user_input: &UserIO,
+        mouse: &MouseState,
+        window_size: [f32; 2],
+        delta_time: f32,
+    ) {
+        if user_input.is_action_pressed(Action::ResetView) {
+            *self = Camera2D::default();
+        }
+
+        let rotate_actions = [(Action::RotateViewLeft, 1.0), (Action::RotateViewRight, -1.0)];
+        for (action, direction) in rotate_actions {
+            if user_input.is_action_held(action) {
+                self.rotation += direction * ROTATION_SPEED * delta_time;
+            }
+            if user_input.is_action_released(action) {
+                self.rotation = (self.rotation / ROTATION_SNAP).round() * ROTATION_SNAP;
+            }
+        }
+
+        let scale = 2.0 / window_size[0].min(window_size[1]);
+        let (drag_x, drag_y) = mouse.drag_delta();
+        if drag_x != 0.0 || drag_y != 0.0 {
+            // Window y points down, screen y up.
+            let pan = self.screen_vector_to_scene([drag_x as f32 * scale, -drag_y as f32 * scale]);
+            self.offset = [self.offset[0] - pan[0], self.offset[1] - pan[1]];
+        }
+
+        let wheel_lines = mouse.wheel_delta() as f32;
+        if wheel_lines != 0.0 {
+            let (cursor_x, cursor_y) = mouse.cursor_position();
+            let cursor = [
+                (cursor_x as f32 * 2.0 - window_size[0]) * scale * 0.5,
+                (window_size[1] - cursor_y as f32 * 2.0) * scale * 0.5,
+            ];
+
+            // Keep the scene point under the cursor in place.
+            let anchor = self.screen_to_scene(cursor);
+            self.zoom = (self.zoom * ZOOM_PER_SCROLL_LINE.powf(wheel_lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+            let moved = self.screen_vector_to_scene(cursor);
+            self.offset = [anchor[0] - moved[0], anchor[1] - moved[1]];
+        }
+    }
+
+    pub fn screen_to_scene(&self, screen: [f32; 2]) -> [f32; 2] {
+        let vector = self.screen_vector_to_scene(screen);
+        [vector[0] + self.offset[0], vector[1] + self.offset[1]]
+    }
+
+    /// Scales and rotates without the offset.
+    fn screen_vector_to_scene(&self, screen: [f32; 2]) -> [f32; 2] {
+        let (s, c) = self.rotation.sin_cos();
+        let [x, y] = [screen[0] / self.zoom, screen[1] / self.zoom];
+        [c * x - s * y, s * x + c * y]
+    }
+}
+
+/// Radians of yaw or pitch per unit of raw mouse motion while looking around.
+const LOOK_SENSITIVITY: f32 = 0.003;
+
+/// Keeps the view just short of straight up or down, where yaw would flip.
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+/// Flying speed in scene units per second.
+const MOVE_SPEED: f32 = 2.0;
+
+/// Each scrolled line narrows the field of view by this factor.
+const FIELD_OF_VIEW_PER_SCROLL_LINE: f32 = 1.1;
+const MIN_FIELD_OF_VIEW: f32 = 0.1;
+const MAX_FIELD_OF_VIEW: f32 = 2.5;
+
+/// Perspective fly camera of the raymarched view. A yaw of 0 looks down -z, increasing yaw
+/// turns left and positive pitch looks up. Rays are built the same way as `camera_ray_direction`
+/// in `raymarch_cs.glsl`, over the same centred screen coordinates as `Camera2D`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera3D {
+    pub position: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+
+    /// Vertical field of view in radians, along the shorter side for wide windows.
+    pub field_of_view: f32,
+}
+
+impl Default for Camera3D {
+    fn default() -> Self {
+        Camera3D {
+            position: [0.0, 0.5, 3.5],
+            yaw: 0.0,
+            pitch: -0.15,
+            field_of_view: std::f32::consts::FRAC_PI_3,
+        }
+    }
+}
+
+impl Camera3D {
+    /// Applies this frame's input. Moving the mouse with the left button down looks around,
+    /// the move actions fly along the view, the wheel zooms and `ResetView` resets the camera.
+    pub fn update(
+        &mut self,
+        user_input: &UserIO,
+        mouse: &MouseState,
+        delta_time: f32,
+    ) {
+        if user_input.is_action_pressed(Action::ResetView) {
+            *self = Camera3D::default();
+        }
+
+        // Raw motion keeps turning at the window edge, unlike the cursor.
+        if mouse.is_button_down(MouseButton::Left) {
+            let (motion_x, motion_y) = user_input.mouse_motion_delta();
+            self.yaw -= motion_x as f32 * LOOK_SENSITIVITY;
+            self.pitch = (self.pitch - motion_y as f32 * LOOK_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+
+        let forward = self.forward();
+        let right = self.right();
+        let move_actions = [
+            (Action::MoveForward, forward),
+            (Action::MoveBackward, forward.map(|x| -x)),
+            (Action::MoveRight, right),
+            (Action::MoveLeft, right.map(|x| -x)),
+            (Action::MoveUp, [0.0, 1.0, 0.0]),
+            (Action::MoveDown, [0.0, -1.0, 0.0]),
+        ];
+        let mut direction = [0.0f32; 3];
+        for (action, axis) in move_actions {
+            if user_input.is_action_held(action) {
+                direction = [direction[0] + axis[0], direction[1] + axis[1], direction[2] + axis[2]];
+            }
+        }
+
+        // Diagonals are no faster than straight moves.
+        let length = direction.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if length > 0.0 {
+            let step = MOVE_SPEED * delta_time / length;
+            for (position, direction) in self.position.iter_mut().zip(direction) {
+                *position += direction * step;
+            }
+        }
+
+        let wheel_lines = mouse.wheel_delta() as f32;
+        if wheel_lines != 0.0 {
+            self.field_of_view = (self.field_of_view / FIELD_OF_VIEW_PER_SCROLL_LINE.powf(wheel_lines))
+                .clamp(MIN_FIELD_OF_VIEW, MAX_FIELD_OF_VIEW);
+        }
+    }
+
+    pub fn forward(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        [-sin_yaw * cos_pitch, sin_pitch, -cos_yaw * cos_pitch]
+    }
+
+    /// Always level, so flying sideways keeps the height.
+    pub fn right(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        [cos_yaw, 0.0, -sin_yaw]
+    }
+
+    /// Tangent of half the field of view, how far the edge of the screen is off the view axis.
+    pub fn focal_scale(&self) -> f32 {
+        (self.field_of_view * 0.5).tan()
+    }
+}
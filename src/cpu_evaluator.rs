@@ -0,0 +1,1178 @@
+use std::f32::consts::{PI, TAU};
+use cgmath::{ElementWise, InnerSpace, Matrix2, Vector2, Vector3};
+use rayon::prelude::*;
+use crate::camera::{Camera2D, Camera3D};
+use crate::palette::{GpuPalette, GpuPalettes, PALETTE_GRADIENT};
+use crate::scene::*;
+use crate::shader_parameters::{resolve_value, ParameterStore};
+use crate::shader_reload::built_in_annotations;
+
+// CPU port of `shapes_cs.glsl`, `raymarch_cs.glsl`, `path_trace_cs.glsl` and their includes. The scene is
+// evaluated from the same postfix program that is uploaded to the GPU, and every function mirrors its GLSL namesake line by
+// line, so both renderers agree up to floating point differences in the transcendental functions.
+
+const MAX_DISTANCE: f32 = 1e10;
+const ROOT_3: f32 = 1.732_050_8;
+
+const BLEND_EXPONENTIAL: u32 = 1;
+
+type Vec2 = Vector2<f32>;
+type Vec3 = Vector3<f32>;
+
+fn vec2(x: f32, y: f32) -> Vec2 {
+    Vector2::new(x, y)
+}
+
+fn vec3(x: f32, y: f32, z: f32) -> Vec3 {
+    Vector3::new(x, y, z)
+}
+
+// GLSL built-ins that behave differently from their Rust counterparts.
+
+/// `sign` is 0 for 0, `f32::signum` is 1 for +0.
+fn sign(x: f32) -> f32 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+/// `mod` takes the sign of `y`, unlike `%`.
+fn glsl_mod(x: f32, y: f32) -> f32 {
+    x - y * (x / y).floor()
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a * (1.0 - t) + b * t
+}
+
+fn clamp(x: f32, low: f32, high: f32) -> f32 {
+    x.max(low).min(high)
+}
+
+fn dot2(v: Vec2) -> f32 {
+    v.dot(v)
+}
+
+// sdf_domain.glsl
+
+fn rotate(point: Vec2, angle: f32) -> Vec2 {
+    let c = angle.cos();
+    let s = angle.sin();
+    Matrix2::new(c, s, -s, c) * point
+}
+
+fn translate_domain(point: Vec2, offset: Vec2) -> Vec2 {
+    point - offset
+}
+
+fn rotate_domain(point: Vec2, angle: f32) -> Vec2 {
+    rotate(point, -angle)
+}
+
+fn scale_domain(point: Vec2, factor: f32) -> Vec2 {
+    point / factor
+}
+
+fn repeat_domain(point: Vec2, period: Vec2) -> Vec2 {
+    point - period.mul_element_wise(point.div_element_wise(period).map(|x| (x + 0.5).floor()))
+}
+
+fn repeat_limited_domain(point: Vec2, period: Vec2, limit: Vec2) -> Vec2 {
+    let cell = point.div_element_wise(period).map(|x| (x + 0.5).floor());
+    point - period.mul_element_wise(vec2(clamp(cell.x, -limit.x, limit.x), clamp(cell.y, -limit.y, limit.y)))
+}
+
+fn polar_repeat_domain(point: Vec2, count: u32) -> Vec2 {
+    let sector = TAU / count as f32;
+    let angle = glsl_mod(point.y.atan2(point.x) + sector * 0.5, sector) - sector * 0.5;
+    vec2(angle.cos(), angle.sin()) * point.magnitude()
+}
+
+fn mirror_domain(point: Vec2, normal: Vec2) -> Vec2 {
+    point - normal * (2.0 * point.dot(normal).min(0.0))
+}
+
+fn twist_domain(point: Vec2, strength: f32) -> Vec2 {
+    rotate(point, strength * point.magnitude())
+}
+
+/// Integer hash from https://nullprogram.com/blog/2018/07/31/, same as the shader.
+fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    x
+}
+
+fn noise_gradient(cell: [i32; 2]) -> Vec2 {
+    let h = hash(cell[0] as u32 ^ hash(cell[1] as u32));
+    vec2((h & 0xffff) as f32, (h >> 16) as f32) / 65535.0 * 2.0 - vec2(1.0, 1.0)
+}
+
+fn gradient_noise(point: Vec2) -> f32 {
+    let cell = point.map(f32::floor);
+    let f = point - cell;
+    let u = f.mul_element_wise(f.mul_element_wise(vec2(3.0, 3.0) - f * 2.0));
+    let i = [cell.x as i32, cell.y as i32];
+
+    let a = noise_gradient(i).dot(f);
+    let b = noise_gradient([i[0] + 1, i[1]]).dot(f - vec2(1.0, 0.0));
+    let c = noise_gradient([i[0], i[1] + 1]).dot(f - vec2(0.0, 1.0));
+    let d = noise_gradient([i[0] + 1, i[1] + 1]).dot(f - vec2(1.0, 1.0));
+    mix(mix(a, b, u.x), mix(c, d, u.x), u.y)
+}
+
+fn warp_domain(point: Vec2, amplitude: f32, frequency: f32, phase: f32) -> Vec2 {
+    let q = point * frequency + vec2(phase, phase);
+    point + vec2(gradient_noise(q), gradient_noise(q + vec2(5.2, 1.3))) * amplitude
+}
+
+// sdf_operators.glsl
+
+fn smooth_minimum(a: f32, b: f32, radius: f32, blend: u32) -> f32 {
+    if blend == BLEND_EXPONENTIAL {
+        return -radius * ((-a / radius).exp2() + (-b / radius).exp2()).log2();
+    }
+
+    let h = (radius - (a - b).abs()).max(0.0) / radius;
+    a.min(b) - h * h * radius * 0.25
+}
+
+fn annular_operator(signed_distance: f32, thickness: f32) -> f32 {
+    signed_distance.abs() - thickness
+}
+
+fn onion_operator(mut signed_distance: f32, mut thickness: f32, layers: u32) -> f32 {
+    for _ in 0..layers {
+        signed_distance = annular_operator(signed_distance, thickness);
+        thickness *= 0.5;
+    }
+    signed_distance
+}
+
+// sdf_2d.glsl
+
+pub fn equalateral_triangle_signed_distance_function(point: Vec2, radius: f32) -> f32 {
+    let k = ROOT_3;
+    let mut point = mirror_domain(point, vec2(1.0, 0.0));
+    point.x -= radius;
+    point.y += radius / k;
+
+    if point.x + k * point.y > 0.0 {
+        point = vec2(point.x - k * point.y, -k * point.x - point.y) / 2.0;
+    }
+
+    point.x -= clamp(point.x, -2.0 * radius, 0.0);
+    -point.magnitude() * sign(point.y)
+}
+
+pub fn circle_signed_distance_function(point: Vec2, radius: f32) -> f32 {
+    point.magnitude() - radius
+}
+
+pub fn box_signed_distance_function(point: Vec2, half_extents: Vec2) -> f32 {
+    let d = point.map(f32::abs) - half_extents;
+    d.map(|x| x.max(0.0)).magnitude() + d.x.max(d.y).min(0.0)
+}
+
+pub fn rounded_box_signed_distance_function(point: Vec2, half_extents: Vec2, corner_radii: [f32; 4]) -> f32 {
+    let side = if point.x > 0.0 { [corner_radii[0], corner_radii[1]] } else { [corner_radii[2], corner_radii[3]] };
+    let radius = if point.y > 0.0 { side[0] } else { side[1] };
+    let q = point.map(f32::abs) - half_extents + vec2(radius, radius);
+    q.x.max(q.y).min(0.0) + q.map(|x| x.max(0.0)).magnitude() - radius
+}
+
+pub fn segment_signed_distance_function(point: Vec2, start: Vec2, end: Vec2, thickness: f32) -> f32 {
+    let pa = point - start;
+    let ba = end - start;
+    let h = clamp(pa.dot(ba) / ba.dot(ba), 0.0, 1.0);
+    (pa - ba * h).magnitude() - thickness
+}
+
+pub fn arc_signed_distance_function(mut point: Vec2, aperture: f32, radius: f32, thickness: f32) -> f32 {
+    let sc = vec2(aperture.sin(), aperture.cos());
+    point.x = point.x.abs();
+    let distance = if sc.y * point.x > sc.x * point.y {
+        (point - sc * radius).magnitude()
+    } else {
+        (point.magnitude() - radius).abs()
+    };
+    distance - thickness
+}
+
+pub fn ellipse_signed_distance_function(point: Vec2, mut radii: Vec2) -> f32 {
+    if (radii.x - radii.y).abs() < 1e-5 {
+        return circle_signed_distance_function(point, radii.x);
+    }
+
+    let mut point = point.map(f32::abs);
+    if point.x > point.y {
+        point = vec2(point.y, point.x);
+        radii = vec2(radii.y, radii.x);
+    }
+
+    let l = radii.y * radii.y - radii.x * radii.x;
+    let m = radii.x * point.x / l;
+    let m2 = m * m;
+    let n = radii.y * point.y / l;
+    let n2 = n * n;
+    let c = (m2 + n2 - 1.0) / 3.0;
+    let c3 = c * c * c;
+    let q = c3 + m2 * n2 * 2.0;
+    let d = c3 + m2 * n2;
+    let g = m + m * n2;
+
+    let co = if d < 0.0 {
+        let h = (q / c3).acos() / 3.0;
+        let s = h.cos();
+        let t = h.sin() * ROOT_3;
+        let rx = (-c * (s + t + 2.0) + m2).sqrt();
+        let ry = (-c * (s - t + 2.0) + m2).sqrt();
+        (ry + sign(l) * rx + g.abs() / (rx * ry) - m) / 2.0
+    } else {
+        let h = 2.0 * m * n * d.sqrt();
+        let s = sign(q + h) * (q + h).abs().powf(1.0 / 3.0);
+        let u = sign(q - h) * (q - h).abs().powf(1.0 / 3.0);
+        let rx = -s - u - c * 4.0 + 2.0 * m2;
+        let ry = (s - u) * ROOT_3;
+        let rm = (rx * rx + ry * ry).sqrt();
+        (ry / (rm - rx).sqrt() + 2.0 * g / rm - m) / 2.0
+    };
+
+    let r = radii.mul_element_wise(vec2(co, (1.0 - co * co).sqrt()));
+    (r - point).magnitude() * sign(point.y - r.y)
+}
+
+pub fn regular_polygon_signed_distance_function(point: Vec2, radius: f32, sides: u32) -> f32 {
+    let an = PI / sides as f32;
+    let acs = vec2(an.cos(), an.sin());
+    let bn = glsl_mod(point.x.atan2(point.y), 2.0 * an) - an;
+    let mut point = vec2(bn.cos(), bn.sin().abs()) * point.magnitude();
+    point -= acs * radius;
+    point.y += clamp(-point.y, 0.0, radius * acs.y);
+    point.magnitude() * sign(point.x)
+}
+
+pub fn star_signed_distance_function(point: Vec2, radius: f32, points: u32, density: f32) -> f32 {
+    let an = PI / points as f32;
+    let en = PI / density;
+    let acs = vec2(an.cos(), an.sin());
+    let ecs = vec2(en.cos(), en.sin());
+    let bn = glsl_mod(point.x.atan2(point.y), 2.0 * an) - an;
+    let mut point = vec2(bn.cos(), bn.sin().abs()) * point.magnitude();
+    point -= acs * radius;
+    point += ecs * clamp(-point.dot(ecs), 0.0, radius * acs.y / ecs.y);
+    point.magnitude() * sign(point.x)
+}
+
+pub fn hexagon_signed_distance_function(point: Vec2, radius: f32) -> f32 {
+    let k = Vector3::new(-0.866_025_4, 0.5, 0.577_350_26);
+    let kxy = vec2(k.x, k.y);
+    let mut point = point.map(f32::abs);
+    point -= kxy * (2.0 * kxy.dot(point).min(0.0));
+    point -= vec2(clamp(point.x, -k.z * radius, k.z * radius), radius);
+    point.magnitude() * sign(point.y)
+}
+
+pub fn vesica_signed_distance_function(point: Vec2, radius: f32, offset: f32) -> f32 {
+    let point = point.map(f32::abs);
+    let b = (radius * radius - offset * offset).sqrt();
+    if (point.y - b) * offset > point.x * b {
+        (point - vec2(0.0, b)).magnitude()
+    } else {
+        (point - vec2(-offset, 0.0)).magnitude() - radius
+    }
+}
+
+pub fn quadratic_bezier_signed_distance_function(point: Vec2, start: Vec2, control: Vec2, end: Vec2, thickness: f32) -> f32 {
+    let a = control - start;
+    let b = start - control * 2.0 + end;
+    let c = a * 2.0;
+    let d = start - point;
+
+    // A straight, evenly spaced curve has no quadratic term.
+    if b.dot(b) < 1e-8 {
+        return segment_signed_distance_function(point, start, end, thickness);
+    }
+
+    let kk = 1.0 / b.dot(b);
+    let kx = kk * a.dot(b);
+    let ky = kk * (2.0 * a.dot(a) + d.dot(b)) / 3.0;
+    let kz = kk * d.dot(a);
+    let p = ky - kx * kx;
+    let p3 = p * p * p;
+    let q = kx * (2.0 * kx * kx - 3.0 * ky) + kz;
+    let h = q * q + 4.0 * p3;
+
+    let result = if h >= 0.0 {
+        let h = h.sqrt();
+        let x = (vec2(h, -h) - vec2(q, q)) / 2.0;
+        let uv = x.map(|x| sign(x) * x.abs().powf(1.0 / 3.0));
+        let t = clamp(uv.x + uv.y - kx, 0.0, 1.0);
+        dot2(d + (c + b * t) * t)
+    } else {
+        let z = (-p).sqrt();
+        let v = (q / (p * z * 2.0)).acos() / 3.0;
+        let m = v.cos();
+        let n = v.sin() * ROOT_3;
+        let t = [m + m, -n - m, n - m].map(|t| clamp(t * z - kx, 0.0, 1.0));
+        dot2(d + (c + b * t[0]) * t[0]).min(dot2(d + (c + b * t[1]) * t[1]))
+    };
+
+    result.sqrt() - thickness
+}
+
+pub fn polygon_signed_distance_function(point: Vec2, vertices: &[[f32; 2]]) -> f32 {
+    let vertex = |index: usize| vec2(vertices[index][0], vertices[index][1]);
+    let count = vertices.len();
+    let mut d = dot2(point - vertex(0));
+    let mut s = 1.0;
+    let mut j = count - 1;
+    for i in 0..count {
+        let vi = vertex(i);
+        let vj = vertex(j);
+        let e = vj - vi;
+        let w = point - vi;
+        let b = w - e * clamp(w.dot(e) / e.dot(e), 0.0, 1.0);
+        d = d.min(b.dot(b));
+
+        let c = [point.y >= vi.y, point.y < vj.y, e.x * w.y > e.y * w.x];
+        if c.iter().all(|&c| c) || c.iter().all(|&c| !c) {
+            s *= -1.0;
+        }
+        j = i;
+    }
+    s * d.sqrt()
+}
+
+// sdf_3d.glsl
+
+pub fn sphere_signed_distance_function(point: Vec3, radius: f32) -> f32 {
+    point.magnitude() - radius
+}
+
+pub fn box_3d_signed_distance_function(point: Vec3, half_extents: Vec3) -> f32 {
+    let q = point.map(f32::abs) - half_extents;
+    q.map(|x| x.max(0.0)).magnitude() + q.x.max(q.y.max(q.z)).min(0.0)
+}
+
+pub fn round_box_3d_signed_distance_function(point: Vec3, half_extents: Vec3, radius: f32) -> f32 {
+    box_3d_signed_distance_function(point, half_extents.map(|x| x - radius)) - radius
+}
+
+pub fn torus_signed_distance_function(point: Vec3, major_radius: f32, minor_radius: f32) -> f32 {
+    let q = vec2(vec2(point.x, point.z).magnitude() - major_radius, point.y);
+    q.magnitude() - minor_radius
+}
+
+pub fn capsule_signed_distance_function(point: Vec3, start: Vec3, end: Vec3, radius: f32) -> f32 {
+    let pa = point - start;
+    let ba = end - start;
+    let h = clamp(pa.dot(ba) / ba.dot(ba), 0.0, 1.0);
+    (pa - ba * h).magnitude() - radius
+}
+
+pub fn cylinder_signed_distance_function(point: Vec3, radius: f32, half_height: f32) -> f32 {
+    let d = vec2(vec2(point.x, point.z).magnitude().abs() - radius, point.y.abs() - half_height);
+    d.x.max(d.y).min(0.0) + vec2(d.x.max(0.0), d.y.max(0.0)).magnitude()
+}
+
+pub fn cone_signed_distance_function(point: Vec3, half_height: f32, bottom_radius: f32, top_radius: f32) -> f32 {
+    let q = vec2(vec2(point.x, point.z).magnitude(), point.y);
+    let k1 = vec2(top_radius, half_height);
+    let k2 = vec2(top_radius - bottom_radius, 2.0 * half_height);
+    let ca = vec2(q.x - q.x.min(if q.y < 0.0 { bottom_radius } else { top_radius }), q.y.abs() - half_height);
+    let cb = q - k1 + k2 * clamp((k1 - q).dot(k2) / dot2(k2), 0.0, 1.0);
+    let s = if cb.x < 0.0 && ca.y < 0.0 { -1.0 } else { 1.0 };
+    s * dot2(ca).min(dot2(cb)).sqrt()
+}
+
+pub fn plane_signed_distance_function(point: Vec3, normal: Vec3, offset: f32) -> f32 {
+    point.dot(normal) - offset
+}
+
+pub fn octahedron_signed_distance_function(point: Vec3, radius: f32) -> f32 {
+    let point = point.map(f32::abs);
+    let m = point.x + point.y + point.z - radius;
+
+    let q = if 3.0 * point.x < m {
+        point
+    } else if 3.0 * point.y < m {
+        vec3(point.y, point.z, point.x)
+    } else if 3.0 * point.z < m {
+        vec3(point.z, point.x, point.y)
+    } else {
+        return m * 0.577_350_27;
+    };
+
+    let k = clamp(0.5 * (q.z - q.y + radius), 0.0, radius);
+    vec3(q.x, q.y - radius + k, q.z - k).magnitude()
+}
+
+fn extrusion_operator(signed_distance: f32, z: f32, half_depth: f32) -> f32 {
+    let w = vec2(signed_distance, z.abs() - half_depth);
+    w.x.max(w.y).min(0.0) + vec2(w.x.max(0.0), w.y.max(0.0)).magnitude()
+}
+
+fn revolution_domain(point: Vec3, radius: f32) -> Vec3 {
+    vec3(vec2(point.x, point.z).magnitude() - radius, point.y, 0.0)
+}
+
+fn translate_3d_domain(point: Vec3, offset: Vec3) -> Vec3 {
+    point - offset
+}
+
+fn rotate_3d_domain(point: Vec3, rotation: [f32; 4]) -> Vec3 {
+    let u = -vec3(rotation[0], rotation[1], rotation[2]);
+    point + u.cross(u.cross(point) + point * rotation[3]) * 2.0
+}
+
+fn scale_3d_domain(point: Vec3, factor: f32) -> Vec3 {
+    point / factor
+}
+
+// material.glsl
+
+fn lattice_value(cell: [i32; 3]) -> f32 {
+    (hash(cell[0] as u32 ^ hash(cell[1] as u32 ^ hash(cell[2] as u32))) & 0xffff) as f32 / 65535.0
+}
+
+fn value_noise(point: Vec3) -> f32 {
+    let cell = vec3(point.x.floor(), point.y.floor(), point.z.floor());
+    let f = point - cell;
+    let u = f.map(|x| x * x * (3.0 - 2.0 * x));
+    let i = [cell.x as i32, cell.y as i32, cell.z as i32];
+    let corner = |x: i32, y: i32, z: i32| lattice_value([i[0] + x, i[1] + y, i[2] + z]);
+
+    let bottom_front = mix(corner(0, 0, 0), corner(1, 0, 0), u.x);
+    let top_front = mix(corner(0, 1, 0), corner(1, 1, 0), u.x);
+    let bottom_back = mix(corner(0, 0, 1), corner(1, 0, 1), u.x);
+    let top_back = mix(corner(0, 1, 1), corner(1, 1, 1), u.x);
+    mix(mix(bottom_front, top_front, u.y), mix(bottom_back, top_back, u.y), u.z)
+}
+
+fn pattern_weight(material: &GpuMaterial, point: Vec3) -> f32 {
+    let p = material.pattern_params;
+
+    match material.header[0] {
+        PATTERN_CHECKER => glsl_mod((point.x / p[0]).floor() + (point.y / p[0]).floor() + (point.z / p[0]).floor(), 2.0),
+        PATTERN_STRIPES => {
+            let stripe = glsl_mod(point.dot(vec3(p[1], p[2], p[3])) / p[0], 2.0);
+            if stripe < 1.0 { 0.0 } else { 1.0 }
+        }
+        PATTERN_NOISE => value_noise(point / p[0]),
+        _ => 0.0,
+    }
+}
+
+fn material_albedo(material: &GpuMaterial, point: Vec3) -> Vec3 {
+    let weight = pattern_weight(material, point);
+    let [albedo, pattern_colour] = [material.albedo, material.pattern_colour]
+        .map(|colour| vec3(colour[0], colour[1], colour[2]));
+    albedo * (1.0 - weight) + pattern_colour * weight
+}
+
+// palette.glsl
+
+/// Keeps the shader's truncated `6.28318` so colours match.
+#[allow(clippy::approx_constant)]
+fn cosine_palette(palette: &GpuPalette, t: f32) -> [f32; 3] {
+    let [a, b, c, d] = [palette.a, palette.b, palette.c, palette.d];
+    [0, 1, 2].map(|i| a[i] + b[i] * (6.28318 * (c[i] * t + d[i])).cos())
+}
+
+fn gradient_palette(palette: &GpuPalette, t: f32) -> [f32; 3] {
+    let count = palette.header[1] as usize;
+    let x = t - t.floor();
+
+    let mut next = 0;
+    while next < count && palette.stops[next][3] <= x {
+        next += 1;
+    }
+
+    let before = if next == 0 {
+        let [r, g, b, position] = palette.stops[count - 1];
+        [r, g, b, position - 1.0]
+    } else {
+        palette.stops[next - 1]
+    };
+    let after = if next == count {
+        let [r, g, b, position] = palette.stops[0];
+        [r, g, b, position + 1.0]
+    } else {
+        palette.stops[next]
+    };
+    let span = after[3] - before[3];
+    let weight = if span > 0.0 { (x - before[3]) / span } else { 0.0 };
+    [0, 1, 2].map(|i| mix(before[i], after[i], weight))
+}
+
+fn evaluate_palette(palette: &GpuPalette, t: f32) -> [f32; 3] {
+    if palette.header[0] == PALETTE_GRADIENT {
+        return gradient_palette(palette, t);
+    }
+    cosine_palette(palette, t)
+}
+
+pub fn palette(palettes: &GpuPalettes, t: f32) -> [f32; 3] {
+    let current = evaluate_palette(&palettes.current, t);
+    if palettes.blend[0] >= 1.0 {
+        return current;
+    }
+    let mut previous = evaluate_palette(&palettes.previous, t);
+    if palettes.blend[1] < 1.0 {
+        let older = evaluate_palette(&palettes.older, t);
+        previous = [0, 1, 2].map(|i| mix(older[i], previous[i], palettes.blend[1]));
+    }
+    [0, 1, 2].map(|i| mix(previous[i], current[i], palettes.blend[0]))
+}
+
+// shapes_cs.glsl
+
+/// Centred screen coordinates of the pixel at `pos`, -1 to 1 along the shorter side.
+fn screen_position(pos: [u32; 2], dims: [u32; 2]) -> [f32; 2] {
+    let shorter_side = dims[0].min(dims[1]) as f32;
+    [
+        (pos[0] as f32 * 2.0 - dims[0] as f32) / shorter_side,
+        (pos[1] as f32 * 2.0 - dims[1] as f32) / shorter_side,
+    ]
+}
+
+/// `Camera2D::screen_to_scene` written with the shader's `rotate`, so rounding matches too.
+fn screen_to_scene(screen_pos: [f32; 2], camera: &Camera2D) -> [f32; 2] {
+    let point = rotate(vec2(screen_pos[0], screen_pos[1]) / camera.zoom, camera.rotation)
+        + vec2(camera.offset[0], camera.offset[1]);
+    [point.x, point.y]
+}
+
+// raymarch.glsl
+
+const MAX_MARCH_STEPS: usize = 160;
+const MAX_RAY_DISTANCE: f32 = 60.0;
+const SURFACE_EPSILON: f32 = 1e-3;
+
+const GROUND_HEIGHT: f32 = -1.0;
+
+const SUN_DIRECTION: [f32; 3] = [-0.4, 0.8, 0.45];
+const SUN_COLOUR: [f32; 3] = [1.0, 0.95, 0.85];
+const SKY_COLOUR: [f32; 3] = [0.45, 0.6, 0.85];
+const HORIZON_COLOUR: [f32; 3] = [0.8, 0.85, 0.9];
+
+fn ground_signed_distance_function(point: Vec3) -> f32 {
+    point.y - GROUND_HEIGHT
+}
+
+fn camera_ray_direction(screen_pos: [f32; 2], camera: &Camera3D) -> Vec3 {
+    let (yaw, pitch) = (camera.yaw, camera.pitch);
+    let forward = vec3(-yaw.sin() * pitch.cos(), pitch.sin(), -yaw.cos() * pitch.cos());
+    let right = vec3(yaw.cos(), 0.0, -yaw.sin());
+    let up = right.cross(forward);
+    (forward + (right * screen_pos[0] + up * screen_pos[1]) * camera.focal_scale()).normalize()
+}
+
+fn sky_colour(direction: Vec3) -> Vec3 {
+    let t = clamp(direction.y * 2.0, 0.0, 1.0);
+    Vec3::from(HORIZON_COLOUR) * (1.0 - t) + Vec3::from(SKY_COLOUR) * t
+}
+
+const DEFAULT_ROUGHNESS: f32 = 0.447_213_6;
+const DEFAULT_REFRACTIVE_INDEX: f32 = 1.5;
+
+struct SurfaceMaterial {
+    albedo: Vec3,
+    emission: Vec3,
+    roughness: f32,
+    metallic: f32,
+    transmission: f32,
+    refractive_index: f32,
+}
+
+fn specular_exponent(roughness: f32) -> f32 {
+    let alpha = roughness * roughness;
+    2.0 / (alpha * alpha).max(1e-4) - 2.0
+}
+
+// raymarch_cs.glsl
+
+const SHADOW_STEPS: usize = 64;
+const SHADOW_SOFTNESS: f32 = 8.0;
+const OCCLUSION_SAMPLES: usize = 5;
+
+const SPECULAR_STRENGTH: f32 = 0.5;
+
+// path_trace_cs.glsl
+
+const MAX_BOUNCES: usize = 8;
+const RUSSIAN_ROULETTE_BOUNCE: usize = 3;
+
+const SUN_SHARPNESS: f32 = 64.0;
+const SUN_STRENGTH: f32 = 12.0;
+
+fn pcg_hash(value: u32) -> u32 {
+    let state = value.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn random_float(rng_state: &mut u32) -> f32 {
+    *rng_state = pcg_hash(*rng_state);
+    (*rng_state >> 8) as f32 / 16777216.0
+}
+
+fn random_unit_vector(rng_state: &mut u32) -> Vec3 {
+    let z = random_float(rng_state) * 2.0 - 1.0;
+    let angle = random_float(rng_state) * TAU;
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    vec3(radius * angle.cos(), radius * angle.sin(), z)
+}
+
+fn environment_light(direction: Vec3) -> Vec3 {
+    let sun = direction.dot(Vec3::from(SUN_DIRECTION).normalize()).max(0.0).powf(SUN_SHARPNESS);
+    sky_colour(direction) + Vec3::from(SUN_COLOUR) * SUN_STRENGTH * sun
+}
+
+fn reflectance(cosine: f32, refraction_ratio: f32) -> f32 {
+    let r0 = (1.0 - refraction_ratio) / (1.0 + refraction_ratio);
+    let r0 = r0 * r0;
+    r0 + (1.0 - r0) * (1.0 - cosine).powf(5.0)
+}
+
+fn reflect(incident: Vec3, normal: Vec3) -> Vec3 {
+    incident - normal * 2.0 * normal.dot(incident)
+}
+
+fn refract(incident: Vec3, normal: Vec3, eta: f32) -> Vec3 {
+    let cosine = normal.dot(incident);
+    let k = 1.0 - eta * eta * (1.0 - cosine * cosine);
+    if k < 0.0 {
+        return vec3(0.0, 0.0, 0.0);
+    }
+    incident * eta - normal * (eta * cosine + k.sqrt())
+}
+
+/// The members of `Parameters` in `parameters.glsl` that the evaluator uses.
+#[derive(Clone, Copy, Debug)]
+struct Parameters {
+    line_glow: f32,
+    line_frequency: f32,
+    exposure: f32,
+    tint: Vec3,
+}
+
+impl Parameters {
+    /// Values from `store`, or the defaults annotated in the shader source.
+    fn resolve(store: &ParameterStore) -> Parameters {
+        let annotations = built_in_annotations();
+        let value = |name: &str| resolve_value(store, name, annotations.get(name));
+        let tint = value("tint");
+        Parameters {
+            line_glow: value("line_glow")[0],
+            line_frequency: value("line_frequency")[0],
+            exposure: value("exposure")[0],
+            tint: vec3(tint[0], tint[1], tint[2]),
+        }
+    }
+}
+
+/// Evaluates scenes on the CPU with the same math as the compute shader. Used as a renderer
+/// without a GPU, as the reference for image tests and to query distances directly.
+pub struct CpuSceneEvaluator {
+    nodes: Vec<GpuSceneNode>,
+    vertices: Vec<[f32; 2]>,
+    materials: Vec<GpuMaterial>,
+    palettes: GpuPalettes,
+    parameters: Parameters,
+}
+
+impl CpuSceneEvaluator {
+    pub fn new(scene: &Scene) -> CpuSceneEvaluator {
+        let encoded_scene = scene.encode();
+        CpuSceneEvaluator {
+            nodes: encoded_scene.nodes,
+            vertices: encoded_scene.vertices,
+            materials: encoded_scene.materials,
+            palettes: GpuPalettes::default(),
+            parameters: Parameters::resolve(&ParameterStore::default()),
+        }
+    }
+
+    /// Palette of the shapes without a material, the default preset until set.
+    pub fn set_palettes(&mut self, palettes: GpuPalettes) {
+        self.palettes = palettes;
+    }
+
+    /// Shader parameters, the annotated defaults for names not in `store`.
+    pub fn set_parameters(&mut self, store: &ParameterStore) {
+        self.parameters = Parameters::resolve(store);
+    }
+
+    /// Signed distance from `point` to the scene at `time` seconds, which only matters for warps,
+    /// and the material ID of the nearest shape. This is the z = 0 plane seen by the 2D view,
+    /// where extrusions are skipped.
+    pub fn signed_distance(&self, point: [f32; 2], time: f32) -> (f32, u32) {
+        self.scene_signed_distance_function_with_material(vec3(point[0], point[1], 0.0), time, false)
+    }
+
+    /// `scene_signed_distance_function`, `extrude` stands for the `SCENE_3D` define.
+    fn scene_signed_distance_function(&self, point: Vec3, time: f32, extrude: bool) -> f32 {
+        self.scene_signed_distance_function_with_material(point, time, extrude).0
+    }
+
+    /// The overload with the `material` output, the material ID of the shape that decides
+    /// the distance or `NO_MATERIAL`.
+    fn scene_signed_distance_function_with_material(&self, mut point: Vec3, time: f32, extrude: bool) -> (f32, u32) {
+        let mut stack = [0.0f32; MAX_STACK_DEPTH];
+        let mut material_stack = [NO_MATERIAL; MAX_STACK_DEPTH];
+        let mut depth = 0;
+
+        // Parent space point and the factor that converts distances back into it.
+        let mut domain_stack = [(vec3(0.0, 0.0, 0.0), 0.0f32); MAX_DOMAIN_DEPTH];
+        let mut domain_depth = 0;
+
+        for node in &self.nodes {
+            let kind = node.header[0];
+
+            if kind < OPERATOR_UNION {
+                stack[depth] = self.primitive_signed_distance_function(node, point);
+                material_stack[depth] = NO_MATERIAL;
+                depth += 1;
+            } else if kind <= OPERATOR_SMOOTH_SUBTRACTION {
+                depth -= 1;
+                material_stack[depth - 1] = binary_operator_material(
+                    node,
+                    stack[depth - 1],
+                    stack[depth],
+                    material_stack[depth - 1],
+                    material_stack[depth],
+                );
+                stack[depth - 1] = binary_operator(node, stack[depth - 1], stack[depth]);
+            } else if kind == OPERATOR_MATERIAL {
+                if material_stack[depth - 1] == NO_MATERIAL {
+                    material_stack[depth - 1] = node.header[1];
+                }
+            } else if kind < DOMAIN_TRANSLATE {
+                stack[depth - 1] = modifier_operator(node, stack[depth - 1], point, extrude);
+            } else if kind == DOMAIN_END {
+                domain_depth -= 1;
+                let (parent_point, distance_factor) = domain_stack[domain_depth];
+                point = parent_point;
+                stack[depth - 1] *= distance_factor;
+            } else {
+                let distance_factor = if kind == DOMAIN_SCALE { node.params[0][0] } else { 1.0 };
+                domain_stack[domain_depth] = (point, distance_factor);
+                domain_depth += 1;
+                point = domain_operator(node, point, time);
+            }
+        }
+
+        if depth > 0 { (stack[0], material_stack[0]) } else { (MAX_DISTANCE, NO_MATERIAL) }
+    }
+
+    /// Colour of the pixel at `pos` in an image of `dims`, as written by `main` in the shader.
+    pub fn shade_pixel(&self, pos: [u32; 2], dims: [u32; 2], time: f32, camera: &Camera2D) -> [f32; 3] {
+        let screen_pos = screen_position(pos, dims);
+        let centered_pos = screen_to_scene(screen_pos, camera);
+
+        let (signed_distance, material_id) = self.signed_distance(centered_pos, time);
+
+        let mut triangle_gradient_colour = palette(&self.palettes, vec2(centered_pos[0], centered_pos[1]).magnitude() + time);
+        if material_id != NO_MATERIAL {
+            let material = &self.materials[material_id as usize];
+            let emission = [0, 1, 2].map(|i| clamp(material.emission[i], 0.0, 1.0));
+            let [r, g, b] = (material_albedo(material, vec3(centered_pos[0], centered_pos[1], 0.0)) + Vec3::from(emission)).into();
+            triangle_gradient_colour = [r, g, b];
+        }
+        let frequency = self.parameters.line_frequency;
+        let triangle_distance_length = self.parameters.line_glow / ((signed_distance * frequency + time).sin() / frequency).abs();
+
+        let tint: [f32; 3] = self.parameters.tint.into();
+        [0, 1, 2].map(|i| triangle_gradient_colour[i] * triangle_distance_length * tint[i])
+    }
+
+    /// Renders an RGBA8 image in parallel, rows in the same order as the compute image.
+    pub fn render(&self, dims: [u32; 2], time: f32, camera: &Camera2D) -> Vec<u8> {
+        render_pixels(dims, |pos| self.shade_pixel(pos, dims, time, camera))
+    }
+
+    /// Colour of the pixel at `pos` as written by `main` in the raymarching shader.
+    pub fn shade_raymarched_pixel(&self, pos: [u32; 2], dims: [u32; 2], time: f32, camera: &Camera3D) -> [f32; 3] {
+        let origin = Vec3::from(camera.position);
+        let direction = camera_ray_direction(screen_position(pos, dims), camera);
+
+        let mut colour = sky_colour(direction);
+        if let Some(t) = self.march(origin, direction, 1.0, time) {
+            let fog = 1.0 - (-0.002 * t * t).exp();
+            colour = self.shade(origin + direction * t, direction, time) * (1.0 - fog) + colour * fog;
+        }
+
+        colour = colour.mul_element_wise(self.parameters.tint * self.parameters.exposure);
+        [colour.x, colour.y, colour.z].map(|channel| channel.powf(1.0 / 2.2))
+    }
+
+    /// Same as `render` for the raymarched 3D view.
+    pub fn render_raymarched(&self, dims: [u32; 2], time: f32, camera: &Camera3D) -> Vec<u8> {
+        render_pixels(dims, |pos| self.shade_raymarched_pixel(pos, dims, time, camera))
+    }
+
+    /// Colour of the pixel at `pos` after `samples` path traced samples, summed in the order
+    /// the shader adds them to its accumulation image.
+    pub fn shade_path_traced_pixel(&self, pos: [u32; 2], dims: [u32; 2], time: f32, camera: &Camera3D, samples: u32) -> [f32; 3] {
+        let mut sum = vec3(0.0, 0.0, 0.0);
+        for sample_index in 0..samples {
+            let mut rng_state = pcg_hash(pos[0].wrapping_add(pcg_hash(pos[1].wrapping_add(pcg_hash(sample_index)))));
+
+            let jitter = [random_float(&mut rng_state) - 0.5, random_float(&mut rng_state) - 0.5];
+            let shorter_side = dims[0].min(dims[1]) as f32;
+            let screen_pos = [
+                ((pos[0] as f32 + jitter[0]) * 2.0 - dims[0] as f32) / shorter_side,
+                ((pos[1] as f32 + jitter[1]) * 2.0 - dims[1] as f32) / shorter_side,
+            ];
+            let direction = camera_ray_direction(screen_pos, camera);
+
+            let colour = self.trace_path(Vec3::from(camera.position), direction, time, &mut rng_state);
+            if [colour.x, colour.y, colour.z].iter().all(|channel| channel.is_finite()) {
+                sum += colour;
+            }
+        }
+
+        let average = (sum / samples as f32 * self.parameters.exposure).mul_element_wise(self.parameters.tint);
+        [average.x, average.y, average.z].map(|channel| channel.powf(1.0 / 2.2))
+    }
+
+    /// Same as `render` for the path traced 3D view with `samples` samples per pixel.
+    pub fn render_path_traced(&self, dims: [u32; 2], time: f32, camera: &Camera3D, samples: u32) -> Vec<u8> {
+        render_pixels(dims, |pos| self.shade_path_traced_pixel(pos, dims, time, camera, samples))
+    }
+
+    fn world_signed_distance_function(&self, point: Vec3, time: f32) -> f32 {
+        self.scene_signed_distance_function(point, time, true).min(ground_signed_distance_function(point))
+    }
+
+    /// Distance along the ray to the first surface, if it hits one. A `side` of -1 marches inside solids.
+    fn march(&self, origin: Vec3, direction: Vec3, side: f32, time: f32) -> Option<f32> {
+        let mut t = 0.0f32;
+        for _ in 0..MAX_MARCH_STEPS {
+            let signed_distance = side * self.world_signed_distance_function(origin + direction * t, time);
+            if signed_distance < SURFACE_EPSILON * t.max(1.0) {
+                return Some(t);
+            }
+            t += signed_distance;
+            if t > MAX_RAY_DISTANCE {
+                break;
+            }
+        }
+        None
+    }
+
+    fn surface_normal(&self, point: Vec3, time: f32) -> Vec3 {
+        let e = 0.5773 * 5e-4;
+        let offsets = [vec3(e, -e, -e), vec3(-e, -e, e), vec3(-e, e, -e), vec3(e, e, e)];
+        offsets.iter()
+            .map(|offset| offset * self.world_signed_distance_function(point + offset, time))
+            .fold(vec3(0.0, 0.0, 0.0), |sum, sample| sum + sample)
+            .normalize()
+    }
+
+    fn soft_shadow(&self, origin: Vec3, direction: Vec3, min_t: f32, max_t: f32, time: f32) -> f32 {
+        let mut light = 1.0f32;
+        let mut t = min_t;
+        for _ in 0..SHADOW_STEPS {
+            if t >= max_t {
+                break;
+            }
+            let signed_distance = self.world_signed_distance_function(origin + direction * t, time);
+            if signed_distance < SURFACE_EPSILON {
+                return 0.0;
+            }
+            light = light.min(SHADOW_SOFTNESS * signed_distance / t);
+            t += clamp(signed_distance, 0.01, 0.25);
+        }
+        clamp(light, 0.0, 1.0)
+    }
+
+    fn ambient_occlusion(&self, point: Vec3, normal: Vec3, time: f32) -> f32 {
+        let mut occlusion = 0.0f32;
+        let mut weight = 1.0f32;
+        for i in 0..OCCLUSION_SAMPLES {
+            let h = 0.02 + 0.1 * i as f32;
+            occlusion += (h - self.world_signed_distance_function(point + normal * h, time)) * weight;
+            weight *= 0.8;
+        }
+        clamp(1.0 - 2.0 * occlusion, 0.0, 1.0)
+    }
+
+    fn surface_material(&self, point: Vec3, time: f32) -> SurfaceMaterial {
+        let (scene_distance, material_id) = self.scene_signed_distance_function_with_material(point, time, true);
+        let albedo = if ground_signed_distance_function(point) < scene_distance {
+            let checker = glsl_mod(point.x.floor() + point.z.floor(), 2.0);
+            Vec3::from([0.35 + 0.15 * checker; 3])
+        } else if material_id == NO_MATERIAL {
+            Vec3::from(palette(&self.palettes, vec2(point.x, point.y).magnitude() + time))
+        } else {
+            let material = &self.materials[material_id as usize];
+            let [roughness, metallic, transmission, refractive_index] = material.properties;
+            return SurfaceMaterial {
+                albedo: material_albedo(material, point),
+                emission: vec3(material.emission[0], material.emission[1], material.emission[2]),
+                roughness,
+                metallic,
+                transmission,
+                refractive_index,
+            };
+        };
+
+        SurfaceMaterial {
+            albedo,
+            emission: vec3(0.0, 0.0, 0.0),
+            roughness: DEFAULT_ROUGHNESS,
+            metallic: 0.0,
+            transmission: 0.0,
+            refractive_index: DEFAULT_REFRACTIVE_INDEX,
+        }
+    }
+
+    fn shade(&self, point: Vec3, view_direction: Vec3, time: f32) -> Vec3 {
+        let normal = self.surface_normal(point, time);
+        let light_direction = Vec3::from(SUN_DIRECTION).normalize();
+        let half_vector = (light_direction - view_direction).normalize();
+        let material = self.surface_material(point, time);
+
+        let exponent = specular_exponent(material.roughness);
+        let normalization = (exponent + 8.0) / (specular_exponent(DEFAULT_ROUGHNESS) + 8.0);
+
+        let diffuse = normal.dot(light_direction).max(0.0);
+        let specular = if diffuse > 0.0 { normalization * normal.dot(half_vector).max(0.0).powf(exponent) } else { 0.0 };
+        let shadow = self.soft_shadow(point + normal * 2.0 * SURFACE_EPSILON, light_direction, 0.02, 20.0, time);
+        let occlusion = self.ambient_occlusion(point, normal, time);
+        let sky = 0.5 + 0.5 * normal.y;
+
+        let sun_colour = Vec3::from(SUN_COLOUR);
+        let diffuse_colour = material.albedo * (1.0 - material.metallic);
+        let specular_colour = Vec3::from([SPECULAR_STRENGTH; 3]) * (1.0 - material.metallic) + material.albedo * material.metallic;
+        let mirrored_sky = sky_colour(reflect(view_direction, normal)) * material.metallic * (1.0 - material.roughness);
+        diffuse_colour.mul_element_wise(sun_colour * diffuse * shadow + Vec3::from(SKY_COLOUR) * 0.35 * sky * occlusion)
+            + sun_colour.mul_element_wise(specular_colour) * specular * shadow
+            + material.albedo.mul_element_wise(mirrored_sky) * occlusion
+            + material.emission
+    }
+
+    fn trace_path(&self, mut origin: Vec3, mut direction: Vec3, time: f32, rng_state: &mut u32) -> Vec3 {
+        let mut radiance = vec3(0.0, 0.0, 0.0);
+        let mut throughput = vec3(1.0, 1.0, 1.0);
+        let mut side = 1.0f32;
+
+        for bounce in 0..MAX_BOUNCES {
+            let Some(t) = self.march(origin, direction, side, time) else {
+                radiance += throughput.mul_element_wise(environment_light(direction));
+                break;
+            };
+
+            let point = origin + direction * t;
+            let outward_normal = self.surface_normal(point, time);
+            let front_face = direction.dot(outward_normal) < 0.0;
+            let normal = if front_face { outward_normal } else { -outward_normal };
+            let material = self.surface_material(point, time);
+            radiance += throughput.mul_element_wise(material.emission);
+
+            let cosine = (-direction).dot(normal).min(1.0);
+            let fuzz = random_unit_vector(rng_state) * material.roughness;
+            if random_float(rng_state) < material.transmission {
+                let refraction_ratio = if front_face { 1.0 / material.refractive_index } else { material.refractive_index };
+                let sine = (1.0 - cosine * cosine).sqrt();
+                if refraction_ratio * sine > 1.0 || reflectance(cosine, refraction_ratio) > random_float(rng_state) {
+                    direction = reflect(direction, normal);
+                } else {
+                    direction = refract(direction, normal, refraction_ratio);
+                }
+                throughput.mul_assign_element_wise(material.albedo);
+            } else if random_float(rng_state) < material.metallic {
+                direction = (reflect(direction, normal) + fuzz).normalize();
+                if direction.dot(normal) <= 0.0 {
+                    break;
+                }
+                throughput.mul_assign_element_wise(material.albedo);
+            } else if reflectance(cosine, 1.0 / material.refractive_index) > random_float(rng_state) {
+                direction = (reflect(direction, normal) + fuzz).normalize();
+                if direction.dot(normal) <= 0.0 {
+                    break;
+                }
+            } else {
+                direction = (normal + random_unit_vector(rng_state)).normalize();
+                throughput.mul_assign_element_wise(material.albedo);
+            }
+
+            let exit_side = if direction.dot(normal) > 0.0 { 1.0 } else { -1.0 };
+            origin = point + normal * exit_side * 4.0 * SURFACE_EPSILON;
+            side = if self.world_signed_distance_function(origin, time) < 0.0 { -1.0 } else { 1.0 };
+
+            if bounce >= RUSSIAN_ROULETTE_BOUNCE {
+                let survival = throughput.x.max(throughput.y.max(throughput.z));
+                if random_float(rng_state) >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+        }
+
+        radiance
+    }
+
+    fn shape_signed_distance_function(&self, primitive: &GpuSceneNode, point: Vec2) -> f32 {
+        let [p0, p1] = primitive.params;
+        match primitive.header[0] {
+            PRIMITIVE_EQUILATERAL_TRIANGLE => equalateral_triangle_signed_distance_function(point, p0[0]),
+            PRIMITIVE_CIRCLE => circle_signed_distance_function(point, p0[0]),
+            PRIMITIVE_BOX => box_signed_distance_function(point, vec2(p0[0], p0[1])),
+            PRIMITIVE_ROUNDED_BOX => rounded_box_signed_distance_function(point, vec2(p0[0], p0[1]), p1),
+            PRIMITIVE_SEGMENT => segment_signed_distance_function(point, vec2(p0[0], p0[1]), vec2(p0[2], p0[3]), p1[0]),
+            PRIMITIVE_ARC => arc_signed_distance_function(point, p0[0], p0[1], p0[2]),
+            PRIMITIVE_ELLIPSE => ellipse_signed_distance_function(point, vec2(p0[0], p0[1])),
+            PRIMITIVE_REGULAR_POLYGON => regular_polygon_signed_distance_function(point, p0[0], primitive.header[3]),
+            PRIMITIVE_STAR => star_signed_distance_function(point, p0[0], primitive.header[3], p0[1]),
+            PRIMITIVE_HEXAGON => hexagon_signed_distance_function(point, p0[0]),
+            PRIMITIVE_VESICA => vesica_signed_distance_function(point, p0[0], p0[1]),
+            PRIMITIVE_QUADRATIC_BEZIER => {
+                quadratic_bezier_signed_distance_function(point, vec2(p0[0], p0[1]), vec2(p0[2], p0[3]), vec2(p1[0], p1[1]), p1[2])
+            }
+            PRIMITIVE_POLYGON => {
+                let first = primitive.header[1] as usize;
+                let count = primitive.header[2] as usize;
+                polygon_signed_distance_function(point, &self.vertices[first..first + count])
+            }
+            _ => MAX_DISTANCE,
+        }
+    }
+
+    fn primitive_signed_distance_function(&self, primitive: &GpuSceneNode, point: Vec3) -> f32 {
+        let transform = primitive.transform;
+        let scale = transform[3];
+        if primitive.header[0] >= PRIMITIVE_SPHERE {
+            let translation = vec3(transform[0], transform[1], transform[2]);
+            let local = scale_3d_domain(rotate_3d_domain(translate_3d_domain(point, translation), primitive.rotation), scale);
+            return solid_signed_distance_function(primitive, local) * scale;
+        }
+
+        let local = scale_domain(rotate_domain(translate_domain(vec2(point.x, point.y), vec2(transform[0], transform[1])), transform[2]), scale);
+        self.shape_signed_distance_function(primitive, local) * scale
+    }
+}
+
+fn solid_signed_distance_function(primitive: &GpuSceneNode, point: Vec3) -> f32 {
+    let [p0, p1] = primitive.params;
+    match primitive.header[0] {
+        PRIMITIVE_SPHERE => sphere_signed_distance_function(point, p0[0]),
+        PRIMITIVE_BOX_3D => box_3d_signed_distance_function(point, vec3(p0[0], p0[1], p0[2])),
+        PRIMITIVE_ROUND_BOX_3D => round_box_3d_signed_distance_function(point, vec3(p0[0], p0[1], p0[2]), p0[3]),
+        PRIMITIVE_TORUS => torus_signed_distance_function(point, p0[0], p0[1]),
+        PRIMITIVE_CAPSULE => capsule_signed_distance_function(point, vec3(p0[0], p0[1], p0[2]), vec3(p1[0], p1[1], p1[2]), p1[3]),
+        PRIMITIVE_CYLINDER => cylinder_signed_distance_function(point, p0[0], p0[1]),
+        PRIMITIVE_CONE => cone_signed_distance_function(point, p0[0], p0[1], p0[2]),
+        PRIMITIVE_PLANE => plane_signed_distance_function(point, vec3(p0[0], p0[1], p0[2]), p0[3]),
+        PRIMITIVE_OCTAHEDRON => octahedron_signed_distance_function(point, p0[0]),
+        _ => MAX_DISTANCE,
+    }
+}
+
+fn binary_operator(operator: &GpuSceneNode, a: f32, b: f32) -> f32 {
+    let radius = operator.params[0][0];
+    let blend = operator.header[1];
+
+    match operator.header[0] {
+        OPERATOR_UNION => a.min(b),
+        OPERATOR_INTERSECTION => a.max(b),
+        OPERATOR_SUBTRACTION => a.max(-b),
+        OPERATOR_SMOOTH_UNION => smooth_minimum(a, b, radius, blend),
+        OPERATOR_SMOOTH_INTERSECTION => -smooth_minimum(-a, -b, radius, blend),
+        OPERATOR_SMOOTH_SUBTRACTION => -smooth_minimum(-a, b, radius, blend),
+        _ => a,
+    }
+}
+
+fn binary_operator_material(operator: &GpuSceneNode, a: f32, b: f32, material_a: u32, material_b: u32) -> u32 {
+    match operator.header[0] {
+        OPERATOR_INTERSECTION | OPERATOR_SMOOTH_INTERSECTION => if a > b { material_a } else { material_b },
+        OPERATOR_SUBTRACTION | OPERATOR_SMOOTH_SUBTRACTION => if a > -b { material_a } else { material_b },
+        _ => if a < b { material_a } else { material_b },
+    }
+}
+
+fn modifier_operator(operator: &GpuSceneNode, signed_distance: f32, point: Vec3, extrude: bool) -> f32 {
+    let thickness = operator.params[0][0];
+
+    match operator.header[0] {
+        OPERATOR_ONION => onion_operator(signed_distance, thickness, operator.header[1]),
+        OPERATOR_ROUND => signed_distance - thickness,
+        OPERATOR_ANNULAR => annular_operator(signed_distance, thickness),
+        OPERATOR_EXTRUDE if extrude => extrusion_operator(signed_distance, point.z, thickness),
+        _ => signed_distance,
+    }
+}
+
+fn planar_domain_operator(operator: &GpuSceneNode, point: Vec2, time: f32) -> Vec2 {
+    let p0 = operator.params[0];
+
+    match operator.header[0] {
+        DOMAIN_TRANSLATE => translate_domain(point, vec2(p0[0], p0[1])),
+        DOMAIN_ROTATE => rotate_domain(point, p0[0]),
+        DOMAIN_REPEAT => repeat_domain(point, vec2(p0[0], p0[1])),
+        DOMAIN_REPEAT_LIMITED => repeat_limited_domain(point, vec2(p0[0], p0[1]), vec2(p0[2], p0[3])),
+        DOMAIN_POLAR_REPEAT => polar_repeat_domain(point, operator.header[1]),
+        DOMAIN_MIRROR => mirror_domain(point, vec2(p0[0], p0[1])),
+        DOMAIN_TWIST => twist_domain(point, p0[0]),
+        DOMAIN_WARP => warp_domain(point, p0[0], p0[1], p0[2] * time),
+        _ => point,
+    }
+}
+
+fn domain_operator(operator: &GpuSceneNode, point: Vec3, time: f32) -> Vec3 {
+    match operator.header[0] {
+        DOMAIN_SCALE => scale_3d_domain(point, operator.params[0][0]),
+        DOMAIN_REVOLVE => revolution_domain(point, operator.params[0][0]),
+        _ => {
+            let planar = planar_domain_operator(operator, vec2(point.x, point.y), time);
+            vec3(planar.x, planar.y, point.z)
+        }
+    }
+}
+
+/// Shades every pixel of an RGBA8 image, rows in parallel.
+fn render_pixels(dims: [u32; 2], shade: impl Fn([u32; 2]) -> [f32; 3] + Sync) -> Vec<u8> {
+    let mut pixels = vec![0u8; dims[0] as usize * dims[1] as usize * 4];
+    pixels
+        .par_chunks_exact_mut(dims[0] as usize * 4)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let colour = shade([x as u32, y as u32]);
+                pixel.copy_from_slice(&[unorm8(colour[0]), unorm8(colour[1]), unorm8(colour[2]), 255]);
+            }
+        });
+    pixels
+}
+
+/// Float to UNORM8 the way `imageStore` converts it.
+fn unorm8(value: f32) -> u8 {
+    (clamp(value, 0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_scene_renders_background_in_every_mode() {
+        let scene = Scene { nodes: Vec::new(), ..Scene::default() };
+        let evaluator = CpuSceneEvaluator::new(&scene);
+        let size = [4, 3];
+        let pixel_bytes = (size[0] * size[1] * 4) as usize;
+        assert_eq!(evaluator.render(size, 0.0, &Camera2D::default()).len(), pixel_bytes);
+        assert_eq!(evaluator.render_raymarched(size, 0.0, &Camera3D::default()).len(), pixel_bytes);
+        assert_eq!(evaluator.render_path_traced(size, 0.0, &Camera3D::default(), 1).len(), pixel_bytes);
+    }
+}
@@ -0,0 +1,280 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use clap::ValueEnum;
+
+/// Placeholder replaced by the frame number in numbered PNG paths.
+const FRAME_PLACEHOLDER: &str = "{frame}";
+
+/// Slowest frame rate, Y4M headers keep three decimals so anything slower would round to 0.
+pub const MIN_FRAME_RATE: f64 = 0.001;
+
+/// How rendered frames are written.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum FrameFormat {
+    /// One PNG file per frame.
+    Png,
+    /// Raw RGBA8 frames back to back, without any header.
+    Rgba,
+    /// YUV4MPEG2 stream with 4:4:4 chroma, readable by most encoders.
+    Y4m,
+}
+
+/// Writes rendered frames as PNG files or as a raw video stream to a file or stdout.
+pub struct FrameWriter {
+    format: FrameFormat,
+    output: PathBuf,
+    size: [u32; 2],
+    frame_count: u32,
+    stream: Option<Box<dyn Write>>,
+}
+
+impl FrameWriter {
+    /// `output` is `-` for stdout. PNG sequences replace `{frame}` in the path with the
+    /// frame number, or append it before the extension if there is no placeholder. A single
+    /// frame without a placeholder is written to `output` as is.
+    pub fn new(
+        format: FrameFormat,
+        output: &Path,
+        size: [u32; 2],
+        frame_rate: f64,
+        frame_count: u32,
+    ) -> Result<FrameWriter, String> {
+        let to_stdout = output == Path::new("-");
+        let stream: Option<Box<dyn Write>> = match format {
+            FrameFormat::Png if to_stdout => {
+                return Err("PNG frames are written to files, use `--format rgba` or `--format y4m` for stdout".to_string());
+            }
+            FrameFormat::Png => None,
+            FrameFormat::Rgba | FrameFormat::Y4m if to_stdout => Some(Box::new(BufWriter::new(io::stdout().lock()))),
+            FrameFormat::Rgba | FrameFormat::Y4m => {
+                let file = File::create(output).map_err(|e| format!("{}: {e}", output.display()))?;
+                Some(Box::new(BufWriter::new(file)))
+            }
+        };
+
+        let mut writer = FrameWriter {
+            format,
+            output: output.to_path_buf(),
+            size,
+            frame_count,
+            stream,
+        };
+
+        if format == FrameFormat::Y4m {
+            let (numerator, denominator) = frame_rate_ratio(frame_rate);
+            let header = format!("YUV4MPEG2 W{} H{} F{numerator}:{denominator} Ip A1:1 C444\n", size[0], size[1]);
+            writer.write_stream(header.as_bytes())?;
+        }
+
+        Ok(writer)
+    }
+
+    /// Writes one frame of RGBA8 pixels in image row order, row 0 at the bottom.
+    pub fn write_frame(
+        &mut self,
+        index: u32,
+        pixels: &[u8],
+    ) -> Result<(), String> {
+        let pixels = flip_rows(pixels, self.size[0]);
+        match self.format {
+            FrameFormat::Png => write_png(&self.frame_path(index), self.size, &pixels),
+            FrameFormat::Rgba => self.write_stream(&pixels),
+            FrameFormat::Y4m => {
+                self.write_stream(b"FRAME\n")?;
+                self.write_stream(&rgba_to_yuv444_planes(&pixels))
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        match self.stream.as_mut() {
+            Some(stream) => stream.flush().map_err(|e| self.stream_error(e)),
+            None => Ok(()),
+        }
+    }
+
+    fn frame_path(&self, index: u32) -> PathBuf {
+        let digits = (self.frame_count.max(1) - 1).to_string().len().max(4);
+        let number = format!("{index:0digits$}");
+        let path = self.output.to_string_lossy();
+        if path.contains(FRAME_PLACEHOLDER) {
+            return PathBuf::from(path.replace(FRAME_PLACEHOLDER, &number));
+        }
+        if self.frame_count == 1 {
+            return self.output.clone();
+        }
+
+        let stem = self.output.file_stem().unwrap_or_default().to_string_lossy();
+        let file_name = match self.output.extension() {
+            Some(extension) => format!("{stem}_{number}.{}", extension.to_string_lossy()),
+            None => format!("{stem}_{number}"),
+        };
+        self.output.with_file_name(file_name)
+    }
+
+    fn write_stream(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let result = self.stream.as_mut().unwrap().write_all(bytes);
+        result.map_err(|e| self.stream_error(e))
+    }
+
+    fn stream_error(&self, error: io::Error) -> String {
+        format!("{}: {error}", self.output.display())
+    }
+}
+
+/// Writes RGBA8 pixels, top row first, to a PNG file.
+pub fn write_png(
+    path: &Path,
+    size: [u32; 2],
+    pixels: &[u8],
+) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), size[0], size[1]);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder.write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// The compute image stores the bottom row first, files store the top row first.
+fn flip_rows(
+    pixels: &[u8],
+    width: u32,
+) -> Vec<u8> {
+    pixels
+        .chunks_exact(width as usize * 4)
+        .rev()
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Y4M wants the frame rate as a ratio, fractional rates such as 29.97 keep three decimals.
+fn frame_rate_ratio(frame_rate: f64) -> (u64, u64) {
+    let mut numerator = (frame_rate * 1000.0).round() as u64;
+    let mut denominator = 1000;
+    let divisor = greatest_common_divisor(numerator, denominator);
+    numerator /= divisor;
+    denominator /= divisor;
+    (numerator, denominator)
+}
+
+fn greatest_common_divisor(a: u64, b: u64) -> u64 {
+    if b == 0 { a.max(1) } else { greatest_common_divisor(b, a % b) }
+}
+
+/// Converts to limited range BT.601 Y, Cb and Cr planes, the Y4M default.
+fn rgba_to_yuv444_planes(pixels: &[u8]) -> Vec<u8> {
+    let pixel_count = pixels.len() / 4;
+    let mut planes = vec![0u8; pixel_count * 3];
+    for (index, pixel) in pixels.chunks_exact(4).enumerate() {
+        let [r, g, b] = [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32];
+        let y = 16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0;
+        let cb = 128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0;
+        let cr = 128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0;
+        planes[index] = y.round() as u8;
+        planes[pixel_count + index] = cb.round() as u8;
+        planes[pixel_count * 2 + index] = cr.round() as u8;
+    }
+    planes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::test_support::TestDirectory;
+
+    /// Red and blue on the bottom row, white and black on the top row, bottom row first.
+    const PIXELS: [u8; 16] = [
+        255, 0, 0, 255, 0, 0, 255, 255,
+        255, 255, 255, 255, 0, 0, 0, 255,
+    ];
+
+    /// The same pixels top row first, as files store them.
+    const FLIPPED: [u8; 16] = [
+        255, 255, 255, 255, 0, 0, 0, 255,
+        255, 0, 0, 255, 0, 0, 255, 255,
+    ];
+
+    #[test]
+    fn y4m_has_header_and_limited_range_planes() {
+        let directory = TestDirectory::new();
+        let path = directory.path().join("frames.y4m");
+        let mut writer = FrameWriter::new(FrameFormat::Y4m, &path, [2, 2], 29.97, 2).unwrap();
+        writer.write_frame(0, &PIXELS).unwrap();
+        writer.write_frame(1, &PIXELS).unwrap();
+        writer.finish().unwrap();
+
+        let frame: Vec<u8> = [
+            b"FRAME\n".as_slice(),
+            // Y: white, black, red, blue. Then Cb and Cr in the same order.
+            &[235, 16, 81, 41],
+            &[128, 128, 90, 240],
+            &[128, 128, 240, 110],
+        ].concat();
+        let expected = [b"YUV4MPEG2 W2 H2 F2997:100 Ip A1:1 C444\n".as_slice(), &frame, &frame].concat();
+        assert_eq!(fs::read(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn rgba_frames_are_flipped_and_back_to_back() {
+        let directory = TestDirectory::new();
+        let path = directory.path().join("frames.rgba");
+        let mut writer = FrameWriter::new(FrameFormat::Rgba, &path, [2, 2], 30.0, 2).unwrap();
+        writer.write_frame(0, &PIXELS).unwrap();
+        writer.write_frame(1, &FLIPPED).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), [FLIPPED, PIXELS].concat());
+    }
+
+    #[test]
+    fn png_frames_are_flipped() {
+        let directory = TestDirectory::new();
+        let mut writer = FrameWriter::new(FrameFormat::Png, &directory.path().join("frame_{frame}.png"), [2, 2], 30.0, 2).unwrap();
+        writer.write_frame(1, &PIXELS).unwrap();
+        writer.finish().unwrap();
+
+        let decoder = png::Decoder::new(File::open(directory.path().join("frame_0001.png")).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!([info.width, info.height], [2, 2]);
+        assert_eq!(pixels, FLIPPED);
+    }
+
+    #[test]
+    fn frame_paths_are_numbered() {
+        let path = |output: &str, frame_count, index| {
+            let writer = FrameWriter::new(FrameFormat::Png, Path::new(output), [2, 2], 30.0, frame_count).unwrap();
+            writer.frame_path(index)
+        };
+
+        assert_eq!(path("out/frame_{frame}.png", 3, 2), Path::new("out/frame_0002.png"));
+        assert_eq!(path("out/{frame}/{frame}.png", 3, 1), Path::new("out/0001/0001.png"));
+        assert_eq!(path("out/frame.png", 3, 2), Path::new("out/frame_0002.png"));
+        assert_eq!(path("out/frame", 3, 2), Path::new("out/frame_0002"));
+        assert_eq!(path("out/frame_{frame}.png", 12000, 7), Path::new("out/frame_00007.png"));
+
+        // A single frame is written exactly where asked, only the placeholder is replaced.
+        assert_eq!(path("out/frame.png", 1, 0), Path::new("out/frame.png"));
+        assert_eq!(path("out/frame_{frame}.png", 1, 0), Path::new("out/frame_0000.png"));
+    }
+
+    #[test]
+    fn frame_rates_become_ratios() {
+        assert_eq!(frame_rate_ratio(30.0), (30, 1));
+        assert_eq!(frame_rate_ratio(29.97), (2997, 100));
+        assert_eq!(frame_rate_ratio(0.8), (4, 5));
+        assert_eq!(frame_rate_ratio(MIN_FRAME_RATE), (1, 1000));
+    }
+
+    #[test]
+    fn png_is_not_written_to_stdout() {
+        assert!(FrameWriter::new(FrameFormat::Png, Path::new("-"), [2, 2], 30.0, 1).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+use vulkano::sync::{self, GpuFuture};
+use crate::application::Application;
+use crate::camera::{Camera2D, Camera3D};
+use crate::cpu_evaluator::CpuSceneEvaluator;
+use crate::frame_export::{FrameFormat, FrameWriter};
+use crate::palette::GpuPalettes;
+use crate::scene::Scene;
+use crate::shader_parameters::ParameterStore;
+use crate::shader_reload::CompiledComputeShader;
+use crate::signed_distance_function_renderer::{RenderMode, SimpleVulkanRendererComputePipeline};
+
+/// What to render without a window and where to write it.
+pub struct HeadlessOptions {
+    pub size: [u32; 2],
+    pub frame_count: u32,
+    pub frame_rate: f64,
+    pub format: FrameFormat,
+    pub output: PathBuf,
+    pub mode: RenderMode,
+    /// Palette of the shapes without a material.
+    pub palette: GpuPalettes,
+    /// Values of the annotated shader uniforms.
+    pub parameters: ParameterStore,
+    /// Path traced samples per pixel of every frame.
+    pub samples: u32,
+    pub workgroup_size: [u32; 2],
+}
+
+/// Renders frames with only the compute pipeline, no window or swapchain. Frame time
+/// comes from the frame index so the output is the same on every run, and the timeline
+/// of the scene is applied at that time before each frame.
+pub fn render_headless(
+    scene: &Scene,
+    shader: Option<&CompiledComputeShader>,
+    options: &HeadlessOptions,
+) -> Result<(), String> {
+    let app = Application::headless().map_err(|e| format!("{e}, pass `--cpu` to render without Vulkan"))?;
+    let compute_queue = app.context.compute_queue().clone();
+    let mut compute = SimpleVulkanRendererComputePipeline::new(&app, compute_queue, options.size, scene);
+    compute.set_fixed_frame_rate(options.frame_rate);
+    compute.set_render_mode(options.mode);
+    compute.set_palettes(options.palette);
+    compute.set_parameter_store(options.parameters.clone());
+    compute.set_sample_target(options.samples);
+    compute.set_samples_per_frame(options.samples);
+    compute.set_workgroup_size(options.workgroup_size)?;
+    if let Some(shader) = shader {
+        compute.apply_compiled_shader(shader)?;
+    }
+
+    let mut writer = FrameWriter::new(
+        options.format,
+        &options.output,
+        options.size,
+        options.frame_rate,
+        options.frame_count,
+    )?;
+
+    for index in 0..options.frame_count {
+        let time = frame_time(index, options.frame_rate);
+        if let Some(animated) = animate_scene(scene, time)? {
+            compute.set_scene(&animated);
+        }
+        for (name, value) in scene.timeline.shader_values(time) {
+            compute.set_parameter(name, &value);
+        }
+
+        // Every frame is path traced from scratch at its own time.
+        compute.reset_accumulation();
+        let before_future = sync::now(app.context.device().clone()).boxed();
+        let after_compute = compute.compute(before_future);
+        let pixels = compute.download_color_image(after_compute);
+        writer.write_frame(index, &pixels)?;
+    }
+
+    writer.finish()
+}
+
+/// Same as `render_headless` for the scene shader, but evaluated on the CPU so it runs
+/// on machines without a Vulkan device.
+pub fn render_headless_cpu(
+    scene: &Scene,
+    options: &HeadlessOptions,
+) -> Result<(), String> {
+    let mut evaluator = CpuSceneEvaluator::new(scene);
+    evaluator.set_palettes(options.palette);
+    let mut parameters = options.parameters.clone();
+    let mut writer = FrameWriter::new(
+        options.format,
+        &options.output,
+        options.size,
+        options.frame_rate,
+        options.frame_count,
+    )?;
+
+    for index in 0..options.frame_count {
+        let time = frame_time(index, options.frame_rate);
+        if let Some(animated) = animate_scene(scene, time)? {
+            evaluator = CpuSceneEvaluator::new(&animated);
+            evaluator.set_palettes(options.palette);
+        }
+        for (name, value) in scene.timeline.shader_values(time) {
+            parameters.set(name, &value);
+        }
+        evaluator.set_parameters(&parameters);
+
+        let pixels = match options.mode {
+            RenderMode::Flat => evaluator.render(options.size, time, &Camera2D::default()),
+            RenderMode::Raymarch => evaluator.render_raymarched(options.size, time, &Camera3D::default()),
+            RenderMode::PathTrace => {
+                evaluator.render_path_traced(options.size, time, &Camera3D::default(), options.samples)
+            }
+        };
+        writer.write_frame(index, &pixels)?;
+    }
+
+    writer.finish()
+}
+
+fn frame_time(index: u32, frame_rate: f64) -> f32 {
+    (index as f64 / frame_rate) as f32
+}
+
+/// The scene with its timeline applied at `time`, or `None` when nothing in it is animated.
+fn animate_scene(scene: &Scene, time: f32) -> Result<Option<Scene>, String> {
+    scene.timeline.animate_scene(scene, time).map_err(|e| format!("scene at {time} s: {e}"))
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use winit::event::VirtualKeyCode;
+
+/// Something the user can do with the keyboard, bound to keys in a bindings file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    ResetView,
+    RotateViewLeft,
+    RotateViewRight,
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    ToggleRenderMode,
+    NextPalette,
+    PreviousPalette,
+    TogglePanel,
+    TogglePlayback,
+}
+
+/// A key by its layout dependent virtual keycode, or by its physical scancode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyBinding {
+    Key(VirtualKeyCode),
+    Scancode(u32),
+}
+
+/// Keys bound to every action.
+#[derive(Clone, Debug)]
+pub struct InputBindings {
+    bindings: HashMap<Action, Vec<KeyBinding>>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        InputBindings {
+            bindings: HashMap::from([
+                (Action::ResetView, vec![KeyBinding::Key(VirtualKeyCode::R), KeyBinding::Key(VirtualKeyCode::Home)]),
+                (Action::RotateViewLeft, vec![KeyBinding::Key(VirtualKeyCode::Q)]),
+                (Action::RotateViewRight, vec![KeyBinding::Key(VirtualKeyCode::E)]),
+                (Action::MoveForward, vec![KeyBinding::Key(VirtualKeyCode::W)]),
+                (Action::MoveBackward, vec![KeyBinding::Key(VirtualKeyCode::S)]),
+                (Action::MoveLeft, vec![KeyBinding::Key(VirtualKeyCode::A)]),
+                (Action::MoveRight, vec![KeyBinding::Key(VirtualKeyCode::D)]),
+                (Action::MoveUp, vec![KeyBinding::Key(VirtualKeyCode::Space)]),
+                (Action::MoveDown, vec![KeyBinding::Key(VirtualKeyCode::LShift)]),
+                (Action::ToggleRenderMode, vec![KeyBinding::Key(VirtualKeyCode::Tab)]),
+                (Action::NextPalette, vec![KeyBinding::Key(VirtualKeyCode::RBracket)]),
+                (Action::PreviousPalette, vec![KeyBinding::Key(VirtualKeyCode::LBracket)]),
+                (Action::TogglePanel, vec![KeyBinding::Key(VirtualKeyCode::F1)]),
+                (Action::TogglePlayback, vec![KeyBinding::Key(VirtualKeyCode::P)]),
+            ]),
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn keys(&self, action: Action) -> &[KeyBinding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// Bindings file layout. Actions that are left out keep their default keys, an empty list unbinds.
+#[derive(Deserialize)]
+struct BindingsFile {
+    bindings: HashMap<Action, Vec<KeyBinding>>,
+}
+
+#[derive(Debug)]
+pub enum BindingsFileError {
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, message: String },
+}
+
+impl Display for BindingsFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindingsFileError::Io { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            BindingsFileError::Parse { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BindingsFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BindingsFileError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a `.ron` bindings file on top of the default bindings.
+pub fn load_bindings(path: &Path) -> Result<InputBindings, BindingsFileError> {
+    let contents = fs::read_to_string(path).map_err(|source| BindingsFileError::Io { path: path.to_path_buf(), source })?;
+    let file: BindingsFile = ron::from_str(&contents)
+        .map_err(|e| BindingsFileError::Parse { path: path.to_path_buf(), message: e.to_string() })?;
+
+    let mut bindings = InputBindings::default();
+    bindings.bindings.extend(file.bindings);
+    Ok(bindings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDirectory;
+
+    #[test]
+    fn unmentioned_actions_keep_their_defaults() {
+        let directory = TestDirectory::new();
+        let path = directory.file("partial.ron", "(bindings: { ResetView: [Key(Back)] })");
+        let bindings = load_bindings(&path).unwrap();
+        let defaults = InputBindings::default();
+
+        assert_eq!(bindings.keys(Action::ResetView), [KeyBinding::Key(VirtualKeyCode::Back)]);
+        assert_eq!(bindings.keys(Action::TogglePanel), defaults.keys(Action::TogglePanel));
+        assert_eq!(bindings.keys(Action::MoveForward), defaults.keys(Action::MoveForward));
+    }
+
+    #[test]
+    fn empty_list_unbinds() {
+        let directory = TestDirectory::new();
+        let path = directory.file("unbind.ron", "(bindings: { ToggleRenderMode: [] })");
+        assert!(load_bindings(&path).unwrap().keys(Action::ToggleRenderMode).is_empty());
+    }
+
+    #[test]
+    fn scancodes_parse() {
+        let directory = TestDirectory::new();
+        let path = directory.file("scancodes.ron", "(bindings: { MoveForward: [Scancode(17), Key(Up)] })");
+        assert_eq!(
+            load_bindings(&path).unwrap().keys(Action::MoveForward),
+            [KeyBinding::Scancode(17), KeyBinding::Key(VirtualKeyCode::Up)],
+        );
+    }
+
+    #[test]
+    fn unknown_action_is_a_parse_error() {
+        let directory = TestDirectory::new();
+        let path = directory.file("unknown.ron", "(bindings: { Jump: [Key(Space)] })");
+        let error = load_bindings(&path).unwrap_err();
+        assert!(matches!(error, BindingsFileError::Parse { .. }), "{error}");
+        assert!(error.to_string().contains("Jump"), "{error}");
+    }
+
+    #[test]
+    fn shipped_bindings_match_the_defaults() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("config/bindings.ron");
+        let bindings = load_bindings(&path).unwrap();
+        let file: BindingsFile = ron::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let defaults = InputBindings::default();
+        assert_eq!(file.bindings.len(), defaults.bindings.len(), "config/bindings.ron misses an action");
+        for action in defaults.bindings.keys() {
+            assert_eq!(bindings.keys(*action), defaults.keys(*action), "{action:?}");
+        }
+    }
+}
@@ -1,33 +1,195 @@
 mod application;
+mod arguments;
+mod camera;
+mod cpu_evaluator;
+mod frame_export;
+mod headless;
+mod input_bindings;
+mod palette;
+mod parameter_panel;
 mod render_pass;
+mod render_pass_gui;
 mod user_io;
 mod signed_distance_function_renderer;
+mod scene;
+mod scene_file;
+mod shader_parameters;
+mod shader_reload;
+mod shadertoy_import;
+mod text_overlay;
+mod timeline;
+#[cfg(test)]
+mod test_support;
 
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
 use std::time::Instant;
-use vulkano::image::ImageAccess;
+use clap::Parser;
 use vulkano_util::window::WindowDescriptor;
 use winit::event::{DeviceEvent, Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::platform::run_return::EventLoopExtRunReturn;
 use crate::application::{Application};
+use crate::arguments::Arguments;
+use crate::headless::{render_headless, render_headless_cpu, HeadlessOptions};
+use crate::input_bindings::{load_bindings, Action, InputBindings};
+use crate::palette::{load_palettes, GpuPalettes, PaletteLibrary, PaletteSelection};
+use crate::scene::Scene;
+use crate::scene_file::{load_scene, save_scene};
+use crate::signed_distance_function_renderer::RenderMode;
+use crate::shader_parameters::{load_parameters, save_parameters, ParameterStore};
+use crate::shader_reload::{built_in_scene_shaders, CompiledComputeShader, ComputeShaderSource, ShaderWatcher, SHADER_DIRECTORY};
 use crate::user_io::UserIO;
 
 fn main() {
+    let arguments = Arguments::parse();
+
+    // Load the scene before creating any window so bad files fail fast.
+    let scene = match &arguments.scene {
+        Some(path) => load_scene(path).unwrap_or_else(|e| {
+            eprintln!("Failed to load scene: {e}");
+            process::exit(1);
+        }),
+        None => Scene::default(),
+    };
+
+    let bindings = match &arguments.bindings {
+        Some(path) => load_bindings(path).unwrap_or_else(|e| {
+            eprintln!("Failed to load key bindings: {e}");
+            process::exit(1);
+        }),
+        None => InputBindings::default(),
+    };
+
+    let palettes = match &arguments.palettes {
+        Some(path) => load_palettes(path).unwrap_or_else(|e| {
+            eprintln!("Failed to load palettes: {e}");
+            process::exit(1);
+        }),
+        None => PaletteLibrary::default(),
+    };
+    let palette_index = match &arguments.palette {
+        Some(name) => palettes.index_of(name).unwrap_or_else(|| {
+            let names = palettes.names().collect::<Vec<_>>().join(", ");
+            eprintln!("Unknown palette `{name}`, expected one of: {names}");
+            process::exit(1);
+        }),
+        None => 0,
+    };
+
+    let parameters = match &arguments.parameters {
+        Some(path) => load_parameters(path).unwrap_or_else(|e| {
+            eprintln!("Failed to load shader parameters: {e}");
+            process::exit(1);
+        }),
+        None => ParameterStore::default(),
+    };
+
+    if let Some(path) = &arguments.save_scene {
+        if let Err(e) = save_scene(path, &scene) {
+            eprintln!("Failed to save scene: {e}");
+            process::exit(1);
+        }
+    }
+
+    let shader_source = match &arguments.shadertoy {
+        Some(path) => ComputeShaderSource::shadertoy(path),
+        None => ComputeShaderSource::Scene,
+    };
+
+    if let Some(path) = &arguments.save_parameters {
+        if let Err(e) = save_shader_parameters(path, &shader_source, &parameters) {
+            eprintln!("Failed to save shader parameters: {e}");
+            process::exit(1);
+        }
+    }
+
+    if let Some(output) = &arguments.output {
+        // The scene shader is built in, only pasted Shadertoy sources need compiling.
+        let compiled = arguments.shadertoy.as_ref().map(|_| {
+            compile_shaders(&shader_source).unwrap_or_else(|e| {
+                eprintln!("Failed to compile shader:\n{e}");
+                process::exit(1);
+            })
+        });
+
+        let options = HeadlessOptions {
+            size: [arguments.width, arguments.height],
+            frame_count: arguments.frames,
+            frame_rate: arguments.fps,
+            format: arguments.format,
+            output: output.clone(),
+            mode: arguments.mode,
+            palette: GpuPalettes::single(palettes.preset(palette_index).palette),
+            parameters,
+            samples: arguments.samples,
+            workgroup_size: arguments.workgroup_size,
+        };
+        let result = if arguments.cpu {
+            render_headless_cpu(&scene, &options)
+        } else {
+            render_headless(&scene, compiled.as_ref(), &options)
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to render: {e}");
+            process::exit(1);
+        }
+        return;
+    }
+
     let mut event_loop = EventLoop::new();
     let mut app = Application::default();
 
     let main_window_descriptor = WindowDescriptor {
-        width: 1024.0,
-        height: 1024.0,
+        width: arguments.width as f32,
+        height: arguments.height as f32,
         title: "Triangle".to_string(),
         ..Default::default()
     };
 
-    app.open_new_window(&event_loop, main_window_descriptor);
+    app.open_new_window(&event_loop, main_window_descriptor, &scene);
+    let palettes = Arc::new(palettes);
+    for pipeline in app.pipelines.values_mut() {
+        pipeline.palette = PaletteSelection::new(palettes.clone(), palette_index);
+        pipeline.compute.set_parameter_store(parameters.clone());
+        pipeline.compute.set_render_mode(arguments.mode);
+        pipeline.compute.set_sample_target(arguments.samples);
+        pipeline.compute.set_render_scale(arguments.render_scale);
+        if let Err(e) = pipeline.compute.set_workgroup_size(arguments.workgroup_size) {
+            eprintln!("Failed to set the workgroup size: {e}");
+            process::exit(1);
+        }
+    }
+
+    // A pasted Shadertoy shader or export replaces the built in scene shader right away.
+    if arguments.shadertoy.is_some() {
+        reload_shaders(&mut app, &shader_source);
+    }
+
+    // Live shader editing, the window keeps running the current shader until a file changes.
+    let shader_watcher = if arguments.hot_reload {
+        let watcher = ShaderWatcher::new(Path::new(SHADER_DIRECTORY)).and_then(|mut watcher| {
+            if let Some(path) = shader_source.external_file() {
+                watcher.watch_file(path)?;
+            }
+            Ok(watcher)
+        });
+
+        match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                eprintln!("Failed to watch shaders: {e}");
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
 
     // Time & inputs...
     let mut time = Instant::now();
-    let mut user_input = UserIO::new();
+    let mut user_input = UserIO::new(bindings);
     loop {
 
         // Window event handling.
@@ -35,8 +197,15 @@ fn main() {
             break;
         }
 
+        if let Some(watcher) = &shader_watcher {
+            if watcher.poll_changed() {
+                reload_shaders(&mut app, &shader_source);
+            }
+        }
+
         // Compute life & render 60fps.
-        if (Instant::now() - time).as_secs_f64() > 1.0 / 60.0 {
+        let delta_time = (Instant::now() - time).as_secs_f64();
+        if delta_time > 1.0 / 60.0 {
             for (window_id, window_renderer) in app.windows.iter_mut() {
                 let pipeline = app.pipelines.get_mut(window_id).unwrap();
 
@@ -58,18 +227,98 @@ fn main() {
                     Ok(future) => future,
                 };
 
+                // Follows the swapchain, which was recreated on acquire if the window resized.
+                pipeline.compute.resize(window_renderer.swapchain_image_size());
+                if user_input.is_action_pressed(Action::TogglePanel) {
+                    pipeline.panel.toggle();
+                }
+                pipeline.update_panel(window_renderer.window());
+                if user_input.is_action_pressed(Action::TogglePlayback) {
+                    pipeline.playback.toggle();
+                }
+                pipeline.update_timeline(delta_time);
+                let mouse = user_input.mouse(*window_id);
+                if user_input.is_action_pressed(Action::ToggleRenderMode) {
+                    pipeline.compute.set_render_mode(pipeline.compute.render_mode().next());
+                }
+                match pipeline.compute.render_mode() {
+                    RenderMode::Flat => {
+                        pipeline.camera.update(&user_input, &mouse, window_renderer.window_size(), delta_time as f32);
+                        pipeline.compute.set_camera(pipeline.camera);
+                    }
+                    RenderMode::Raymarch | RenderMode::PathTrace => {
+                        pipeline.camera_3d.update(&user_input, &mouse, delta_time as f32);
+                        pipeline.compute.set_camera_3d(pipeline.camera_3d);
+                    }
+                }
+                pipeline.palette.update(&user_input, delta_time as f32);
+                pipeline.compute.set_palettes(pipeline.palette.encode());
+                let status = pipeline.timeline_error().map(|e| format!("Timeline: {e}")).or_else(|| {
+                    pipeline.compute.path_trace_progress().map(|[samples, target]| format!("{samples} / {target} samples"))
+                });
+                pipeline.place_over_frame.set_status_text(status.as_deref());
+                pipeline.compute.set_mouse(mouse.shadertoy_mouse(window_renderer.window_size()[1] as f64), mouse.shader_buttons());
                 let after_compute = pipeline.compute.compute(before_pipeline_future);
                 let color_image = pipeline.compute.color_image();
                 let target_image = window_renderer.swapchain_image_view();
 
-                let after_render = pipeline.place_over_frame.render(after_compute, color_image, target_image);
-                window_renderer.present(after_render, true);
+                let after_render = pipeline.place_over_frame.render(after_compute, color_image, target_image.clone());
+                let after_panel = pipeline.panel.render(after_render, target_image);
+                window_renderer.present(after_panel, true);
             }
+            user_input.end_frame();
             time = Instant::now();
         }
     }
 }
 
+/// Compiles the shader source and prints the warnings of imported Shadertoy shaders.
+fn compile_shaders(shader_source: &ComputeShaderSource) -> Result<CompiledComputeShader, String> {
+    let compiled = shader_source.compile(Path::new(SHADER_DIRECTORY))?;
+    if let CompiledComputeShader::Shadertoy { project, .. } = &compiled {
+        for warning in &project.warnings {
+            eprintln!("Warning: {}: {warning}", project.name);
+        }
+    }
+
+    Ok(compiled)
+}
+
+/// Writes the annotated parameters of the shader source with their values in `store`, or
+/// their defaults. The scene shaders are the built in ones, other sources are compiled.
+fn save_shader_parameters(path: &Path, shader_source: &ComputeShaderSource, store: &ParameterStore) -> Result<(), String> {
+    let compiled;
+    let shader = match shader_source {
+        ComputeShaderSource::Scene => built_in_scene_shaders(),
+        _ => {
+            compiled = compile_shaders(shader_source)?;
+            &compiled
+        }
+    };
+
+    save_parameters(path, shader.parameters(), store).map_err(|e| e.to_string())
+}
+
+/// Recompiles the compute shader and swaps it into every window. Errors are printed
+/// and shown over the frame while the previous shader keeps running.
+fn reload_shaders(app: &mut Application, shader_source: &ComputeShaderSource) {
+    let compiled = compile_shaders(shader_source);
+    for pipeline in app.pipelines.values_mut() {
+        let result = match &compiled {
+            Ok(compiled) => pipeline.compute.apply_compiled_shader(compiled),
+            Err(e) => Err(e.clone()),
+        };
+
+        match result {
+            Ok(()) => pipeline.place_over_frame.set_overlay_text(None),
+            Err(e) => {
+                eprintln!("Shader reload failed:\n{e}");
+                pipeline.place_over_frame.set_overlay_text(Some(&e));
+            }
+        }
+    }
+}
+
 /// Handles events and returns a `bool` indicating if we should quit.
 fn handle_events(
     event_loop: &mut EventLoop<()>,
@@ -81,16 +330,13 @@ fn handle_events(
         *control_flow = ControlFlow::Poll;
 
         match &window_event {
-            Event::DeviceEvent { event, .. } => {
-                match event {
-                    DeviceEvent::MouseMotion { delta } => {
-                        user_input.set_mouse_delta(delta.0, delta.1);
-                    }
-                    _ => {}
-                }
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. } => {
+                user_input.add_mouse_motion_delta(delta.0, delta.1);
             },
 
             Event::WindowEvent { event, window_id, .. } => {
+                // The panel sees input first, what it takes never reaches the cameras.
+                let taken = app.pipelines.get_mut(window_id).is_some_and(|pipeline| pipeline.panel.on_window_event(event));
                 match event {
                     WindowEvent::CloseRequested => {
                         if *window_id == app.windows.primary_window_id().unwrap() {
@@ -100,6 +346,7 @@ fn handle_events(
                             // Destroy window by removing its renderer.
                             app.windows.remove_renderer(*window_id);
                             app.pipelines.remove(window_id);
+                            user_input.remove_window(*window_id);
                         }
                     }
 
@@ -109,12 +356,30 @@ fn handle_events(
                         vulkano_window.resize();
                     }
 
-                    WindowEvent::KeyboardInput { input, .. } => {
+                    WindowEvent::KeyboardInput { input, .. } if !taken => {
                         user_input.set_keyboard_input(input);
                     },
 
+                    WindowEvent::Focused(false) => {
+                        user_input.release_all_keys();
+                    }
+
+                    WindowEvent::CursorMoved { position, .. } if !taken => {
+                        user_input.set_cursor_position(*window_id, position.x, position.y);
+                    }
+
+                    WindowEvent::CursorMoved { .. } | WindowEvent::CursorLeft { .. } => {
+                        user_input.forget_cursor_position(*window_id);
+                    }
+
+                    WindowEvent::MouseWheel { delta, .. } if !taken => {
+                        user_input.add_mouse_wheel_delta(*window_id, delta);
+                    }
+
                     // Handle mouse button events.
-                    WindowEvent::MouseInput { .. } => {}
+                    WindowEvent::MouseInput { state, button, .. } if !taken => {
+                        user_input.set_mouse_button(*window_id, *button, *state);
+                    }
 
                     _ => (),
                 }
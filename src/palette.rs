@@ -0,0 +1,542 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use vulkano::buffer::BufferContents;
+use crate::input_bindings::Action;
+use crate::user_io::UserIO;
+
+/// Most colour stops a gradient palette can have, `MAX_GRADIENT_STOPS` in `palette.glsl`.
+pub const MAX_GRADIENT_STOPS: usize = 16;
+
+// Palette kinds, keep in sync with `palette.glsl`.
+pub const PALETTE_COSINE: u32 = 0;
+pub const PALETTE_GRADIENT: u32 = 1;
+
+/// Seconds a switch to another palette takes.
+const BLEND_DURATION: f32 = 0.75;
+
+/// Colours the scene shaders give shapes without a material, by distance and time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Palette {
+    /// `a + b * cos(2π (c t + d))` per channel, see https://iquilezles.org/articles/palettes/
+    Cosine { a: [f32; 3], b: [f32; 3], c: [f32; 3], d: [f32; 3] },
+    /// Colours blended linearly between stops, wrapping around from the last stop to the first.
+    Gradient { stops: Vec<ColourStop> },
+    /// Evenly spaced stops taken from the first row of a PNG, such as a 1D gradient texture.
+    /// Relative paths start at the palette file.
+    Image { path: PathBuf },
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::Cosine {
+            a: [0.500, 0.500, 0.500],
+            b: [0.420, 0.420, 0.420],
+            c: [0.760, 0.760, 0.760],
+            d: [1.588, 1.922, 2.255],
+        }
+    }
+}
+
+/// Colour of a gradient at `position`, from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColourStop {
+    pub position: f32,
+    pub colour: [f32; 3],
+}
+
+impl Palette {
+    fn validate(&self) -> Result<(), &'static str> {
+        match self {
+            Palette::Cosine { a, b, c, d } => {
+                if [a, b, c, d].iter().flat_map(|vector| vector.iter()).all(|value| value.is_finite()) {
+                    Ok(())
+                } else {
+                    Err("must have finite `a`, `b`, `c` and `d`")
+                }
+            }
+            Palette::Gradient { stops } => validate_stops(stops),
+            Palette::Image { .. } => Ok(()),
+        }
+    }
+
+    /// Image palettes are read from `directory` here.
+    fn encode(&self, directory: &Path) -> Result<GpuPalette, PaletteFileError> {
+        let palette = match self {
+            Palette::Cosine { a, b, c, d } => {
+                let [a, b, c, d] = [a, b, c, d].map(|&[x, y, z]| [x, y, z, 0.0]);
+                GpuPalette {
+                    header: [PALETTE_COSINE, 0, 0, 0],
+                    a,
+                    b,
+                    c,
+                    d,
+                    ..Default::default()
+                }
+            }
+            Palette::Gradient { stops } => encode_gradient(stops),
+            Palette::Image { path } => encode_gradient(&load_gradient_image(&directory.join(path))?),
+        };
+
+        Ok(palette)
+    }
+}
+
+fn validate_stops(stops: &[ColourStop]) -> Result<(), &'static str> {
+    if stops.is_empty() || stops.len() > MAX_GRADIENT_STOPS {
+        return Err("needs 1 to 16 colour stops");
+    }
+    if !stops.iter().all(|stop| (0.0..=1.0).contains(&stop.position)) {
+        return Err("has a stop position outside 0 to 1");
+    }
+    if !stops.windows(2).all(|pair| pair[0].position <= pair[1].position) {
+        return Err("has stop positions that are not in ascending order");
+    }
+    if !stops.iter().flat_map(|stop| stop.colour).all(|channel| (0.0..=1.0).contains(&channel)) {
+        return Err("has a colour channel outside 0 to 1");
+    }
+
+    Ok(())
+}
+
+fn encode_gradient(stops: &[ColourStop]) -> GpuPalette {
+    let mut palette = GpuPalette {
+        header: [PALETTE_GRADIENT, stops.len() as u32, 0, 0],
+        ..Default::default()
+    };
+    for (encoded, stop) in palette.stops.iter_mut().zip(stops) {
+        let [r, g, b] = stop.colour;
+        *encoded = [r, g, b, stop.position];
+    }
+
+    palette
+}
+
+/// Samples the first row of a PNG at the centres of up to `MAX_GRADIENT_STOPS` equal parts.
+fn load_gradient_image(path: &Path) -> Result<Vec<ColourStop>, PaletteFileError> {
+    let file = File::open(path).map_err(|source| PaletteFileError::Io { path: path.to_path_buf(), source })?;
+    let image_error = |e: png::DecodingError| PaletteFileError::Image { path: path.to_path_buf(), message: e.to_string() };
+
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(image_error)?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(image_error)?;
+
+    let channels = info.color_type.samples();
+    let width = info.width as usize;
+    let count = width.min(MAX_GRADIENT_STOPS);
+    let stops = (0..count)
+        .map(|index| {
+            let position = (index as f32 + 0.5) / count as f32;
+            let pixel = &pixels[(position * width as f32) as usize * channels..][..channels];
+            // Grey images have a single colour channel.
+            let colour = match pixel {
+                [r, g, b, ..] => [*r, *g, *b],
+                [grey, ..] => [*grey; 3],
+                [] => unreachable!(),
+            };
+            ColourStop { position, colour: colour.map(|channel| channel as f32 / 255.0) }
+        })
+        .collect();
+
+    Ok(stops)
+}
+
+/// GPU side palette, matches std140 layout and `Palette` in `palette.glsl`.
+/// `header` holds the kind and the number of gradient stops, stops are colour and position.
+#[derive(BufferContents, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct GpuPalette {
+    pub header: [u32; 4],
+    pub a: [f32; 4],
+    pub b: [f32; 4],
+    pub c: [f32; 4],
+    pub d: [f32; 4],
+    pub stops: [[f32; 4]; MAX_GRADIENT_STOPS],
+}
+
+/// Palette uniform of the scene shaders, `Palettes` in `palette.glsl`. The shaders blend from
+/// `previous` to `current` by `blend[0]`, where `previous` is first blended from `older` by `blend[1]`.
+#[derive(BufferContents, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct GpuPalettes {
+    pub previous: GpuPalette,
+    pub current: GpuPalette,
+    pub older: GpuPalette,
+    pub blend: [f32; 4],
+}
+
+impl GpuPalettes {
+    /// Only `palette`, without a blend.
+    pub fn single(palette: GpuPalette) -> GpuPalettes {
+        GpuPalettes {
+            previous: palette,
+            current: palette,
+            older: palette,
+            blend: [1.0, 1.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl Default for GpuPalettes {
+    fn default() -> Self {
+        GpuPalettes::single(PaletteLibrary::default().presets[0].palette)
+    }
+}
+
+/// A palette by the name hotkeys and `--palette` pick it with.
+#[derive(Clone, Debug)]
+pub struct PalettePreset {
+    pub name: String,
+    pub palette: GpuPalette,
+}
+
+/// Presets in the order the hotkeys cycle through them.
+#[derive(Clone, Debug)]
+pub struct PaletteLibrary {
+    presets: Vec<PalettePreset>,
+}
+
+impl Default for PaletteLibrary {
+    fn default() -> Self {
+        let presets = built_in_palettes()
+            .into_iter()
+            .map(|(name, palette)| PalettePreset {
+                name: name.to_string(),
+                palette: palette.encode(Path::new("")).expect("built in palettes need no files"),
+            })
+            .collect();
+
+        PaletteLibrary { presets }
+    }
+}
+
+impl PaletteLibrary {
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.presets.iter().position(|preset| preset.name == name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.iter().map(|preset| preset.name.as_str())
+    }
+
+    pub fn preset(&self, index: usize) -> &PalettePreset {
+        &self.presets[index]
+    }
+}
+
+/// The first one is the palette the scene shaders always had.
+fn built_in_palettes() -> Vec<(&'static str, Palette)> {
+    let stop = |position, colour| ColourStop { position, colour };
+    vec![
+        ("default", Palette::default()),
+        ("rainbow", Palette::Cosine {
+            a: [0.5, 0.5, 0.5],
+            b: [0.5, 0.5, 0.5],
+            c: [1.0, 1.0, 1.0],
+            d: [0.0, 0.33, 0.67],
+        }),
+        ("dusk", Palette::Cosine {
+            a: [0.5, 0.5, 0.5],
+            b: [0.5, 0.5, 0.5],
+            c: [1.0, 1.0, 0.5],
+            d: [0.8, 0.9, 0.3],
+        }),
+        ("ocean", Palette::Gradient {
+            stops: vec![
+                stop(0.0, [0.02, 0.09, 0.25]),
+                stop(0.4, [0.0, 0.45, 0.6]),
+                stop(0.7, [0.55, 0.85, 0.85]),
+                stop(0.9, [0.95, 0.98, 0.95]),
+            ],
+        }),
+        ("ember", Palette::Gradient {
+            stops: vec![
+                stop(0.0, [0.1, 0.02, 0.02]),
+                stop(0.35, [0.7, 0.12, 0.03]),
+                stop(0.65, [0.98, 0.55, 0.1]),
+                stop(0.85, [1.0, 0.9, 0.55]),
+            ],
+        }),
+    ]
+}
+
+/// The preset a window shows and the blend over from what it showed before.
+#[derive(Clone, Debug)]
+pub struct PaletteSelection {
+    library: Arc<PaletteLibrary>,
+    index: usize,
+    /// The blend starts from `older` mixed into `previous` by `previous_weight`.
+    older: GpuPalette,
+    previous: GpuPalette,
+    previous_weight: f32,
+    blend: f32,
+}
+
+impl Default for PaletteSelection {
+    fn default() -> Self {
+        PaletteSelection::new(Arc::new(PaletteLibrary::default()), 0)
+    }
+}
+
+impl PaletteSelection {
+    pub fn new(library: Arc<PaletteLibrary>, index: usize) -> PaletteSelection {
+        let previous = library.preset(index).palette;
+        PaletteSelection {
+            library,
+            index,
+            older: previous,
+            previous,
+            previous_weight: 1.0,
+            blend: 1.0,
+        }
+    }
+
+    /// Cycles through the presets on the palette hotkeys and advances the blend.
+    pub fn update(&mut self, user_input: &UserIO, delta_time: f32) {
+        let count = self.library.presets.len();
+        if user_input.is_action_pressed(Action::NextPalette) {
+            self.select((self.index + 1) % count);
+        }
+        if user_input.is_action_pressed(Action::PreviousPalette) {
+            self.select((self.index + count - 1) % count);
+        }
+
+        self.blend = (self.blend + delta_time / BLEND_DURATION).min(1.0);
+    }
+
+    /// Starts blending over to the preset at `index`, from the colours on screen. The uniform
+    /// holds only three palettes, so a switch during a blend keeps the two that contribute most
+    /// and drops the third, which has at most a third of the weight and usually far less.
+    pub fn select(&mut self, index: usize) {
+        if index == self.index {
+            return;
+        }
+
+        let blend = self.eased_blend();
+        let mut shown = [
+            (self.older, (1.0 - self.previous_weight) * (1.0 - blend)),
+            (self.previous, self.previous_weight * (1.0 - blend)),
+            (self.library.preset(self.index).palette, blend),
+        ];
+        shown.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let [(previous, previous_weight), (older, older_weight), _] = shown;
+
+        self.older = older;
+        self.previous = previous;
+        self.previous_weight = previous_weight / (previous_weight + older_weight);
+        self.index = index;
+        self.blend = 0.0;
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn library(&self) -> &PaletteLibrary {
+        &self.library
+    }
+
+    /// Uniform for the current point of the blend.
+    pub fn encode(&self) -> GpuPalettes {
+        GpuPalettes {
+            previous: self.previous,
+            current: self.library.preset(self.index).palette,
+            older: self.older,
+            blend: [self.eased_blend(), self.previous_weight, 0.0, 0.0],
+        }
+    }
+
+    /// The blend eased in and out.
+    fn eased_blend(&self) -> f32 {
+        let t = self.blend;
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// Palette file layout. The presets replace the built in ones.
+#[derive(Deserialize)]
+struct PaletteFile {
+    palettes: Vec<PaletteFileEntry>,
+}
+
+#[derive(Deserialize)]
+struct PaletteFileEntry {
+    name: String,
+    palette: Palette,
+}
+
+#[derive(Debug)]
+pub enum PaletteFileError {
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, message: String },
+    NoPalettes { path: PathBuf },
+    InvalidPalette { path: PathBuf, name: String, reason: &'static str },
+    Image { path: PathBuf, message: String },
+}
+
+impl Display for PaletteFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaletteFileError::Io { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            PaletteFileError::Parse { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+            PaletteFileError::NoPalettes { path } => {
+                write!(f, "{}: needs at least one palette", path.display())
+            }
+            PaletteFileError::InvalidPalette { path, name, reason } => {
+                write!(f, "{}: palette `{}` {}", path.display(), name, reason)
+            }
+            PaletteFileError::Image { path, message } => {
+                write!(f, "{}: failed to read gradient image: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PaletteFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PaletteFileError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a `.ron` palette file and the gradient images it names.
+pub fn load_palettes(path: &Path) -> Result<PaletteLibrary, PaletteFileError> {
+    let contents = fs::read_to_string(path).map_err(|source| PaletteFileError::Io { path: path.to_path_buf(), source })?;
+    let file: PaletteFile = ron::from_str(&contents)
+        .map_err(|e| PaletteFileError::Parse { path: path.to_path_buf(), message: e.to_string() })?;
+    if file.palettes.is_empty() {
+        return Err(PaletteFileError::NoPalettes { path: path.to_path_buf() });
+    }
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut presets: Vec<PalettePreset> = Vec::with_capacity(file.palettes.len());
+    for entry in file.palettes {
+        let invalid = |reason| PaletteFileError::InvalidPalette { path: path.to_path_buf(), name: entry.name.clone(), reason };
+        if presets.iter().any(|preset| preset.name == entry.name) {
+            return Err(invalid("is used by an earlier palette"));
+        }
+        entry.palette.validate().map_err(invalid)?;
+
+        let palette = entry.palette.encode(directory)?;
+        presets.push(PalettePreset { name: entry.name, palette });
+    }
+
+    Ok(PaletteLibrary { presets })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu_evaluator::palette;
+    use crate::test_support::TestDirectory;
+
+    /// Positions along the palette the colours are compared at.
+    const SAMPLES: [f32; 5] = [0.0, 0.2, 0.45, 0.7, 0.95];
+
+    fn colours(selection: &PaletteSelection) -> Vec<[f32; 3]> {
+        let palettes = selection.encode();
+        SAMPLES.iter().map(|t| palette(&palettes, *t)).collect()
+    }
+
+    fn largest_difference(a: &[[f32; 3]], b: &[[f32; 3]]) -> f32 {
+        a.iter().flatten().zip(b.iter().flatten()).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max)
+    }
+
+    fn invalid_reason(contents: &str) -> &'static str {
+        let directory = TestDirectory::new();
+        match load_palettes(&directory.file("invalid.ron", contents)).unwrap_err() {
+            PaletteFileError::InvalidPalette { reason, .. } => reason,
+            error => panic!("unexpected error: {error}"),
+        }
+    }
+
+    fn gradient(stops: &[(f32, f32)]) -> String {
+        let stops: Vec<String> = stops.iter().map(|(position, grey)| {
+            format!("(position: {position}, colour: ({grey}, {grey}, {grey}))")
+        }).collect();
+        format!("Gradient(stops: [{}])", stops.join(", "))
+    }
+
+    #[test]
+    fn switch_during_a_blend_starts_from_the_colours_on_screen() {
+        let mut selection = PaletteSelection::default();
+        selection.select(1);
+        selection.blend = 0.4;
+        let before = colours(&selection);
+
+        selection.select(2);
+        assert!(largest_difference(&before, &colours(&selection)) < 1e-5);
+    }
+
+    #[test]
+    fn third_switch_during_a_blend_drops_the_least_visible_palette() {
+        let mut selection = PaletteSelection::default();
+        selection.select(1);
+        selection.blend = 0.5;
+        selection.select(2);
+        selection.blend = 0.3;
+        let before = colours(&selection);
+
+        let blend = selection.eased_blend();
+        let weights = [
+            (1.0 - selection.previous_weight) * (1.0 - blend),
+            selection.previous_weight * (1.0 - blend),
+            blend,
+        ];
+        let dropped = weights.into_iter().fold(f32::INFINITY, f32::min);
+
+        // Every built in palette stays within 0 to 1, so no channel moves by more than the dropped weight.
+        selection.select(3);
+        assert!(largest_difference(&before, &colours(&selection)) <= dropped + 1e-5);
+    }
+
+    #[test]
+    fn finished_blend_shows_only_the_selected_palette() {
+        let mut selection = PaletteSelection::default();
+        selection.select(2);
+        selection.blend = 1.0;
+        let expected = GpuPalettes::single(selection.library().preset(2).palette);
+        assert_eq!(colours(&selection), SAMPLES.iter().map(|t| palette(&expected, *t)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let palette = gradient(&[(0.0, 0.0), (1.0, 1.0)]);
+        let contents = format!("(palettes: [(name: \"grey\", palette: {palette}), (name: \"grey\", palette: {palette})])");
+        assert_eq!(invalid_reason(&contents), "is used by an earlier palette");
+    }
+
+    #[test]
+    fn unsorted_stops_are_rejected() {
+        let contents = format!("(palettes: [(name: \"grey\", palette: {})])", gradient(&[(0.6, 0.0), (0.2, 1.0)]));
+        assert_eq!(invalid_reason(&contents), "has stop positions that are not in ascending order");
+    }
+
+    #[test]
+    fn too_many_stops_are_rejected() {
+        let stops: Vec<(f32, f32)> = (0..=MAX_GRADIENT_STOPS).map(|index| (index as f32 / 20.0, 0.5)).collect();
+        let contents = format!("(palettes: [(name: \"grey\", palette: {})])", gradient(&stops));
+        assert_eq!(invalid_reason(&contents), "needs 1 to 16 colour stops");
+
+        let stops = &stops[..MAX_GRADIENT_STOPS];
+        let contents = format!("(palettes: [(name: \"grey\", palette: {})])", gradient(stops));
+        assert!(load_palettes(&TestDirectory::new().file("sixteen.ron", &contents)).is_ok());
+    }
+
+    #[test]
+    fn shipped_palettes_load() {
+        let library = load_palettes(&Path::new(env!("CARGO_MANIFEST_DIR")).join("config/palettes.ron")).unwrap();
+        assert!(library.index_of("default").is_some());
+    }
+}
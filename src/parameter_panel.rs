@@ -0,0 +1,382 @@
+use std::sync::Arc;
+use egui::{ClippedPrimitive, CollapsingHeader, ComboBox, DragValue, Slider, TexturesDelta, Ui};
+use serde_json::Value;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::sync::GpuFuture;
+use vulkano_util::renderer::SwapchainImageView;
+use winit::event::{ElementState, WindowEvent};
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::Window;
+use crate::application::Application;
+use crate::camera::{Camera2D, Camera3D};
+use crate::palette::PaletteSelection;
+use crate::render_pass_gui::RenderPassGui;
+use crate::scene::Scene;
+use crate::shader_parameters::ScalarKind;
+use crate::signed_distance_function_renderer::{RenderMode, SimpleVulkanRendererComputePipeline};
+use crate::timeline::{Playback, Timeline};
+
+const MAX_PLAYBACK_SPEED: f32 = 4.0;
+
+/// Shortest span of the time slider, so scenes without keyframes can still be scrubbed.
+const MIN_SCRUB_RANGE: f32 = 10.0;
+
+/// What the panel edits, borrowed from the window's pipeline for one frame.
+pub struct PanelTargets<'a> {
+    pub camera: &'a mut Camera2D,
+    pub camera_3d: &'a mut Camera3D,
+    pub palette: &'a mut PaletteSelection,
+    pub playback: &'a mut Playback,
+    pub timeline: &'a Timeline,
+    pub compute: &'a mut SimpleVulkanRendererComputePipeline,
+}
+
+/// Immediate mode egui panel over a window, for the playback, render mode, palette, cameras,
+/// annotated shader uniforms and scene parameters. It sees window events before `UserIO` does, see `on_window_event`.
+pub struct ParameterPanel {
+    context: egui::Context,
+    winit_state: egui_winit::State,
+    render_pass: RenderPassGui,
+    visible: bool,
+
+    /// The scene as edited, in its serialized form so any parameter can be shown generically.
+    scene: Value,
+    scene_error: Option<String>,
+
+    primitives: Vec<ClippedPrimitive>,
+    textures_delta: TexturesDelta,
+}
+
+impl ParameterPanel {
+    pub fn new(
+        app: &Application,
+        event_loop: &EventLoopWindowTarget<()>,
+        graphics_queue: Arc<Queue>,
+        output_format: Format,
+        scene: &Scene,
+    ) -> ParameterPanel {
+        ParameterPanel {
+            context: egui::Context::default(),
+            winit_state: egui_winit::State::new(event_loop),
+            render_pass: RenderPassGui::new(app, graphics_queue, output_format),
+            visible: false,
+            scene: serde_json::to_value(scene).unwrap(),
+            scene_error: None,
+            primitives: Vec::new(),
+            textures_delta: TexturesDelta::default(),
+        }
+    }
+
+    /// Shows or hides the panel. A hidden panel takes no input.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.primitives.clear();
+    }
+
+    /// Hands a window event to the panel first and returns whether it took the event, in which
+    /// case `UserIO` should not see it. Key and button releases are never taken, so nothing
+    /// stays held down in `UserIO` after a press that it did see.
+    pub fn on_window_event(&mut self, event: &WindowEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        let response = self.winit_state.on_event(&self.context, event);
+        match event {
+            // egui-winit always claims `Tab`, which toggles the render mode here.
+            WindowEvent::KeyboardInput { input, .. } => {
+                input.state == ElementState::Pressed && self.context.wants_keyboard_input()
+            }
+            WindowEvent::MouseInput { state: ElementState::Released, .. } => false,
+            _ => response.consumed,
+        }
+    }
+
+    /// Builds this frame's panel and applies the edits to `targets`. Returns the edited scene
+    /// when a scene parameter changed and the scene is still valid.
+    pub fn update(&mut self, window: &Window, targets: PanelTargets) -> Option<Scene> {
+        if !self.visible {
+            return None;
+        }
+
+        // Scale factor changes while hidden never reached the panel.
+        self.winit_state.set_pixels_per_point(window.scale_factor() as f32);
+        let raw_input = self.winit_state.take_egui_input(window);
+        let mut scene_changed = false;
+        let output = self.context.run(raw_input, |context| {
+            egui::Window::new("Parameters")
+                .default_pos([8.0, 8.0])
+                .vscroll(true)
+                .show(context, |ui| {
+                    CollapsingHeader::new("View").default_open(true).show(ui, |ui| view_controls(ui, targets.compute, targets.palette));
+                    CollapsingHeader::new("Timeline").default_open(true).show(ui, |ui| timeline_controls(ui, targets.playback, targets.timeline));
+                    CollapsingHeader::new("Camera").show(ui, |ui| camera_controls(ui, targets.camera, targets.camera_3d));
+                    CollapsingHeader::new("Shader").show(ui, |ui| shader_parameter_controls(ui, targets.compute));
+                    CollapsingHeader::new("Scene").show(ui, |ui| {
+                        if let Some(error) = &self.scene_error {
+                            ui.colored_label(egui::Color32::LIGHT_RED, error);
+                        }
+                        scene_changed = edit_value(ui, &mut self.scene);
+                    });
+                });
+        });
+
+        self.winit_state.handle_platform_output(window, &self.context, output.platform_output);
+        self.primitives = self.context.tessellate(output.shapes);
+        self.textures_delta.append(output.textures_delta);
+
+        if !scene_changed {
+            return None;
+        }
+
+        let scene = serde_json::from_value::<Scene>(self.scene.clone()).map_err(|e| e.to_string()).and_then(|scene| {
+            scene.validate().map_err(|invalid| {
+                format!("`{}` has an invalid `{}`: {}", invalid.location, invalid.parameter, invalid.reason)
+            })?;
+            Ok(scene)
+        });
+        match scene {
+            Ok(scene) => {
+                self.scene_error = None;
+                Some(scene)
+            }
+            Err(e) => {
+                self.scene_error = Some(e);
+                None
+            }
+        }
+    }
+
+    /// Draws the panel over `target`, after the frame itself was drawn.
+    pub fn render<F>(&mut self, before_future: F, target: SwapchainImageView) -> Box<dyn GpuFuture> where F: GpuFuture + 'static {
+        if !self.visible {
+            return before_future.boxed();
+        }
+
+        let textures_delta = std::mem::take(&mut self.textures_delta);
+        let pixels_per_point = self.context.pixels_per_point();
+        self.render_pass.render(before_future, target, &self.primitives, &textures_delta, pixels_per_point)
+    }
+}
+
+fn view_controls(ui: &mut Ui, compute: &mut SimpleVulkanRendererComputePipeline, palette: &mut PaletteSelection) {
+    let mut mode = compute.render_mode();
+    // Single and Shadertoy shaders have no raymarched or path traced version.
+    ui.add_enabled_ui(compute.runs_scene_shaders(), |ui| {
+        ComboBox::from_label("Render mode")
+            .selected_text(format!("{mode:?}"))
+            .show_ui(ui, |ui| {
+                for option in [RenderMode::Flat, RenderMode::Raymarch, RenderMode::PathTrace] {
+                    ui.selectable_value(&mut mode, option, format!("{option:?}"));
+                }
+            });
+    });
+    compute.set_render_mode(mode);
+
+    let mut index = palette.index();
+    ComboBox::from_label("Palette")
+        .selected_text(palette.library().preset(index).name.as_str())
+        .show_ui(ui, |ui| {
+            for (option, name) in palette.library().names().enumerate() {
+                ui.selectable_value(&mut index, option, name);
+            }
+        });
+    palette.select(index);
+}
+
+/// Play, pause, scrub, speed and loop region of the scene time, and the tracks of the timeline.
+/// Keyframes themselves are edited with the rest of the scene.
+fn timeline_controls(ui: &mut Ui, playback: &mut Playback, timeline: &Timeline) {
+    let loop_start = playback.loop_region.map_or(0.0, |[start, _]| start);
+    ui.horizontal(|ui| {
+        if ui.button(if playback.playing { "Pause" } else { "Play" }).clicked() {
+            playback.toggle();
+        }
+        if ui.button("Restart").clicked() {
+            playback.scrub(loop_start as f64);
+        }
+    });
+
+    // The slider grows with the time, so playing past the last keyframe never pins the handle.
+    let mut time = playback.time() as f32;
+    let end = playback.loop_region.map_or(0.0, |[_, end]| end)
+        .max(timeline.duration())
+        .max(time)
+        .max(MIN_SCRUB_RANGE);
+    if ui.add(Slider::new(&mut time, 0.0..=end).suffix(" s").text("Time")).changed() {
+        playback.scrub(time as f64);
+    }
+    ui.add(Slider::new(&mut playback.speed, 0.0..=MAX_PLAYBACK_SPEED).text("Speed"));
+
+    ui.horizontal(|ui| {
+        let mut looping = playback.loop_region.is_some();
+        if ui.checkbox(&mut looping, "Loop").changed() {
+            playback.loop_region = looping.then(|| [0.0, timeline.duration().max(1.0)]);
+        }
+        if let Some([start, end]) = &mut playback.loop_region {
+            ui.add(DragValue::new(start).speed(0.01).clamp_range(0.0..=f32::MAX).suffix(" s"));
+            ui.add(DragValue::new(end).speed(0.01).clamp_range(0.0..=f32::MAX).suffix(" s"));
+            *end = end.max(*start);
+        }
+    });
+
+    if timeline.is_empty() {
+        ui.weak("no keyframes");
+    }
+    for track in &timeline.tracks {
+        ui.label(format!("{}, {} keyframes", track.target, track.keyframes.len()));
+    }
+}
+
+fn camera_controls(ui: &mut Ui, camera: &mut Camera2D, camera_3d: &mut Camera3D) {
+    ui.label("2D");
+    ui.horizontal(|ui| {
+        ui.label("Offset");
+        ui.add(DragValue::new(&mut camera.offset[0]).speed(0.01));
+        ui.add(DragValue::new(&mut camera.offset[1]).speed(0.01));
+    });
+    ui.add(Slider::new(&mut camera.zoom, 0.01..=100.0).logarithmic(true).text("Zoom"));
+    ui.drag_angle(&mut camera.rotation);
+
+    ui.separator();
+    ui.label("3D");
+    ui.horizontal(|ui| {
+        ui.label("Position");
+        for coordinate in &mut camera_3d.position {
+            ui.add(DragValue::new(coordinate).speed(0.01));
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Yaw");
+        ui.drag_angle(&mut camera_3d.yaw);
+        ui.label("Pitch");
+        ui.drag_angle(&mut camera_3d.pitch);
+    });
+    camera_3d.pitch = camera_3d.pitch.clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2);
+    ui.add(Slider::new(&mut camera_3d.field_of_view, 0.1..=2.5).text("Field of view"));
+}
+
+/// A control per annotated uniform of the running shader, made from its reflected type and
+/// annotation: colour pickers for `@colour`, sliders for `@range` and drag values otherwise.
+fn shader_parameter_controls(ui: &mut Ui, compute: &mut SimpleVulkanRendererComputePipeline) {
+    let parameters = compute.annotated_parameters();
+    if parameters.is_empty() {
+        ui.weak("no annotated parameters");
+        return;
+    }
+
+    for parameter in parameters {
+        let annotation = parameter.annotation.clone().unwrap_or_default();
+        let integer = parameter.kind != ScalarKind::Float;
+        let mut value = parameter.value(compute.parameter_store());
+        let components = &mut value[..parameter.components];
+
+        let changed = ui.horizontal(|ui| {
+            ui.label(&parameter.name);
+            match components {
+                [r, g, b] if annotation.colour => {
+                    let mut colour = [*r, *g, *b];
+                    let changed = ui.color_edit_button_rgb(&mut colour).changed();
+                    [*r, *g, *b] = colour;
+                    changed
+                }
+                [r, g, b, a] if annotation.colour => {
+                    let mut colour = [*r, *g, *b, *a];
+                    let changed = ui.color_edit_button_rgba_unmultiplied(&mut colour).changed();
+                    [*r, *g, *b, *a] = colour;
+                    changed
+                }
+                _ => components.iter_mut().fold(false, |changed, component| {
+                    let response = match annotation.range {
+                        Some([min, max]) => {
+                            let slider = Slider::new(component, min..=max);
+                            ui.add(if integer { slider.integer() } else { slider })
+                        }
+                        None => ui.add(DragValue::new(component).speed(if integer { 0.1 } else { 0.01 })),
+                    };
+                    if integer {
+                        *component = component.round();
+                    }
+                    response.changed() | changed
+                }),
+            }
+        }).inner;
+
+        if changed {
+            compute.set_parameter(&parameter.name, &value[..parameter.components]);
+        }
+    }
+}
+
+/// Numbers and flags of a serialized value as drag values and checkboxes, nested in collapsing
+/// headers. Strings such as names are shown but not edited. Returns whether anything changed.
+fn edit_value(ui: &mut Ui, value: &mut Value) -> bool {
+    match value {
+        Value::Object(fields) => {
+            let mut changed = false;
+            for (key, field) in fields.iter_mut() {
+                changed |= edit_field(ui, key, field);
+            }
+            changed
+        }
+        Value::Array(elements) => {
+            let mut changed = false;
+            for (index, element) in elements.iter_mut().enumerate() {
+                changed |= edit_field(ui, &index.to_string(), element);
+            }
+            changed
+        }
+        _ => edit_leaf(ui, value),
+    }
+}
+
+fn edit_field(ui: &mut Ui, label: &str, value: &mut Value) -> bool {
+    match value {
+        // Short lists of numbers are points, sizes and colours, kept on one line.
+        Value::Array(elements) if (2..=4).contains(&elements.len()) && elements.iter().all(Value::is_number) => {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                elements.iter_mut().fold(false, |changed, element| edit_leaf(ui, element) | changed)
+            }).inner
+        }
+        Value::Object(_) | Value::Array(_) => {
+            ui.push_id(label, |ui| {
+                CollapsingHeader::new(label).show(ui, |ui| edit_value(ui, value)).body_returned.unwrap_or(false)
+            }).inner
+        }
+        _ => ui.horizontal(|ui| {
+            ui.label(label);
+            edit_leaf(ui, value)
+        }).inner,
+    }
+}
+
+fn edit_leaf(ui: &mut Ui, value: &mut Value) -> bool {
+    match value {
+        Value::Bool(flag) => ui.checkbox(flag, "").changed(),
+        Value::Number(number) if number.is_u64() => {
+            let mut integer = number.as_u64().unwrap();
+            let changed = ui.add(DragValue::new(&mut integer).speed(0.1)).changed();
+            *value = Value::from(integer);
+            changed
+        }
+        Value::Number(number) => {
+            let mut float = number.as_f64().unwrap_or_default();
+            let changed = ui.add(DragValue::new(&mut float).speed(0.01)).changed();
+            if changed {
+                *value = Value::from(float);
+            }
+            changed
+        }
+        Value::String(text) => {
+            ui.label(text.as_str());
+            false
+        }
+        Value::Null => {
+            ui.weak("none");
+            false
+        }
+        Value::Object(_) | Value::Array(_) => unreachable!("handled by `edit_value`"),
+    }
+}
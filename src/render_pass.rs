@@ -1,13 +1,15 @@
 use std::sync::Arc;
 use vulkano::buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer};
 use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
-use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBufferInheritanceInfo, CommandBufferUsage, RenderPassBeginInfo, SecondaryAutoCommandBuffer, SubpassContents};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBufferInheritanceInfo, CommandBufferUsage, PrimaryAutoCommandBuffer, RenderPassBeginInfo, SecondaryAutoCommandBuffer, SubpassContents};
 use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
 use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
 use vulkano::device::Queue;
 use vulkano::format::Format;
-use vulkano::image::{ImageAccess, ImageViewAbstract};
-use vulkano::memory::allocator::{AllocationCreateInfo, MemoryUsage};
+use vulkano::image::view::ImageView;
+use vulkano::image::{ImageAccess, ImageDimensions, ImageViewAbstract, ImmutableImage, MipmapsCount};
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator};
+use vulkano::pipeline::graphics::color_blend::ColorBlendState;
 use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
 use vulkano::pipeline::graphics::vertex_input::Vertex;
 use vulkano::pipeline::graphics::viewport::{Viewport, ViewportState};
@@ -17,6 +19,8 @@ use vulkano::sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo, S
 use vulkano::sync::GpuFuture;
 use vulkano_util::renderer::{DeviceImageView, SwapchainImageView};
 use crate::application::Application;
+use embedded_graphics::pixelcolor::Rgb888;
+use crate::text_overlay::{rasterize_text, TextOverlayImage, ERROR_FOREGROUND, STATUS_FOREGROUND};
 
 
 
@@ -66,7 +70,12 @@ pub struct DrawPipeline {
 impl DrawPipeline {
 
 
-    pub fn new(app: &Application, gfx_queue: Arc<Queue>, sub_pass: Subpass) -> DrawPipeline {
+    pub fn new(
+        app: &Application,
+        gfx_queue: Arc<Queue>,
+        sub_pass: Subpass,
+        color_blend_state: ColorBlendState,
+    ) -> DrawPipeline {
         let (vertices, indices) = textured_quad(2.0, 2.0);
         let memory_allocator = app.context.memory_allocator();
         let vertex_buffer = Buffer::from_iter(
@@ -101,6 +110,7 @@ impl DrawPipeline {
                 .vertex_shader(vs.entry_point("main").unwrap(), ())
                 .input_assembly_state(InputAssemblyState::new())
                 .fragment_shader(fs.entry_point("main").unwrap(), ())
+                .color_blend_state(color_blend_state)
                 .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
                 .render_pass(sub_pass.clone())
                 .build(gfx_queue.device().clone())
@@ -118,9 +128,10 @@ impl DrawPipeline {
         }
     }
 
-    /// Draws input `image` over a quad of size -1.0 to 1.0.
+    /// Draws input `image` over a quad of size -1.0 to 1.0, which fills the viewport.
     pub fn draw(
         &self,
+        viewport_origin: [u32; 2],
         viewport_dimensions: [u32; 2],
         image: Arc<dyn ImageViewAbstract>,
     ) -> SecondaryAutoCommandBuffer {
@@ -140,7 +151,7 @@ impl DrawPipeline {
             .set_viewport(
                 0,
                 [Viewport {
-                    origin: [0.0, 0.0],
+                    origin: [viewport_origin[0] as f32, viewport_origin[1] as f32],
                     dimensions: [viewport_dimensions[0] as f32, viewport_dimensions[1] as f32],
                     depth_range: 0.0..1.0,
                 }],
@@ -164,7 +175,7 @@ impl DrawPipeline {
         &self,
         image: Arc<dyn ImageViewAbstract>,
     ) -> Arc<PersistentDescriptorSet> {
-        let layout = self.pipeline.layout().set_layouts().get(0).unwrap();
+        let layout = self.pipeline.layout().set_layouts().first().unwrap();
         let sampler = Sampler::new(
             self.gfx_queue.device().clone(),
             SamplerCreateInfo {
@@ -189,11 +200,64 @@ impl DrawPipeline {
 }
 
 
+/// Where on the frame a text overlay is drawn.
+#[derive(Clone, Copy)]
+enum OverlayCorner {
+    TopLeft,
+    BottomLeft,
+}
+
+/// A line of text drawn over the frame. The text is rasterized when it changes and
+/// uploaded with the next rendered frame.
+struct TextOverlay {
+    corner: OverlayCorner,
+    foreground: Rgb888,
+    text: Option<String>,
+    pending: Option<TextOverlayImage>,
+    uploaded: Option<(Arc<dyn ImageViewAbstract>, [u32; 2])>,
+}
+
+impl TextOverlay {
+    fn new(corner: OverlayCorner, foreground: Rgb888) -> TextOverlay {
+        TextOverlay {
+            corner,
+            foreground,
+            text: None,
+            pending: None,
+            uploaded: None,
+        }
+    }
+
+    fn set_text(&mut self, text: Option<&str>) {
+        if text == self.text.as_deref() {
+            return;
+        }
+
+        self.text = text.map(str::to_string);
+        self.pending = text.map(|text| rasterize_text(text, self.foreground));
+        if text.is_none() {
+            self.uploaded = None;
+        }
+    }
+
+    /// Pixel origin of the overlay on a frame of `frame_dims`, the viewport origin is top left.
+    fn origin(&self, overlay_dims: [u32; 2], frame_dims: [u32; 2]) -> [u32; 2] {
+        match self.corner {
+            OverlayCorner::TopLeft => [0, 0],
+            OverlayCorner::BottomLeft => [0, frame_dims[1].saturating_sub(overlay_dims[1])],
+        }
+    }
+}
+
 pub struct RenderPassPlaceOverFrame {
     gfx_queue: Arc<Queue>,
     render_pass: Arc<RenderPass>,
     draw_pipeline: DrawPipeline,
+    overlay_pipeline: DrawPipeline,
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    error_overlay: TextOverlay,
+    status_overlay: TextOverlay,
 }
 
 impl RenderPassPlaceOverFrame {
@@ -214,16 +278,33 @@ impl RenderPassPlaceOverFrame {
             },
         ).unwrap();
         let sub_pass = Subpass::from(render_pass.clone(), 0).unwrap();
-        let draw_pipeline = DrawPipeline::new(app, graphics_queue.clone(), sub_pass);
+        let draw_pipeline = DrawPipeline::new(app, graphics_queue.clone(), sub_pass.clone(), ColorBlendState::new(1));
+        let overlay_pipeline = DrawPipeline::new(app, graphics_queue.clone(), sub_pass, ColorBlendState::new(1).blend_alpha());
         RenderPassPlaceOverFrame {
             gfx_queue: graphics_queue,
             render_pass,
             draw_pipeline,
-            command_buffer_allocator: app.command_buffer_allocator.clone()
+            overlay_pipeline,
+            command_buffer_allocator: app.command_buffer_allocator.clone(),
+            memory_allocator: app.context.memory_allocator().clone(),
+            error_overlay: TextOverlay::new(OverlayCorner::TopLeft, ERROR_FOREGROUND),
+            status_overlay: TextOverlay::new(OverlayCorner::BottomLeft, STATUS_FOREGROUND),
         }
     }
 
-    pub fn render<F>(&self, before_future: F, view: DeviceImageView, target: SwapchainImageView) -> Box<dyn GpuFuture> where F: GpuFuture + 'static {
+    /// Sets the error text drawn in the top left corner over the frame, `None` hides it.
+    /// The text is uploaded with the next rendered frame.
+    pub fn set_overlay_text(&mut self, text: Option<&str>) {
+        self.error_overlay.set_text(text);
+    }
+
+    /// Sets the status line drawn in the bottom left corner, `None` hides it.
+    /// Setting the same text again does not upload it again.
+    pub fn set_status_text(&mut self, text: Option<&str>) {
+        self.status_overlay.set_text(text);
+    }
+
+    pub fn render<F>(&mut self, before_future: F, view: DeviceImageView, target: SwapchainImageView) -> Box<dyn GpuFuture> where F: GpuFuture + 'static {
         let img_dims = target.image().dimensions();
         let framebuffer = Framebuffer::new(
             self.render_pass.clone(),
@@ -233,6 +314,14 @@ impl RenderPassPlaceOverFrame {
             },
         ).unwrap();
         let mut command_buffer_builder = AutoCommandBufferBuilder::primary(&self.command_buffer_allocator, self.gfx_queue.queue_family_index(), CommandBufferUsage::OneTimeSubmit).unwrap();
+
+        // Upload new overlay text before the render pass starts.
+        for overlay in [&mut self.error_overlay, &mut self.status_overlay] {
+            if let Some(pending) = overlay.pending.take() {
+                overlay.uploaded = Some(upload_overlay(&self.memory_allocator, pending, &mut command_buffer_builder));
+            }
+        }
+
         command_buffer_builder.begin_render_pass(
             RenderPassBeginInfo {
                 clear_values: vec![Some([0.0; 4].into())],
@@ -240,8 +329,18 @@ impl RenderPassPlaceOverFrame {
             },
             SubpassContents::SecondaryCommandBuffers,
         ).unwrap();
-        let cb = self.draw_pipeline.draw(img_dims.width_height(), view);
+        let cb = self.draw_pipeline.draw([0, 0], img_dims.width_height(), view);
         command_buffer_builder.execute_commands(cb).unwrap();
+
+        // Overlays are drawn at their pixel size by shrinking the viewport to them.
+        for overlay in [&self.error_overlay, &self.status_overlay] {
+            if let Some((overlay_view, overlay_dims)) = &overlay.uploaded {
+                let origin = overlay.origin(*overlay_dims, img_dims.width_height());
+                let cb = self.overlay_pipeline.draw(origin, *overlay_dims, overlay_view.clone());
+                command_buffer_builder.execute_commands(cb).unwrap();
+            }
+        }
+
         command_buffer_builder.end_render_pass().unwrap();
         let command_buffer = command_buffer_builder.build().unwrap();
         let after_future = before_future.then_execute(self.gfx_queue.clone(), command_buffer).unwrap();
@@ -249,6 +348,37 @@ impl RenderPassPlaceOverFrame {
     }
 }
 
+fn upload_overlay(
+    memory_allocator: &StandardMemoryAllocator,
+    overlay: TextOverlayImage,
+    command_buffer_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer, Arc<StandardCommandBufferAllocator>>,
+) -> (Arc<dyn ImageViewAbstract>, [u32; 2]) {
+    let [width, height] = overlay.dimensions;
+
+    // The quad samples with a flipped v coordinate, so rows are uploaded bottom up.
+    let pixels: Vec<u8> = overlay.pixels
+        .chunks_exact(width as usize * 4)
+        .rev()
+        .flatten()
+        .copied()
+        .collect();
+
+    let image = ImmutableImage::from_iter(
+        memory_allocator,
+        pixels,
+        ImageDimensions::Dim2d {
+            width,
+            height,
+            array_layers: 1,
+        },
+        MipmapsCount::One,
+        Format::R8G8B8A8_UNORM,
+        command_buffer_builder,
+    ).unwrap();
+
+    (ImageView::new_default(image).unwrap(), overlay.dimensions)
+}
+
 mod vs {
     vulkano_shaders::shader! {
         ty: "vertex",
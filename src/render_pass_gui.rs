@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use egui::epaint::{ImageDelta, Primitive};
+use egui::{ClippedPrimitive, ImageData, TextureFilter, TextureId, TexturesDelta};
+use vulkano::buffer::allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo};
+use vulkano::buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage};
+use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilder, BufferImageCopy, CommandBufferUsage, CopyBufferToImageInfo, PrimaryAutoCommandBuffer,
+    RenderPassBeginInfo, SubpassContents,
+};
+use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
+use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
+use vulkano::device::Queue;
+use vulkano::format::{Format, NumericType};
+use vulkano::image::view::ImageView;
+use vulkano::image::{ImageAccess, ImageCreateFlags, ImageDimensions, ImageUsage, StorageImage};
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator};
+use vulkano::pipeline::graphics::color_blend::{AttachmentBlend, BlendFactor, BlendOp, ColorBlendState};
+use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
+use vulkano::pipeline::graphics::rasterization::{CullMode, RasterizationState};
+use vulkano::pipeline::graphics::vertex_input::Vertex;
+use vulkano::pipeline::graphics::viewport::{Scissor, Viewport, ViewportState};
+use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint};
+use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass};
+use vulkano::sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode};
+use vulkano::sync::GpuFuture;
+use vulkano_util::renderer::SwapchainImageView;
+use crate::application::Application;
+
+#[derive(BufferContents, Vertex)]
+#[repr(C)]
+struct GuiVertex {
+    #[format(R32G32_SFLOAT)]
+    position: [f32; 2],
+    #[format(R32G32_SFLOAT)]
+    tex_coords: [f32; 2],
+    #[format(R8G8B8A8_UNORM)]
+    colour: [u8; 4],
+}
+
+/// An egui texture and the descriptor set that samples it.
+struct GuiTexture {
+    image: Arc<StorageImage>,
+    descriptor_set: Arc<PersistentDescriptorSet>,
+}
+
+/// Draws egui meshes over the swapchain image in a render pass of its own, after
+/// `RenderPassPlaceOverFrame` has drawn the frame. The frame is loaded, not cleared.
+pub struct RenderPassGui {
+    gfx_queue: Arc<Queue>,
+    render_pass: Arc<RenderPass>,
+    pipeline: Arc<GraphicsPipeline>,
+    command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    mesh_buffer_allocator: SubbufferAllocator,
+    textures: HashMap<TextureId, GuiTexture>,
+
+    /// egui colours are sRGB, an sRGB swapchain expects linear ones.
+    linear_output: bool,
+}
+
+impl RenderPassGui {
+    pub fn new(app: &Application, graphics_queue: Arc<Queue>, output_format: Format) -> RenderPassGui {
+        let render_pass = vulkano::single_pass_renderpass!(
+            graphics_queue.device().clone(),
+            attachments: {
+                color: {
+                    load: Load,
+                    store: Store,
+                    format: output_format,
+                    samples: 1,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {},
+            },
+        ).unwrap();
+
+        // egui meshes have premultiplied alpha.
+        let blend = AttachmentBlend {
+            color_op: BlendOp::Add,
+            color_source: BlendFactor::One,
+            color_destination: BlendFactor::OneMinusSrcAlpha,
+            alpha_op: BlendOp::Add,
+            alpha_source: BlendFactor::OneMinusDstAlpha,
+            alpha_destination: BlendFactor::One,
+        };
+        let pipeline = {
+            let vs = vs::load(graphics_queue.device().clone()).expect("failed to create shader module");
+            let fs = fs::load(graphics_queue.device().clone()).expect("failed to create shader module");
+            GraphicsPipeline::start()
+                .vertex_input_state(GuiVertex::per_vertex())
+                .vertex_shader(vs.entry_point("main").unwrap(), ())
+                .input_assembly_state(InputAssemblyState::new())
+                .fragment_shader(fs.entry_point("main").unwrap(), ())
+                .rasterization_state(RasterizationState::new().cull_mode(CullMode::None))
+                .color_blend_state(ColorBlendState::new(1).blend(blend))
+                .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+                .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
+                .build(graphics_queue.device().clone())
+                .unwrap()
+        };
+
+        let memory_allocator = app.context.memory_allocator().clone();
+        RenderPassGui {
+            gfx_queue: graphics_queue,
+            render_pass,
+            pipeline,
+            command_buffer_allocator: app.command_buffer_allocator.clone(),
+            descriptor_set_allocator: app.descriptor_set_allocator.clone(),
+            mesh_buffer_allocator: SubbufferAllocator::new(
+                memory_allocator.clone(),
+                SubbufferAllocatorCreateInfo {
+                    buffer_usage: BufferUsage::VERTEX_BUFFER | BufferUsage::INDEX_BUFFER,
+                    ..Default::default()
+                },
+            ),
+            memory_allocator,
+            textures: HashMap::new(),
+            linear_output: output_format.type_color() == Some(NumericType::SRGB),
+        }
+    }
+
+    /// Applies the texture changes and draws `primitives`, which are in points of
+    /// `pixels_per_point` pixels. Textures egui freed are dropped after the frame.
+    pub fn render<F>(
+        &mut self,
+        before_future: F,
+        target: SwapchainImageView,
+        primitives: &[ClippedPrimitive],
+        textures_delta: &TexturesDelta,
+        pixels_per_point: f32,
+    ) -> Box<dyn GpuFuture> where F: GpuFuture + 'static {
+        let img_dims = target.image().dimensions().width_height();
+        let framebuffer = Framebuffer::new(
+            self.render_pass.clone(),
+            FramebufferCreateInfo {
+                attachments: vec![target],
+                ..Default::default()
+            },
+        ).unwrap();
+        let mut command_buffer_builder = AutoCommandBufferBuilder::primary(&self.command_buffer_allocator, self.gfx_queue.queue_family_index(), CommandBufferUsage::OneTimeSubmit).unwrap();
+
+        for (id, delta) in &textures_delta.set {
+            self.update_texture(*id, delta, &mut command_buffer_builder);
+        }
+
+        command_buffer_builder.begin_render_pass(
+            RenderPassBeginInfo {
+                clear_values: vec![None],
+                ..RenderPassBeginInfo::framebuffer(framebuffer)
+            },
+            SubpassContents::Inline,
+        ).unwrap();
+
+        let push_constants = vs::PushConstants {
+            screen_size: [img_dims[0] as f32 / pixels_per_point, img_dims[1] as f32 / pixels_per_point],
+            linear_output: self.linear_output as u32,
+        };
+        command_buffer_builder
+            .set_viewport(
+                0,
+                [Viewport {
+                    origin: [0.0, 0.0],
+                    dimensions: [img_dims[0] as f32, img_dims[1] as f32],
+                    depth_range: 0.0..1.0,
+                }],
+            )
+            .bind_pipeline_graphics(self.pipeline.clone())
+            .push_constants(self.pipeline.layout().clone(), 0, push_constants);
+
+        for ClippedPrimitive { clip_rect, primitive } in primitives {
+            // Paint callbacks are for custom rendering, which the panel does not use.
+            let Primitive::Mesh(mesh) = primitive else {
+                continue;
+            };
+            let Some(texture) = self.textures.get(&mesh.texture_id) else {
+                continue;
+            };
+            if mesh.indices.is_empty() {
+                continue;
+            }
+
+            // The clip rectangle is in points, the scissor in pixels inside the frame.
+            let min = [clip_rect.min.x, clip_rect.min.y].map(|value| (value * pixels_per_point).round().max(0.0) as u32);
+            let max = [
+                ((clip_rect.max.x * pixels_per_point).round().max(0.0) as u32).min(img_dims[0]),
+                ((clip_rect.max.y * pixels_per_point).round().max(0.0) as u32).min(img_dims[1]),
+            ];
+            if max[0] <= min[0] || max[1] <= min[1] {
+                continue;
+            }
+
+            let vertices = self.mesh_buffer_allocator.allocate_slice::<GuiVertex>(mesh.vertices.len() as u64).unwrap();
+            for (target, vertex) in vertices.write().unwrap().iter_mut().zip(&mesh.vertices) {
+                *target = GuiVertex {
+                    position: [vertex.pos.x, vertex.pos.y],
+                    tex_coords: [vertex.uv.x, vertex.uv.y],
+                    colour: vertex.color.to_array(),
+                };
+            }
+            let indices = self.mesh_buffer_allocator.allocate_slice::<u32>(mesh.indices.len() as u64).unwrap();
+            indices.write().unwrap().copy_from_slice(&mesh.indices);
+
+            command_buffer_builder
+                .set_scissor(0, [Scissor { origin: min, dimensions: [max[0] - min[0], max[1] - min[1]] }])
+                .bind_descriptor_sets(
+                    PipelineBindPoint::Graphics,
+                    self.pipeline.layout().clone(),
+                    0,
+                    texture.descriptor_set.clone(),
+                )
+                .bind_vertex_buffers(0, vertices)
+                .bind_index_buffer(indices)
+                .draw_indexed(mesh.indices.len() as u32, 1, 0, 0, 0)
+                .unwrap();
+        }
+
+        command_buffer_builder.end_render_pass().unwrap();
+        let command_buffer = command_buffer_builder.build().unwrap();
+
+        // The command buffer keeps freed textures alive until it has run.
+        for id in &textures_delta.free {
+            self.textures.remove(id);
+        }
+
+        before_future.then_execute(self.gfx_queue.clone(), command_buffer).unwrap().boxed()
+    }
+
+    /// Creates the texture for a whole image, or copies a partial update into the existing one.
+    fn update_texture(
+        &mut self,
+        id: TextureId,
+        delta: &ImageDelta,
+        command_buffer_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer, Arc<StandardCommandBufferAllocator>>,
+    ) {
+        let (size, pixels): ([usize; 2], Vec<u8>) = match &delta.image {
+            ImageData::Color(image) => (image.size, image.pixels.iter().flat_map(|colour| colour.to_array()).collect()),
+            ImageData::Font(image) => (image.size, image.srgba_pixels(None).flat_map(|colour| colour.to_array()).collect()),
+        };
+        let [width, height] = size.map(|length| length as u32);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let image = match delta.pos {
+            Some(_) => match self.textures.get(&id) {
+                Some(texture) => texture.image.clone(),
+                None => return,
+            },
+            None => {
+                let texture = self.create_texture([width, height], delta);
+                let image = texture.image.clone();
+                self.textures.insert(id, texture);
+                image
+            }
+        };
+
+        let staging_buffer = Buffer::from_iter(
+            &self.memory_allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Upload,
+                ..Default::default()
+            },
+            pixels,
+        ).unwrap();
+
+        let [x, y] = delta.pos.unwrap_or([0, 0]).map(|offset| offset as u32);
+        command_buffer_builder.copy_buffer_to_image(CopyBufferToImageInfo {
+            regions: [BufferImageCopy {
+                image_subresource: image.subresource_layers(),
+                image_offset: [x, y, 0],
+                image_extent: [width, height, 1],
+                ..Default::default()
+            }].into(),
+            ..CopyBufferToImageInfo::buffer_image(staging_buffer, image)
+        }).unwrap();
+    }
+
+    fn create_texture(&self, size: [u32; 2], delta: &ImageDelta) -> GuiTexture {
+        let image = StorageImage::with_usage(
+            &self.memory_allocator,
+            ImageDimensions::Dim2d {
+                width: size[0],
+                height: size[1],
+                array_layers: 1,
+            },
+            Format::R8G8B8A8_UNORM,
+            ImageUsage::SAMPLED | ImageUsage::TRANSFER_DST,
+            ImageCreateFlags::empty(),
+            [self.gfx_queue.queue_family_index()],
+        ).unwrap();
+
+        let filter = |filter| match filter {
+            TextureFilter::Nearest => Filter::Nearest,
+            TextureFilter::Linear => Filter::Linear,
+        };
+        let sampler = Sampler::new(
+            self.gfx_queue.device().clone(),
+            SamplerCreateInfo {
+                mag_filter: filter(delta.options.magnification),
+                min_filter: filter(delta.options.minification),
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                mipmap_mode: SamplerMipmapMode::Nearest,
+                ..Default::default()
+            },
+        ).unwrap();
+
+        let layout = self.pipeline.layout().set_layouts().first().unwrap();
+        let descriptor_set = PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            layout.clone(),
+            [WriteDescriptorSet::image_view_sampler(0, ImageView::new_default(image.clone()).unwrap(), sampler)],
+        ).unwrap();
+
+        GuiTexture { image, descriptor_set }
+    }
+}
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: r"
+            #version 450
+            layout(location = 0) in vec2 position;
+            layout(location = 1) in vec2 tex_coords;
+            layout(location = 2) in vec4 colour;
+
+            layout(location = 0) out vec4 v_colour;
+            layout(location = 1) out vec2 v_tex_coords;
+
+            layout(push_constant) uniform PushConstants {
+                vec2 screen_size;
+                uint linear_output;
+            } push_constants;
+
+            vec3 srgb_to_linear(in vec3 srgb) {
+                return mix(pow((srgb + 0.055) / 1.055, vec3(2.4)), srgb / 12.92, lessThan(srgb, vec3(0.04045)));
+            }
+
+            // Positions are in points from the top left corner.
+            void main() {
+                gl_Position = vec4(2.0 * position / push_constants.screen_size - 1.0, 0.0, 1.0);
+                v_colour = push_constants.linear_output != 0 ? vec4(srgb_to_linear(colour.rgb), colour.a) : colour;
+                v_tex_coords = tex_coords;
+            }
+        ",
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: r"
+            #version 450
+            layout(location = 0) in vec4 v_colour;
+            layout(location = 1) in vec2 v_tex_coords;
+
+            layout(location = 0) out vec4 f_color;
+
+            layout(set = 0, binding = 0) uniform sampler2D tex;
+
+            layout(push_constant) uniform PushConstants {
+                vec2 screen_size;
+                uint linear_output;
+            } push_constants;
+
+            vec3 srgb_to_linear(in vec3 srgb) {
+                return mix(pow((srgb + 0.055) / 1.055, vec3(2.4)), srgb / 12.92, lessThan(srgb, vec3(0.04045)));
+            }
+
+            void main() {
+                vec4 texel = texture(tex, v_tex_coords);
+                if (push_constants.linear_output != 0) {
+                    texel.rgb = srgb_to_linear(texel.rgb);
+                }
+                f_color = v_colour * texel;
+            }
+        ",
+    }
+}
@@ -0,0 +1,1266 @@
+use serde::{Deserialize, Serialize};
+use vulkano::buffer::BufferContents;
+use crate::timeline::Timeline;
+
+/// Shape identifiers shared with `scene.glsl`. Keep these in sync with the `PRIMITIVE_*` defines.
+pub const PRIMITIVE_EQUILATERAL_TRIANGLE: u32 = 0;
+pub const PRIMITIVE_CIRCLE: u32 = 1;
+pub const PRIMITIVE_BOX: u32 = 2;
+pub const PRIMITIVE_ROUNDED_BOX: u32 = 3;
+pub const PRIMITIVE_SEGMENT: u32 = 4;
+pub const PRIMITIVE_ARC: u32 = 5;
+pub const PRIMITIVE_ELLIPSE: u32 = 6;
+pub const PRIMITIVE_REGULAR_POLYGON: u32 = 7;
+pub const PRIMITIVE_STAR: u32 = 8;
+pub const PRIMITIVE_HEXAGON: u32 = 9;
+pub const PRIMITIVE_VESICA: u32 = 10;
+pub const PRIMITIVE_QUADRATIC_BEZIER: u32 = 11;
+pub const PRIMITIVE_POLYGON: u32 = 12;
+
+/// Solid identifiers shared with `scene.glsl`, every kind from `PRIMITIVE_SPHERE` up is a 3D primitive.
+pub const PRIMITIVE_SPHERE: u32 = 20;
+pub const PRIMITIVE_BOX_3D: u32 = 21;
+pub const PRIMITIVE_ROUND_BOX_3D: u32 = 22;
+pub const PRIMITIVE_TORUS: u32 = 23;
+pub const PRIMITIVE_CAPSULE: u32 = 24;
+pub const PRIMITIVE_CYLINDER: u32 = 25;
+pub const PRIMITIVE_CONE: u32 = 26;
+pub const PRIMITIVE_PLANE: u32 = 27;
+pub const PRIMITIVE_OCTAHEDRON: u32 = 28;
+
+/// Operator identifiers shared with `scene.glsl`. Binary operators combine the two
+/// distances on top of the shader stack, modifiers rewrite the top distance.
+pub const OPERATOR_UNION: u32 = 100;
+pub const OPERATOR_INTERSECTION: u32 = 101;
+pub const OPERATOR_SUBTRACTION: u32 = 102;
+pub const OPERATOR_SMOOTH_UNION: u32 = 103;
+pub const OPERATOR_SMOOTH_INTERSECTION: u32 = 104;
+pub const OPERATOR_SMOOTH_SUBTRACTION: u32 = 105;
+pub const OPERATOR_ONION: u32 = 110;
+pub const OPERATOR_ROUND: u32 = 111;
+pub const OPERATOR_ANNULAR: u32 = 112;
+
+/// Only applied by the raymarched view, the 2D view shows the unextruded shape at z = 0.
+pub const OPERATOR_EXTRUDE: u32 = 113;
+
+/// Tags the top distance with the material ID in its header, the distance itself is unchanged.
+pub const OPERATOR_MATERIAL: u32 = 114;
+
+/// Material ID of distances no material node applies to, others index the material table.
+pub const NO_MATERIAL: u32 = u32::MAX;
+
+/// Procedural pattern identifiers shared with `material.glsl`.
+pub const PATTERN_NONE: u32 = 0;
+pub const PATTERN_CHECKER: u32 = 1;
+pub const PATTERN_STRIPES: u32 = 2;
+pub const PATTERN_NOISE: u32 = 3;
+
+/// Domain operator identifiers shared with `scene.glsl`. A domain node transforms the point
+/// for every node encoded between it and the matching `DOMAIN_END`.
+pub const DOMAIN_TRANSLATE: u32 = 200;
+pub const DOMAIN_ROTATE: u32 = 201;
+pub const DOMAIN_SCALE: u32 = 202;
+pub const DOMAIN_REPEAT: u32 = 203;
+pub const DOMAIN_REPEAT_LIMITED: u32 = 204;
+pub const DOMAIN_POLAR_REPEAT: u32 = 205;
+pub const DOMAIN_MIRROR: u32 = 206;
+pub const DOMAIN_TWIST: u32 = 207;
+pub const DOMAIN_WARP: u32 = 208;
+pub const DOMAIN_REVOLVE: u32 = 209;
+pub const DOMAIN_END: u32 = 255;
+
+/// Size of the distance stack in `scene.glsl`, deeper trees are rejected on validation.
+pub const MAX_STACK_DEPTH: usize = 16;
+
+/// Size of the domain stack in `scene.glsl`, limits how many domain operators can be nested.
+pub const MAX_DOMAIN_DEPTH: usize = 16;
+
+/// Most layers of an onion. Every evaluation loops over them, and past this the halved
+/// thickness of the innermost outlines is far below a pixel.
+pub const MAX_ONION_LAYERS: u32 = 16;
+
+/// Half depth of the slab that 2D shapes become when they are combined with 3D nodes,
+/// or when the whole scene is 2D and viewed in 3D.
+pub const PLANAR_EXTRUSION_HALF_DEPTH: f32 = 0.25;
+
+/// A parameter that was rejected while validating a scene.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InvalidParameter {
+    pub parameter: &'static str,
+    pub reason: &'static str,
+}
+
+fn require_finite(parameter: &'static str, value: f32) -> Result<(), InvalidParameter> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(InvalidParameter { parameter, reason: "must be a finite number" })
+    }
+}
+
+fn require_positive(parameter: &'static str, value: f32) -> Result<(), InvalidParameter> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(InvalidParameter { parameter, reason: "must be greater than zero" })
+    }
+}
+
+fn require_non_negative(parameter: &'static str, value: f32) -> Result<(), InvalidParameter> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(())
+    } else {
+        Err(InvalidParameter { parameter, reason: "must be zero or greater" })
+    }
+}
+
+fn require_finite_point(parameter: &'static str, point: [f32; 2]) -> Result<(), InvalidParameter> {
+    require_finite(parameter, point[0])?;
+    require_finite(parameter, point[1])
+}
+
+fn require_colour(parameter: &'static str, colour: [f32; 3]) -> Result<(), InvalidParameter> {
+    if colour.iter().all(|channel| (0.0..=1.0).contains(channel)) {
+        Ok(())
+    } else {
+        Err(InvalidParameter { parameter, reason: "must have every channel between 0 and 1" })
+    }
+}
+
+fn require_unit_interval(parameter: &'static str, value: f32) -> Result<(), InvalidParameter> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(InvalidParameter { parameter, reason: "must be between 0 and 1" })
+    }
+}
+
+fn require_finite_point_3d(parameter: &'static str, point: [f32; 3]) -> Result<(), InvalidParameter> {
+    point.iter().try_for_each(|value| require_finite(parameter, *value))
+}
+
+fn require_positive_3d(parameter: &'static str, point: [f32; 3]) -> Result<(), InvalidParameter> {
+    point.iter().try_for_each(|value| require_positive(parameter, *value))
+}
+
+/// Placement of a primitive in the scene. Rotation is in radians, scale is uniform.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform2D {
+    pub translation: [f32; 2],
+    pub rotation: f32,
+    pub scale: f32,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Transform2D {
+            translation: [0.0, 0.0],
+            rotation: 0.0,
+            scale: 1.0,
+        }
+    }
+}
+
+impl Transform2D {
+    pub fn validate(&self) -> Result<(), InvalidParameter> {
+        require_finite("translation", self.translation[0])?;
+        require_finite("translation", self.translation[1])?;
+        require_finite("rotation", self.rotation)?;
+        require_positive("scale", self.scale)
+    }
+}
+
+/// Placement of a 3D primitive. Rotation is in radians around x, then y, then z, scale is uniform.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform3D {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 3],
+    pub scale: f32,
+}
+
+impl Default for Transform3D {
+    fn default() -> Self {
+        Transform3D {
+            translation: [0.0; 3],
+            rotation: [0.0; 3],
+            scale: 1.0,
+        }
+    }
+}
+
+impl Transform3D {
+    pub fn validate(&self) -> Result<(), InvalidParameter> {
+        require_finite_point_3d("translation", self.translation)?;
+        require_finite_point_3d("rotation", self.rotation)?;
+        require_positive("scale", self.scale)
+    }
+
+    /// The rotation as a unit quaternion, vector part first.
+    fn quaternion(&self) -> [f32; 4] {
+        let [(sx, cx), (sy, cy), (sz, cz)] = self.rotation.map(|angle| (angle * 0.5).sin_cos());
+        [
+            sx * cy * cz - cx * sy * sz,
+            cx * sy * cz + sx * cy * sz,
+            cx * cy * sz - sx * sy * cz,
+            cx * cy * cz + sx * sy * sz,
+        ]
+    }
+}
+
+/// Every 2D primitive implemented in `sdf_2d.glsl`. Shapes are centred on the origin of their
+/// local space, the primitive transform moves them into place.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Shape {
+    EquilateralTriangle { radius: f32 },
+    Circle { radius: f32 },
+    Box { half_extents: [f32; 2] },
+
+    /// Corner radii are ordered top right, bottom right, top left, bottom left.
+    RoundedBox { half_extents: [f32; 2], corner_radii: [f32; 4] },
+    Segment { start: [f32; 2], end: [f32; 2], thickness: f32 },
+
+    /// Arc around the y axis spanning `aperture` radians to each side.
+    Arc { aperture: f32, radius: f32, thickness: f32 },
+    Ellipse { radii: [f32; 2] },
+    RegularPolygon { radius: f32, sides: u32 },
+
+    /// `density` is between 2 and `points`, lower values give sharper tips.
+    Star { radius: f32, points: u32, density: f32 },
+    Hexagon { radius: f32 },
+
+    /// Intersection of two circles of `radius` whose centres are `offset` away from the origin.
+    Vesica { radius: f32, offset: f32 },
+    QuadraticBezier { start: [f32; 2], control: [f32; 2], end: [f32; 2], thickness: f32 },
+    Polygon { vertices: Vec<[f32; 2]> },
+}
+
+impl Shape {
+    pub fn validate(&self) -> Result<(), InvalidParameter> {
+        match self {
+            Shape::EquilateralTriangle { radius } => require_positive("radius", *radius),
+            Shape::Circle { radius } => require_positive("radius", *radius),
+            Shape::Box { half_extents } => {
+                require_positive("half_extents", half_extents[0])?;
+                require_positive("half_extents", half_extents[1])
+            }
+            Shape::RoundedBox { half_extents, corner_radii } => {
+                require_positive("half_extents", half_extents[0])?;
+                require_positive("half_extents", half_extents[1])?;
+                let max_radius = half_extents[0].min(half_extents[1]);
+                for radius in corner_radii {
+                    require_non_negative("corner_radii", *radius)?;
+                    if *radius > max_radius {
+                        return Err(InvalidParameter { parameter: "corner_radii", reason: "must not exceed the smallest half extent" });
+                    }
+                }
+                Ok(())
+            }
+            Shape::Segment { start, end, thickness } => {
+                require_finite_point("start", *start)?;
+                require_finite_point("end", *end)?;
+                if start == end {
+                    return Err(InvalidParameter { parameter: "end", reason: "must differ from `start`" });
+                }
+                require_non_negative("thickness", *thickness)
+            }
+            Shape::Arc { aperture, radius, thickness } => {
+                if !(*aperture > 0.0 && *aperture <= std::f32::consts::PI) {
+                    return Err(InvalidParameter { parameter: "aperture", reason: "must be in the range (0, pi]" });
+                }
+                require_positive("radius", *radius)?;
+                require_non_negative("thickness", *thickness)
+            }
+            Shape::Ellipse { radii } => {
+                require_positive("radii", radii[0])?;
+                require_positive("radii", radii[1])
+            }
+            Shape::RegularPolygon { radius, sides } => {
+                require_positive("radius", *radius)?;
+                if *sides < 3 {
+                    return Err(InvalidParameter { parameter: "sides", reason: "must be at least 3" });
+                }
+                Ok(())
+            }
+            Shape::Star { radius, points, density } => {
+                require_positive("radius", *radius)?;
+                if *points < 3 {
+                    return Err(InvalidParameter { parameter: "points", reason: "must be at least 3" });
+                }
+                if !(*density >= 2.0 && *density <= *points as f32) {
+                    return Err(InvalidParameter { parameter: "density", reason: "must be between 2 and `points`" });
+                }
+                Ok(())
+            }
+            Shape::Hexagon { radius } => require_positive("radius", *radius),
+            Shape::Vesica { radius, offset } => {
+                require_positive("radius", *radius)?;
+                require_non_negative("offset", *offset)?;
+                if offset >= radius {
+                    return Err(InvalidParameter { parameter: "offset", reason: "must be smaller than `radius`" });
+                }
+                Ok(())
+            }
+            Shape::QuadraticBezier { start, control, end, thickness } => {
+                require_finite_point("start", *start)?;
+                require_finite_point("control", *control)?;
+                require_finite_point("end", *end)?;
+                if start == end {
+                    return Err(InvalidParameter { parameter: "end", reason: "must differ from `start`" });
+                }
+                require_non_negative("thickness", *thickness)
+            }
+            Shape::Polygon { vertices } => {
+                if vertices.len() < 3 {
+                    return Err(InvalidParameter { parameter: "vertices", reason: "must contain at least 3 points" });
+                }
+                for vertex in vertices {
+                    require_finite_point("vertices", *vertex)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the primitive header and packed parameters for this shape. Polygon vertices
+    /// are appended to `vertices` and referenced from the header.
+    fn encode(&self, vertices: &mut Vec<[f32; 2]>) -> ([u32; 4], [[f32; 4]; 2]) {
+        match self {
+            Shape::EquilateralTriangle { radius } => {
+                ([PRIMITIVE_EQUILATERAL_TRIANGLE, 0, 0, 0], [[*radius, 0.0, 0.0, 0.0], [0.0; 4]])
+            }
+            Shape::Circle { radius } => {
+                ([PRIMITIVE_CIRCLE, 0, 0, 0], [[*radius, 0.0, 0.0, 0.0], [0.0; 4]])
+            }
+            Shape::Box { half_extents } => {
+                ([PRIMITIVE_BOX, 0, 0, 0], [[half_extents[0], half_extents[1], 0.0, 0.0], [0.0; 4]])
+            }
+            Shape::RoundedBox { half_extents, corner_radii } => {
+                ([PRIMITIVE_ROUNDED_BOX, 0, 0, 0], [[half_extents[0], half_extents[1], 0.0, 0.0], *corner_radii])
+            }
+            Shape::Segment { start, end, thickness } => {
+                ([PRIMITIVE_SEGMENT, 0, 0, 0], [[start[0], start[1], end[0], end[1]], [*thickness, 0.0, 0.0, 0.0]])
+            }
+            Shape::Arc { aperture, radius, thickness } => {
+                ([PRIMITIVE_ARC, 0, 0, 0], [[*aperture, *radius, *thickness, 0.0], [0.0; 4]])
+            }
+            Shape::Ellipse { radii } => {
+                ([PRIMITIVE_ELLIPSE, 0, 0, 0], [[radii[0], radii[1], 0.0, 0.0], [0.0; 4]])
+            }
+            Shape::RegularPolygon { radius, sides } => {
+                ([PRIMITIVE_REGULAR_POLYGON, 0, 0, *sides], [[*radius, 0.0, 0.0, 0.0], [0.0; 4]])
+            }
+            Shape::Star { radius, points, density } => {
+                ([PRIMITIVE_STAR, 0, 0, *points], [[*radius, *density, 0.0, 0.0], [0.0; 4]])
+            }
+            Shape::Hexagon { radius } => {
+                ([PRIMITIVE_HEXAGON, 0, 0, 0], [[*radius, 0.0, 0.0, 0.0], [0.0; 4]])
+            }
+            Shape::Vesica { radius, offset } => {
+                ([PRIMITIVE_VESICA, 0, 0, 0], [[*radius, *offset, 0.0, 0.0], [0.0; 4]])
+            }
+            Shape::QuadraticBezier { start, control, end, thickness } => {
+                (
+                    [PRIMITIVE_QUADRATIC_BEZIER, 0, 0, 0],
+                    [[start[0], start[1], control[0], control[1]], [end[0], end[1], *thickness, 0.0]],
+                )
+            }
+            Shape::Polygon { vertices: polygon } => {
+                let first_vertex = vertices.len() as u32;
+                vertices.extend_from_slice(polygon);
+                ([PRIMITIVE_POLYGON, first_vertex, polygon.len() as u32, 0], [[0.0; 4]; 2])
+            }
+        }
+    }
+}
+
+/// Every 3D primitive implemented in `sdf_3d.glsl`. Like 2D shapes they are centred on the
+/// origin, and shapes with an axis are built around y.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Shape3D {
+    Sphere { radius: f32 },
+    Box { half_extents: [f32; 3] },
+
+    /// Box with its edges rounded by `radius`, within the same `half_extents`.
+    RoundBox { half_extents: [f32; 3], radius: f32 },
+
+    /// Ring of `major_radius` around the y axis with a tube of `minor_radius`.
+    Torus { major_radius: f32, minor_radius: f32 },
+    Capsule { start: [f32; 3], end: [f32; 3], radius: f32 },
+    Cylinder { radius: f32, half_height: f32 },
+
+    /// Capped cone, `bottom_radius` at the bottom and `top_radius` at the top.
+    Cone { half_height: f32, bottom_radius: f32, top_radius: f32 },
+
+    /// Everything behind the plane `dot(point, normal) = offset`.
+    Plane { normal: [f32; 3], offset: f32 },
+    Octahedron { radius: f32 },
+}
+
+impl Shape3D {
+    pub fn validate(&self) -> Result<(), InvalidParameter> {
+        match self {
+            Shape3D::Sphere { radius } => require_positive("radius", *radius),
+            Shape3D::Box { half_extents } => require_positive_3d("half_extents", *half_extents),
+            Shape3D::RoundBox { half_extents, radius } => {
+                require_positive_3d("half_extents", *half_extents)?;
+                require_non_negative("radius", *radius)?;
+                if half_extents.iter().any(|half_extent| radius > half_extent) {
+                    return Err(InvalidParameter { parameter: "radius", reason: "must not exceed the smallest half extent" });
+                }
+                Ok(())
+            }
+            Shape3D::Torus { major_radius, minor_radius } => {
+                require_positive("major_radius", *major_radius)?;
+                require_positive("minor_radius", *minor_radius)
+            }
+            Shape3D::Capsule { start, end, radius } => {
+                require_finite_point_3d("start", *start)?;
+                require_finite_point_3d("end", *end)?;
+                if start == end {
+                    return Err(InvalidParameter { parameter: "end", reason: "must differ from `start`" });
+                }
+                require_positive("radius", *radius)
+            }
+            Shape3D::Cylinder { radius, half_height } => {
+                require_positive("radius", *radius)?;
+                require_positive("half_height", *half_height)
+            }
+            Shape3D::Cone { half_height, bottom_radius, top_radius } => {
+                require_positive("half_height", *half_height)?;
+                require_non_negative("bottom_radius", *bottom_radius)?;
+                require_non_negative("top_radius", *top_radius)?;
+                if *bottom_radius == 0.0 && *top_radius == 0.0 {
+                    return Err(InvalidParameter { parameter: "bottom_radius", reason: "must not be zero together with `top_radius`" });
+                }
+                Ok(())
+            }
+            Shape3D::Plane { normal, offset } => {
+                require_finite_point_3d("normal", *normal)?;
+                if normal.iter().all(|value| *value == 0.0) {
+                    return Err(InvalidParameter { parameter: "normal", reason: "must not be zero" });
+                }
+                require_finite("offset", *offset)
+            }
+            Shape3D::Octahedron { radius } => require_positive("radius", *radius),
+        }
+    }
+
+    /// Returns the primitive header and packed parameters for this solid.
+    fn encode(&self) -> ([u32; 4], [[f32; 4]; 2]) {
+        match self {
+            Shape3D::Sphere { radius } => {
+                ([PRIMITIVE_SPHERE, 0, 0, 0], [[*radius, 0.0, 0.0, 0.0], [0.0; 4]])
+            }
+            Shape3D::Box { half_extents } => {
+                ([PRIMITIVE_BOX_3D, 0, 0, 0], [[half_extents[0], half_extents[1], half_extents[2], 0.0], [0.0; 4]])
+            }
+            Shape3D::RoundBox { half_extents, radius } => {
+                ([PRIMITIVE_ROUND_BOX_3D, 0, 0, 0], [[half_extents[0], half_extents[1], half_extents[2], *radius], [0.0; 4]])
+            }
+            Shape3D::Torus { major_radius, minor_radius } => {
+                ([PRIMITIVE_TORUS, 0, 0, 0], [[*major_radius, *minor_radius, 0.0, 0.0], [0.0; 4]])
+            }
+            Shape3D::Capsule { start, end, radius } => {
+                ([PRIMITIVE_CAPSULE, 0, 0, 0], [[start[0], start[1], start[2], 0.0], [end[0], end[1], end[2], *radius]])
+            }
+            Shape3D::Cylinder { radius, half_height } => {
+                ([PRIMITIVE_CYLINDER, 0, 0, 0], [[*radius, *half_height, 0.0, 0.0], [0.0; 4]])
+            }
+            Shape3D::Cone { half_height, bottom_radius, top_radius } => {
+                ([PRIMITIVE_CONE, 0, 0, 0], [[*half_height, *bottom_radius, *top_radius, 0.0], [0.0; 4]])
+            }
+            Shape3D::Plane { normal, offset } => {
+                let length = normal.iter().map(|value| value * value).sum::<f32>().sqrt();
+                ([PRIMITIVE_PLANE, 0, 0, 0], [[normal[0] / length, normal[1] / length, normal[2] / length, *offset], [0.0; 4]])
+            }
+            Shape3D::Octahedron { radius } => {
+                ([PRIMITIVE_OCTAHEDRON, 0, 0, 0], [[*radius, 0.0, 0.0, 0.0], [0.0; 4]])
+            }
+        }
+    }
+}
+
+/// Second colour blended into the albedo by position, evaluated in scene space so shapes
+/// move through it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Pattern {
+    /// Cubes of `size` alternating between the albedo and `colour`.
+    Checker { size: f32, colour: [f32; 3] },
+
+    /// Bands of `width` across `direction`.
+    Stripes { width: f32, direction: [f32; 3], colour: [f32; 3] },
+
+    /// Smooth value noise with features about `scale` across.
+    Noise { scale: f32, colour: [f32; 3] },
+}
+
+impl Pattern {
+    pub fn validate(&self) -> Result<(), InvalidParameter> {
+        match self {
+            Pattern::Checker { size, colour } => {
+                require_positive("size", *size)?;
+                require_colour("colour", *colour)
+            }
+            Pattern::Stripes { width, direction, colour } => {
+                require_positive("width", *width)?;
+                require_finite_point_3d("direction", *direction)?;
+                if direction.iter().all(|value| *value == 0.0) {
+                    return Err(InvalidParameter { parameter: "direction", reason: "must not be zero" });
+                }
+                require_colour("colour", *colour)
+            }
+            Pattern::Noise { scale, colour } => {
+                require_positive("scale", *scale)?;
+                require_colour("colour", *colour)
+            }
+        }
+    }
+
+    /// Kind, the pattern colour and its parameters. Stripe directions are normalized.
+    fn encode(&self) -> (u32, [f32; 3], [f32; 4]) {
+        match self {
+            Pattern::Checker { size, colour } => (PATTERN_CHECKER, *colour, [*size, 0.0, 0.0, 0.0]),
+            Pattern::Stripes { width, direction, colour } => {
+                let length = direction.iter().map(|value| value * value).sum::<f32>().sqrt();
+                (PATTERN_STRIPES, *colour, [*width, direction[0] / length, direction[1] / length, direction[2] / length])
+            }
+            Pattern::Noise { scale, colour } => (PATTERN_NOISE, *colour, [*scale, 0.0, 0.0, 0.0]),
+        }
+    }
+}
+
+/// Physically based surface, referenced by `name` from `Material` nodes. The 2D view uses
+/// the albedo and emission, the 3D views use all of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Material {
+    pub name: String,
+    pub albedo: [f32; 3],
+
+    /// From a mirror at 0 to fully diffuse at 1.
+    pub roughness: f32,
+
+    /// 0 for plastics and stone, 1 for metals, which tint their reflections with the albedo.
+    pub metallic: f32,
+
+    /// Light given off by the surface, channels may go above 1.
+    pub emission: [f32; 3],
+
+    /// Share of light passing through like glass, bent by `refractive_index`.
+    pub transmission: f32,
+    pub refractive_index: f32,
+    pub pattern: Option<Pattern>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            name: String::new(),
+            albedo: [0.8, 0.8, 0.8],
+            roughness: 0.5,
+            metallic: 0.0,
+            emission: [0.0, 0.0, 0.0],
+            transmission: 0.0,
+            refractive_index: 1.5,
+            pattern: None,
+        }
+    }
+}
+
+impl Material {
+    pub fn validate(&self) -> Result<(), InvalidParameter> {
+        if self.name.is_empty() {
+            return Err(InvalidParameter { parameter: "name", reason: "must not be empty" });
+        }
+        require_colour("albedo", self.albedo)?;
+        require_unit_interval("roughness", self.roughness)?;
+        require_unit_interval("metallic", self.metallic)?;
+        self.emission.iter().try_for_each(|channel| require_non_negative("emission", *channel))?;
+        require_unit_interval("transmission", self.transmission)?;
+        if !(self.refractive_index.is_finite() && self.refractive_index >= 1.0) {
+            return Err(InvalidParameter { parameter: "refractive_index", reason: "must be at least 1" });
+        }
+        match &self.pattern {
+            Some(pattern) => pattern.validate(),
+            None => Ok(()),
+        }
+    }
+
+    fn encode(&self) -> GpuMaterial {
+        let (pattern, pattern_colour, pattern_params) = match &self.pattern {
+            Some(pattern) => pattern.encode(),
+            None => (PATTERN_NONE, self.albedo, [0.0; 4]),
+        };
+        let [r, g, b] = self.albedo;
+        let [emission_r, emission_g, emission_b] = self.emission;
+
+        GpuMaterial {
+            header: [pattern, 0, 0, 0],
+            albedo: [r, g, b, 1.0],
+            emission: [emission_r, emission_g, emission_b, 0.0],
+            properties: [self.roughness, self.metallic, self.transmission, self.refractive_index],
+            pattern_colour: [pattern_colour[0], pattern_colour[1], pattern_colour[2], 0.0],
+            pattern_params,
+        }
+    }
+}
+
+/// Curve used by the smooth boolean operators to blend between two distances.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Blend {
+    #[default]
+    Polynomial,
+    Exponential,
+}
+
+impl Blend {
+    fn encode(&self) -> u32 {
+        match self {
+            Blend::Polynomial => 0,
+            Blend::Exponential => 1,
+        }
+    }
+}
+
+/// A node of the scene tree. Leaves are primitives, every other node combines or modifies
+/// the distance of its children. 2D nodes lie in the xy plane, where they are extruded
+/// into slabs when combined with 3D nodes. 2D domain operators leave z alone.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SceneNode {
+    Primitive {
+        shape: Shape,
+        #[serde(default)]
+        transform: Transform2D,
+    },
+    Primitive3D {
+        shape: Shape3D,
+        #[serde(default)]
+        transform: Transform3D,
+    },
+    Union { children: Vec<SceneNode> },
+    Intersection { children: Vec<SceneNode> },
+
+    /// Removes every following child from the first one.
+    Subtraction { children: Vec<SceneNode> },
+    SmoothUnion {
+        radius: f32,
+        #[serde(default)]
+        blend: Blend,
+        children: Vec<SceneNode>,
+    },
+    SmoothIntersection {
+        radius: f32,
+        #[serde(default)]
+        blend: Blend,
+        children: Vec<SceneNode>,
+    },
+    SmoothSubtraction {
+        radius: f32,
+        #[serde(default)]
+        blend: Blend,
+        children: Vec<SceneNode>,
+    },
+
+    /// Applies the annular modifier `layers` times, giving concentric shells.
+    Onion { thickness: f32, layers: u32, child: Box<SceneNode> },
+    Round { radius: f32, child: Box<SceneNode> },
+    Annular { thickness: f32, child: Box<SceneNode> },
+
+    /// Pulls a 2D child along z into a slab reaching `half_depth` to each side of the xy plane.
+    Extrude { half_depth: f32, child: Box<SceneNode> },
+
+    /// Sweeps a 2D child around the y axis, after moving it `radius` away from the axis.
+    Revolve { radius: f32, child: Box<SceneNode> },
+
+    /// Gives the child the scene material called `material`. Shapes inside with a material
+    /// of their own keep it.
+    Material { material: String, child: Box<SceneNode> },
+
+    Translate { offset: [f32; 2], child: Box<SceneNode> },
+    Rotate { angle: f32, child: Box<SceneNode> },
+    Scale { factor: f32, child: Box<SceneNode> },
+
+    /// Repeats the child infinitely on a grid with cells of `period` size.
+    Repeat { period: [f32; 2], child: Box<SceneNode> },
+
+    /// Repeats the child `limit` cells to each side of the origin along each axis.
+    RepeatLimited { period: [f32; 2], limit: [u32; 2], child: Box<SceneNode> },
+
+    /// Repeats the child `count` times around the origin.
+    PolarRepeat { count: u32, child: Box<SceneNode> },
+
+    /// Reflects the half plane behind `normal` onto the half plane in front of it.
+    Mirror { normal: [f32; 2], child: Box<SceneNode> },
+
+    /// Rotates the space by `strength` radians per unit of distance from the origin.
+    Twist { strength: f32, child: Box<SceneNode> },
+
+    /// Offsets the space with gradient noise, `speed` scrolls the noise over time.
+    Warp {
+        amplitude: f32,
+        frequency: f32,
+        #[serde(default)]
+        speed: f32,
+        child: Box<SceneNode>,
+    },
+}
+
+/// A node that failed validation and where it is in the tree, e.g. `nodes[0].children[2]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidNode {
+    pub location: String,
+    pub parameter: &'static str,
+    pub reason: &'static str,
+}
+
+impl SceneNode {
+    fn validate(&self, location: &str, materials: &[Material]) -> Result<(), InvalidNode> {
+        let invalid = |error: InvalidParameter| InvalidNode {
+            location: location.to_string(),
+            parameter: error.parameter,
+            reason: error.reason,
+        };
+
+        match self {
+            SceneNode::Primitive { shape, transform } => {
+                shape.validate().map_err(invalid)?;
+                transform.validate().map_err(invalid)
+            }
+            SceneNode::Primitive3D { shape, transform } => {
+                shape.validate().map_err(invalid)?;
+                transform.validate().map_err(invalid)
+            }
+            SceneNode::Union { children }
+            | SceneNode::Intersection { children }
+            | SceneNode::Subtraction { children } => {
+                validate_children(location, children, materials)
+            }
+            SceneNode::SmoothUnion { radius, children, .. }
+            | SceneNode::SmoothIntersection { radius, children, .. }
+            | SceneNode::SmoothSubtraction { radius, children, .. } => {
+                require_positive("radius", *radius).map_err(invalid)?;
+                validate_children(location, children, materials)
+            }
+            SceneNode::Onion { thickness, layers, child } => {
+                require_positive("thickness", *thickness).map_err(invalid)?;
+                if *layers == 0 || *layers > MAX_ONION_LAYERS {
+                    return Err(invalid(InvalidParameter { parameter: "layers", reason: "must be between 1 and 16" }));
+                }
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Round { radius, child } => {
+                require_non_negative("radius", *radius).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Annular { thickness, child } => {
+                require_positive("thickness", *thickness).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Extrude { half_depth, child } => {
+                require_positive("half_depth", *half_depth).map_err(invalid)?;
+                require_planar(child).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Revolve { radius, child } => {
+                require_non_negative("radius", *radius).map_err(invalid)?;
+                require_planar(child).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Material { material, child } => {
+                if !materials.iter().any(|candidate| candidate.name == *material) {
+                    return Err(invalid(InvalidParameter { parameter: "material", reason: "must name one of the scene materials" }));
+                }
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Translate { offset, child } => {
+                require_finite_point("offset", *offset).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Rotate { angle, child } => {
+                require_finite("angle", *angle).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Scale { factor, child } => {
+                require_positive("factor", *factor).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Repeat { period, child }
+            | SceneNode::RepeatLimited { period, child, .. } => {
+                require_positive("period", period[0]).map_err(invalid)?;
+                require_positive("period", period[1]).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::PolarRepeat { count, child } => {
+                if *count == 0 {
+                    return Err(invalid(InvalidParameter { parameter: "count", reason: "must be at least 1" }));
+                }
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Mirror { normal, child } => {
+                require_finite_point("normal", *normal).map_err(invalid)?;
+                if normal[0] == 0.0 && normal[1] == 0.0 {
+                    return Err(invalid(InvalidParameter { parameter: "normal", reason: "must not be zero" }));
+                }
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Twist { strength, child } => {
+                require_finite("strength", *strength).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+            SceneNode::Warp { amplitude, frequency, speed, child } => {
+                require_non_negative("amplitude", *amplitude).map_err(invalid)?;
+                require_positive("frequency", *frequency).map_err(invalid)?;
+                require_finite("speed", *speed).map_err(invalid)?;
+                child.validate(&format!("{location}.child"), materials)
+            }
+        }
+    }
+
+    /// Number of domain operators the shader has to keep open at once to evaluate this node.
+    fn domain_depth(&self) -> usize {
+        match self {
+            SceneNode::Primitive { .. }
+            | SceneNode::Primitive3D { .. } => 0,
+            SceneNode::Union { children }
+            | SceneNode::Intersection { children }
+            | SceneNode::Subtraction { children }
+            | SceneNode::SmoothUnion { children, .. }
+            | SceneNode::SmoothIntersection { children, .. }
+            | SceneNode::SmoothSubtraction { children, .. } => {
+                children.iter().map(SceneNode::domain_depth).max().unwrap_or(0)
+            }
+            SceneNode::Onion { child, .. }
+            | SceneNode::Round { child, .. }
+            | SceneNode::Annular { child, .. }
+            | SceneNode::Extrude { child, .. }
+            | SceneNode::Material { child, .. } => {
+                child.domain_depth()
+            }
+            SceneNode::Revolve { child, .. }
+            | SceneNode::Translate { child, .. }
+            | SceneNode::Rotate { child, .. }
+            | SceneNode::Scale { child, .. }
+            | SceneNode::Repeat { child, .. }
+            | SceneNode::RepeatLimited { child, .. }
+            | SceneNode::PolarRepeat { child, .. }
+            | SceneNode::Mirror { child, .. }
+            | SceneNode::Twist { child, .. }
+            | SceneNode::Warp { child, .. } => {
+                child.domain_depth() + 1
+            }
+        }
+    }
+
+    /// Number of stack slots the shader needs to evaluate this node.
+    fn stack_depth(&self) -> usize {
+        match self {
+            SceneNode::Primitive { .. }
+            | SceneNode::Primitive3D { .. } => 1,
+            SceneNode::Union { children }
+            | SceneNode::Intersection { children }
+            | SceneNode::Subtraction { children }
+            | SceneNode::SmoothUnion { children, .. }
+            | SceneNode::SmoothIntersection { children, .. }
+            | SceneNode::SmoothSubtraction { children, .. } => {
+                children_stack_depth(children)
+            }
+            SceneNode::Onion { child, .. }
+            | SceneNode::Round { child, .. }
+            | SceneNode::Annular { child, .. }
+            | SceneNode::Extrude { child, .. }
+            | SceneNode::Revolve { child, .. }
+            | SceneNode::Material { child, .. }
+            | SceneNode::Translate { child, .. }
+            | SceneNode::Rotate { child, .. }
+            | SceneNode::Scale { child, .. }
+            | SceneNode::Repeat { child, .. }
+            | SceneNode::RepeatLimited { child, .. }
+            | SceneNode::PolarRepeat { child, .. }
+            | SceneNode::Mirror { child, .. }
+            | SceneNode::Twist { child, .. }
+            | SceneNode::Warp { child, .. } => {
+                child.stack_depth()
+            }
+        }
+    }
+
+    /// Whether this node only contains 2D shapes in the xy plane.
+    fn is_planar(&self) -> bool {
+        match self {
+            SceneNode::Primitive { .. } => true,
+            SceneNode::Primitive3D { .. }
+            | SceneNode::Extrude { .. }
+            | SceneNode::Revolve { .. } => false,
+            SceneNode::Union { children }
+            | SceneNode::Intersection { children }
+            | SceneNode::Subtraction { children }
+            | SceneNode::SmoothUnion { children, .. }
+            | SceneNode::SmoothIntersection { children, .. }
+            | SceneNode::SmoothSubtraction { children, .. } => {
+                children.iter().all(SceneNode::is_planar)
+            }
+            SceneNode::Onion { child, .. }
+            | SceneNode::Round { child, .. }
+            | SceneNode::Annular { child, .. }
+            | SceneNode::Material { child, .. }
+            | SceneNode::Translate { child, .. }
+            | SceneNode::Rotate { child, .. }
+            | SceneNode::Scale { child, .. }
+            | SceneNode::Repeat { child, .. }
+            | SceneNode::RepeatLimited { child, .. }
+            | SceneNode::PolarRepeat { child, .. }
+            | SceneNode::Mirror { child, .. }
+            | SceneNode::Twist { child, .. }
+            | SceneNode::Warp { child, .. } => {
+                child.is_planar()
+            }
+        }
+    }
+
+    /// Appends this node in postfix order: children first, then the operator that consumes them.
+    fn encode(&self, materials: &[Material], nodes: &mut Vec<GpuSceneNode>, vertices: &mut Vec<[f32; 2]>) {
+        match self {
+            SceneNode::Primitive { shape, transform } => {
+                let (header, params) = shape.encode(vertices);
+                nodes.push(GpuSceneNode {
+                    header,
+                    transform: [transform.translation[0], transform.translation[1], transform.rotation, transform.scale],
+                    params,
+                    ..Default::default()
+                });
+            }
+            SceneNode::Primitive3D { shape, transform } => {
+                let (header, params) = shape.encode();
+                let [x, y, z] = transform.translation;
+                nodes.push(GpuSceneNode {
+                    header,
+                    transform: [x, y, z, transform.scale],
+                    rotation: transform.quaternion(),
+                    params,
+                });
+            }
+            SceneNode::Union { children } => {
+                encode_children(children, OPERATOR_UNION, 0, 0.0, materials, nodes, vertices);
+            }
+            SceneNode::Intersection { children } => {
+                encode_children(children, OPERATOR_INTERSECTION, 0, 0.0, materials, nodes, vertices);
+            }
+            SceneNode::Subtraction { children } => {
+                encode_children(children, OPERATOR_SUBTRACTION, 0, 0.0, materials, nodes, vertices);
+            }
+            SceneNode::SmoothUnion { radius, blend, children } => {
+                encode_children(children, OPERATOR_SMOOTH_UNION, blend.encode(), *radius, materials, nodes, vertices);
+            }
+            SceneNode::SmoothIntersection { radius, blend, children } => {
+                encode_children(children, OPERATOR_SMOOTH_INTERSECTION, blend.encode(), *radius, materials, nodes, vertices);
+            }
+            SceneNode::SmoothSubtraction { radius, blend, children } => {
+                encode_children(children, OPERATOR_SMOOTH_SUBTRACTION, blend.encode(), *radius, materials, nodes, vertices);
+            }
+            SceneNode::Onion { thickness, layers, child } => {
+                child.encode(materials, nodes, vertices);
+                nodes.push(GpuSceneNode::operator(OPERATOR_ONION, *layers, *thickness));
+            }
+            SceneNode::Round { radius, child } => {
+                child.encode(materials, nodes, vertices);
+                nodes.push(GpuSceneNode::operator(OPERATOR_ROUND, 0, *radius));
+            }
+            SceneNode::Annular { thickness, child } => {
+                child.encode(materials, nodes, vertices);
+                nodes.push(GpuSceneNode::operator(OPERATOR_ANNULAR, 0, *thickness));
+            }
+            SceneNode::Extrude { half_depth, child } => {
+                child.encode(materials, nodes, vertices);
+                nodes.push(GpuSceneNode::operator(OPERATOR_EXTRUDE, 0, *half_depth));
+            }
+            SceneNode::Material { material, child } => {
+                child.encode(materials, nodes, vertices);
+                let id = materials.iter().position(|candidate| candidate.name == *material).unwrap();
+                nodes.push(GpuSceneNode {
+                    header: [OPERATOR_MATERIAL, id as u32, 0, 0],
+                    ..Default::default()
+                });
+            }
+            SceneNode::Revolve { radius, child } => {
+                encode_domain(GpuSceneNode::domain(DOMAIN_REVOLVE, 0, [*radius, 0.0, 0.0, 0.0]), child, materials, nodes, vertices);
+            }
+            SceneNode::Translate { offset, child } => {
+                encode_domain(GpuSceneNode::domain(DOMAIN_TRANSLATE, 0, [offset[0], offset[1], 0.0, 0.0]), child, materials, nodes, vertices);
+            }
+            SceneNode::Rotate { angle, child } => {
+                encode_domain(GpuSceneNode::domain(DOMAIN_ROTATE, 0, [*angle, 0.0, 0.0, 0.0]), child, materials, nodes, vertices);
+            }
+            SceneNode::Scale { factor, child } => {
+                encode_domain(GpuSceneNode::domain(DOMAIN_SCALE, 0, [*factor, 0.0, 0.0, 0.0]), child, materials, nodes, vertices);
+            }
+            SceneNode::Repeat { period, child } => {
+                encode_domain(GpuSceneNode::domain(DOMAIN_REPEAT, 0, [period[0], period[1], 0.0, 0.0]), child, materials, nodes, vertices);
+            }
+            SceneNode::RepeatLimited { period, limit, child } => {
+                let params = [period[0], period[1], limit[0] as f32, limit[1] as f32];
+                encode_domain(GpuSceneNode::domain(DOMAIN_REPEAT_LIMITED, 0, params), child, materials, nodes, vertices);
+            }
+            SceneNode::PolarRepeat { count, child } => {
+                encode_domain(GpuSceneNode::domain(DOMAIN_POLAR_REPEAT, *count, [0.0; 4]), child, materials, nodes, vertices);
+            }
+            SceneNode::Mirror { normal, child } => {
+                let length = (normal[0] * normal[0] + normal[1] * normal[1]).sqrt();
+                let params = [normal[0] / length, normal[1] / length, 0.0, 0.0];
+                encode_domain(GpuSceneNode::domain(DOMAIN_MIRROR, 0, params), child, materials, nodes, vertices);
+            }
+            SceneNode::Twist { strength, child } => {
+                encode_domain(GpuSceneNode::domain(DOMAIN_TWIST, 0, [*strength, 0.0, 0.0, 0.0]), child, materials, nodes, vertices);
+            }
+            SceneNode::Warp { amplitude, frequency, speed, child } => {
+                let params = [*amplitude, *frequency, *speed, 0.0];
+                encode_domain(GpuSceneNode::domain(DOMAIN_WARP, 0, params), child, materials, nodes, vertices);
+            }
+        }
+    }
+}
+
+/// Encodes `child` bracketed by a domain node and the `DOMAIN_END` that restores the point.
+fn encode_domain(
+    domain: GpuSceneNode,
+    child: &SceneNode,
+    materials: &[Material],
+    nodes: &mut Vec<GpuSceneNode>,
+    vertices: &mut Vec<[f32; 2]>,
+) {
+    nodes.push(domain);
+    child.encode(materials, nodes, vertices);
+    nodes.push(GpuSceneNode::domain(DOMAIN_END, 0, [0.0; 4]));
+}
+
+fn require_planar(child: &SceneNode) -> Result<(), InvalidParameter> {
+    if child.is_planar() {
+        Ok(())
+    } else {
+        Err(InvalidParameter { parameter: "child", reason: "must only contain 2D nodes" })
+    }
+}
+
+fn validate_children(location: &str, children: &[SceneNode], materials: &[Material]) -> Result<(), InvalidNode> {
+    if children.is_empty() {
+        return Err(InvalidNode {
+            location: location.to_string(),
+            parameter: "children",
+            reason: "must contain at least one node",
+        });
+    }
+
+    for (index, child) in children.iter().enumerate() {
+        child.validate(&format!("{location}.children[{index}]"), materials)?;
+    }
+    Ok(())
+}
+
+/// The first child keeps its result on the stack while every later child is evaluated above it.
+fn children_stack_depth(children: &[SceneNode]) -> usize {
+    children.iter()
+        .enumerate()
+        .map(|(index, child)| child.stack_depth() + index.min(1))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Encodes `children` folded left to right with a binary operator, `a b op c op ...`.
+/// 2D children with 3D siblings are extruded so both have a depth.
+fn encode_children(
+    children: &[SceneNode],
+    operator: u32,
+    blend: u32,
+    radius: f32,
+    materials: &[Material],
+    nodes: &mut Vec<GpuSceneNode>,
+    vertices: &mut Vec<[f32; 2]>,
+) {
+    let extrude_planar = !children.iter().all(SceneNode::is_planar);
+    for (index, child) in children.iter().enumerate() {
+        child.encode(materials, nodes, vertices);
+        if extrude_planar && child.is_planar() {
+            nodes.push(GpuSceneNode::operator(OPERATOR_EXTRUDE, 0, PLANAR_EXTRUSION_HALF_DEPTH));
+        }
+        if index > 0 {
+            nodes.push(GpuSceneNode::operator(operator, blend, radius));
+        }
+    }
+}
+
+/// The top level nodes of a scene, evaluated by the compute shader as the union of their distances.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub nodes: Vec<SceneNode>,
+
+    /// Material table, `Material` nodes refer to entries by name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub materials: Vec<Material>,
+
+    /// Keyframes of scene numbers and shader parameters over the scene time.
+    #[serde(default, skip_serializing_if = "Timeline::is_empty")]
+    pub timeline: Timeline,
+}
+
+impl Scene {
+    pub fn validate(&self) -> Result<(), InvalidNode> {
+        for (index, material) in self.materials.iter().enumerate() {
+            let location = format!("materials[{index}]");
+            material.validate().map_err(|error| InvalidNode {
+                location: location.clone(),
+                parameter: error.parameter,
+                reason: error.reason,
+            })?;
+            if self.materials[..index].iter().any(|other| other.name == material.name) {
+                return Err(InvalidNode { location, parameter: "name", reason: "is used by an earlier material" });
+            }
+        }
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            node.validate(&format!("nodes[{index}]"), &self.materials)?;
+        }
+
+        if children_stack_depth(&self.nodes) > MAX_STACK_DEPTH {
+            return Err(InvalidNode {
+                location: "nodes".to_string(),
+                parameter: "children",
+                reason: "the tree is nested too deeply for the shader stack",
+            });
+        }
+
+        if self.nodes.iter().map(SceneNode::domain_depth).max().unwrap_or(0) > MAX_DOMAIN_DEPTH {
+            return Err(InvalidNode {
+                location: "nodes".to_string(),
+                parameter: "children",
+                reason: "too many nested domain operators for the shader",
+            });
+        }
+
+        self.timeline.validate(self)
+    }
+
+    /// Packs the scene into the postfix program read by `scene_signed_distance_function` in `scene.glsl`.
+    pub fn encode(&self) -> EncodedScene {
+        let mut nodes = Vec::new();
+        let mut vertices = Vec::new();
+        encode_children(&self.nodes, OPERATOR_UNION, 0, 0.0, &self.materials, &mut nodes, &mut vertices);
+        // An empty scene leaves nothing on the stack to extrude.
+        if !self.nodes.is_empty() && self.nodes.iter().all(SceneNode::is_planar) {
+            nodes.push(GpuSceneNode::operator(OPERATOR_EXTRUDE, 0, PLANAR_EXTRUSION_HALF_DEPTH));
+        }
+
+        EncodedScene {
+            nodes,
+            vertices,
+            materials: self.materials.iter().map(Material::encode).collect(),
+        }
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Scene {
+            nodes: vec![
+                SceneNode::Primitive {
+                    shape: Shape::EquilateralTriangle { radius: 1.0 },
+                    transform: Transform2D::default(),
+                }
+            ],
+            materials: Vec::new(),
+            timeline: Timeline::default(),
+        }
+    }
+}
+
+/// Scene data ready to be uploaded into the compute shader storage buffers.
+pub struct EncodedScene {
+    pub nodes: Vec<GpuSceneNode>,
+    pub vertices: Vec<[f32; 2]>,
+    pub materials: Vec<GpuMaterial>,
+}
+
+/// GPU side representation of a single scene node, matches std430 layout.
+#[derive(BufferContents, Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct GpuSceneNode {
+    pub header: [u32; 4],
+    pub transform: [f32; 4],
+
+    /// Unit quaternion of a 3D primitive, unused by every other node.
+    pub rotation: [f32; 4],
+    pub params: [[f32; 4]; 2],
+}
+
+impl GpuSceneNode {
+    fn operator(kind: u32, option: u32, value: f32) -> GpuSceneNode {
+        GpuSceneNode {
+            header: [kind, option, 0, 0],
+            params: [[value, 0.0, 0.0, 0.0], [0.0; 4]],
+            ..Default::default()
+        }
+    }
+
+    fn domain(kind: u32, option: u32, params: [f32; 4]) -> GpuSceneNode {
+        GpuSceneNode {
+            header: [kind, option, 0, 0],
+            params: [params, [0.0; 4]],
+            ..Default::default()
+        }
+    }
+}
+
+/// GPU side entry of the material table, matches std430 layout and `Material` in `material.glsl`.
+#[derive(BufferContents, Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct GpuMaterial {
+    /// x: pattern kind.
+    pub header: [u32; 4],
+    pub albedo: [f32; 4],
+    pub emission: [f32; 4],
+
+    /// Roughness, metallic, transmission and refractive index.
+    pub properties: [f32; 4],
+    pub pattern_colour: [f32; 4],
+
+    /// x: checker size, stripe width or noise scale. yzw: stripe direction.
+    pub pattern_params: [f32; 4],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_scene_encodes_no_nodes() {
+        let scene = Scene { nodes: Vec::new(), ..Scene::default() };
+        assert_eq!(scene.validate(), Ok(()));
+        assert!(scene.encode().nodes.is_empty());
+    }
+
+    #[test]
+    fn planar_scene_is_extruded_once() {
+        let encoded = Scene::default().encode();
+        let kinds: Vec<u32> = encoded.nodes.iter().map(|node| node.header[0]).collect();
+        assert_eq!(kinds, [PRIMITIVE_EQUILATERAL_TRIANGLE, OPERATOR_EXTRUDE]);
+    }
+
+    #[test]
+    fn onion_layers_are_limited() {
+        let onion = |layers| Scene {
+            nodes: vec![SceneNode::Onion {
+                thickness: 0.05,
+                layers,
+                child: Box::new(SceneNode::Primitive { shape: Shape::Circle { radius: 0.5 }, transform: Transform2D::default() }),
+            }],
+            ..Scene::default()
+        };
+
+        assert_eq!(onion(1).validate(), Ok(()));
+        assert_eq!(onion(MAX_ONION_LAYERS).validate(), Ok(()));
+        for layers in [0, MAX_ONION_LAYERS + 1, u32::MAX] {
+            let error = onion(layers).validate().unwrap_err();
+            assert_eq!((error.location.as_str(), error.parameter), ("nodes[0]", "layers"), "{layers} layers");
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use crate::scene::{Scene, SceneNode, Shape, Transform2D};
+use crate::timeline::Timeline;
+
+/// Version written into every saved scene. Bump this when the layout of `Scene` changes
+/// and keep a migration for the previous layout in `load_scene`.
+pub const SCENE_FILE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct SceneFile {
+    version: u32,
+    scene: Scene,
+}
+
+/// Version 1 scenes were a flat list of primitives, drawn as their union.
+#[derive(Deserialize)]
+struct SceneFileV1 {
+    scene: SceneV1,
+}
+
+#[derive(Deserialize)]
+struct SceneV1 {
+    primitives: Vec<PrimitiveV1>,
+}
+
+#[derive(Deserialize)]
+struct PrimitiveV1 {
+    shape: Shape,
+    #[serde(default)]
+    transform: Transform2D,
+}
+
+impl From<SceneV1> for Scene {
+    fn from(scene: SceneV1) -> Self {
+        Scene {
+            nodes: scene.primitives.into_iter()
+                .map(|primitive| SceneNode::Primitive { shape: primitive.shape, transform: primitive.transform })
+                .collect(),
+            materials: Vec::new(),
+            timeline: Timeline::default(),
+        }
+    }
+}
+
+/// Only the version is read first, so files from a newer build fail with a version error
+/// instead of a confusing parse error.
+#[derive(Deserialize)]
+struct SceneFileHeader {
+    version: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SceneFileFormat {
+    Ron,
+    Json,
+}
+
+impl SceneFileFormat {
+    fn from_path(path: &Path) -> Result<SceneFileFormat, SceneFileError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ron") => Ok(SceneFileFormat::Ron),
+            Some("json") => Ok(SceneFileFormat::Json),
+            _ => Err(SceneFileError::UnsupportedFormat { path: path.to_path_buf() }),
+        }
+    }
+
+    fn parse<'a, T: Deserialize<'a>>(&self, contents: &'a str) -> Result<T, String> {
+        match self {
+            SceneFileFormat::Ron => ron::from_str(contents).map_err(|e| e.to_string()),
+            SceneFileFormat::Json => serde_json::from_str(contents).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io { path: PathBuf, source: std::io::Error },
+    UnsupportedFormat { path: PathBuf },
+    Parse { path: PathBuf, message: String },
+    UnsupportedVersion { path: PathBuf, version: u32 },
+    InvalidParameter { path: PathBuf, location: String, parameter: &'static str, reason: &'static str },
+    Serialize { path: PathBuf, message: String },
+}
+
+impl Display for SceneFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneFileError::Io { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            SceneFileError::UnsupportedFormat { path } => {
+                write!(f, "{}: unsupported scene file extension, expected `.ron` or `.json`", path.display())
+            }
+            SceneFileError::Parse { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+            SceneFileError::UnsupportedVersion { path, version } => {
+                write!(f, "{}: unsupported scene file version {}, expected 1 to {}", path.display(), version, SCENE_FILE_VERSION)
+            }
+            SceneFileError::InvalidParameter { path, location, parameter, reason } => {
+                write!(f, "{}: `{}` has an invalid `{}`: {}", path.display(), location, parameter, reason)
+            }
+            SceneFileError::Serialize { path, message } => {
+                write!(f, "{}: failed to serialize scene: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneFileError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a `.ron` or `.json` scene file and validates its parameters.
+pub fn load_scene(path: &Path) -> Result<Scene, SceneFileError> {
+    let format = SceneFileFormat::from_path(path)?;
+    let contents = fs::read_to_string(path).map_err(|source| SceneFileError::Io { path: path.to_path_buf(), source })?;
+    let parse_error = |message| SceneFileError::Parse { path: path.to_path_buf(), message };
+
+    let header: SceneFileHeader = format.parse(&contents).map_err(parse_error)?;
+    let scene = match header.version {
+        1 => format.parse::<SceneFileV1>(&contents).map_err(parse_error)?.scene.into(),
+        SCENE_FILE_VERSION => format.parse::<SceneFile>(&contents).map_err(parse_error)?.scene,
+        version => return Err(SceneFileError::UnsupportedVersion { path: path.to_path_buf(), version }),
+    };
+
+    scene.validate().map_err(|invalid| SceneFileError::InvalidParameter {
+        path: path.to_path_buf(),
+        location: invalid.location,
+        parameter: invalid.parameter,
+        reason: invalid.reason,
+    })?;
+
+    Ok(scene)
+}
+
+/// Writes `scene` to `path`, picking RON or JSON from the file extension.
+pub fn save_scene(path: &Path, scene: &Scene) -> Result<(), SceneFileError> {
+    let format = SceneFileFormat::from_path(path)?;
+    let scene_file = SceneFile {
+        version: SCENE_FILE_VERSION,
+        scene: scene.clone(),
+    };
+
+    let contents = match format {
+        SceneFileFormat::Ron => ron::ser::to_string_pretty(&scene_file, PrettyConfig::default()).map_err(|e| e.to_string()),
+        SceneFileFormat::Json => serde_json::to_string_pretty(&scene_file).map_err(|e| e.to_string()),
+    }.map_err(|message| SceneFileError::Serialize { path: path.to_path_buf(), message })?;
+
+    fs::write(path, contents).map_err(|source| SceneFileError::Io { path: path.to_path_buf(), source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestDirectory;
+
+    fn circle_scene(radius: &str) -> String {
+        format!("(version: 2, scene: (nodes: [Primitive(shape: Circle(radius: {radius}))]))")
+    }
+
+    #[test]
+    fn unknown_primitive_is_a_parse_error() {
+        let directory = TestDirectory::new();
+        let path = directory.file("unknown.ron", "(version: 2, scene: (nodes: [Primitive(shape: Blob(radius: 1.0))]))");
+        let error = load_scene(&path).unwrap_err();
+        assert!(matches!(error, SceneFileError::Parse { .. }), "{error}");
+        assert!(error.to_string().contains("Blob"), "{error}");
+    }
+
+    #[test]
+    fn out_of_range_parameter_is_rejected() {
+        let directory = TestDirectory::new();
+        let path = directory.file("negative.ron", &circle_scene("-0.5"));
+        match load_scene(&path).unwrap_err() {
+            SceneFileError::InvalidParameter { location, parameter, reason, .. } => {
+                assert_eq!((location.as_str(), parameter, reason), ("nodes[0]", "radius", "must be greater than zero"));
+            }
+            error => panic!("unexpected error: {error}"),
+        }
+    }
+
+    #[test]
+    fn non_finite_parameter_is_rejected() {
+        let directory = TestDirectory::new();
+        for (name, radius) in [("infinite.ron", "inf"), ("nan.ron", "NaN")] {
+            let error = load_scene(&directory.file(name, &circle_scene(radius))).unwrap_err();
+            assert!(matches!(error, SceneFileError::InvalidParameter { parameter: "radius", .. }), "{error}");
+        }
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let directory = TestDirectory::new();
+        let path = directory.file("future.json", r#"{"version": 99, "scene": {"nodes": [], "lights": []}}"#);
+        let error = load_scene(&path).unwrap_err();
+        assert!(matches!(error, SceneFileError::UnsupportedVersion { version: 99, .. }), "{error}");
+    }
+
+    #[test]
+    fn unknown_extension_is_rejected() {
+        let directory = TestDirectory::new();
+        let path = directory.file("scene.txt", &circle_scene("0.5"));
+        assert!(matches!(load_scene(&path).unwrap_err(), SceneFileError::UnsupportedFormat { .. }));
+        assert!(matches!(save_scene(&path, &Scene::default()).unwrap_err(), SceneFileError::UnsupportedFormat { .. }));
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        let directory = TestDirectory::new();
+        let scene = Scene::default();
+        let ron_path = directory.file("saved.ron", "");
+        let json_path = directory.file("saved.json", "");
+        save_scene(&ron_path, &scene).unwrap();
+        save_scene(&json_path, &scene).unwrap();
+
+        let ron_contents = fs::read_to_string(&ron_path).unwrap();
+        let json_contents = fs::read_to_string(&json_path).unwrap();
+        assert!(ron_contents.starts_with('('), "{ron_contents}");
+        assert!(serde_json::from_str::<serde_json::Value>(&json_contents).is_ok(), "{json_contents}");
+        assert_eq!(load_scene(&ron_path).unwrap(), scene);
+        assert_eq!(load_scene(&json_path).unwrap(), scene);
+
+        // The extension decides the parser, not the contents.
+        let misnamed = directory.file("misnamed.ron", &json_contents);
+        assert!(matches!(load_scene(&misnamed).unwrap_err(), SceneFileError::Parse { .. }));
+    }
+
+    #[test]
+    fn version_1_scenes_are_migrated() {
+        let directory = TestDirectory::new();
+        let path = directory.file("v1.ron", "(version: 1, scene: (primitives: [(shape: Circle(radius: 0.5))]))");
+        let scene = load_scene(&path).unwrap();
+        assert_eq!(scene.nodes, [SceneNode::Primitive { shape: Shape::Circle { radius: 0.5 }, transform: Transform2D::default() }]);
+    }
+}
@@ -0,0 +1,609 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use ron::ser::PrettyConfig;
+use vulkano::shader::spirv::{Decoration, Id, Instruction, Spirv, StorageClass};
+
+/// Component type of a reflected parameter. Every component takes 4 bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarKind {
+    Float,
+    Int,
+    Uint,
+}
+
+/// What the `@` tags in the comment after a block member say about it, see `parse_annotations`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterAnnotation {
+    /// `@range(min, max)`, shown as a slider.
+    pub range: Option<[f32; 2]>,
+    /// `@default(x, ...)`, one value per component.
+    pub default: Option<Vec<f32>>,
+    /// `@colour`, shown as a colour picker. Only for `vec3` and `vec4`.
+    pub colour: bool,
+}
+
+/// A scalar or vector member of a push constant or uniform block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShaderParameter {
+    pub name: String,
+    pub offset: u32,
+    pub kind: ScalarKind,
+    pub components: usize,
+    pub annotation: Option<ParameterAnnotation>,
+}
+
+impl ShaderParameter {
+    /// The value in `store`, or the annotated default, or zero.
+    pub fn value(&self, store: &ParameterStore) -> [f32; 4] {
+        resolve_value(store, &self.name, self.annotation.as_ref())
+    }
+
+    /// Writes `value` at the member offset in the type of the member.
+    fn write(&self, bytes: &mut [u8], value: [f32; 4]) {
+        for (index, component) in value.iter().take(self.components).enumerate() {
+            let encoded = match self.kind {
+                ScalarKind::Float => component.to_le_bytes(),
+                ScalarKind::Int => (*component as i32).to_le_bytes(),
+                ScalarKind::Uint => (*component as u32).to_le_bytes(),
+            };
+            let start = self.offset as usize + index * 4;
+            bytes[start..start + 4].copy_from_slice(&encoded);
+        }
+    }
+}
+
+/// Where the contents of a block come from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockKind {
+    PushConstants,
+    Uniform { set: u32, binding: u32 },
+}
+
+/// A push constant or uniform block as laid out by the compiler. Members that are not scalars
+/// or vectors, such as arrays and structs, are not listed and stay zero when encoded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterBlock {
+    pub kind: BlockKind,
+    pub size: u32,
+    pub parameters: Vec<ShaderParameter>,
+}
+
+impl ParameterBlock {
+    /// The block contents. Values the renderer owns, such as the camera, come from `builtins`,
+    /// everything else from `store` or the annotated defaults.
+    pub fn encode(&self, store: &ParameterStore, builtins: &[(&str, [f32; 4])]) -> Vec<u8> {
+        let mut bytes = vec![0; self.size as usize];
+        for parameter in &self.parameters {
+            let builtin = builtins.iter().find(|(name, _)| *name == parameter.name);
+            let value = builtin.map_or_else(|| parameter.value(store), |(_, value)| *value);
+            parameter.write(&mut bytes, value);
+        }
+        bytes
+    }
+}
+
+/// Push constant and uniform blocks of a compiled shader.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShaderParameters {
+    pub blocks: Vec<ParameterBlock>,
+}
+
+impl ShaderParameters {
+    /// Reflects the blocks from SPIR-V and attaches the annotations of the sources by member
+    /// name. Needs the debug names shaderc writes without optimization.
+    pub fn reflect(words: &[u32], annotations: &HashMap<String, ParameterAnnotation>) -> Result<ShaderParameters, String> {
+        let spirv = Spirv::new(words).map_err(|e| format!("Failed to reflect shader: {e}"))?;
+
+        let mut blocks = Vec::new();
+        for instruction in spirv.iter_global() {
+            let Instruction::Variable { result_type_id, result_id, storage_class, .. } = instruction else {
+                continue;
+            };
+            let Instruction::TypePointer { ty, .. } = spirv.id(*result_type_id).instruction() else {
+                continue;
+            };
+
+            let kind = match storage_class {
+                StorageClass::PushConstant => BlockKind::PushConstants,
+                // Storage buffers are `Uniform` with a `BufferBlock` struct in SPIR-V 1.0.
+                StorageClass::Uniform if has_decoration(&spirv, *ty, |decoration| matches!(decoration, Decoration::Block)) => {
+                    let mut set = 0;
+                    let mut binding = 0;
+                    for decoration in spirv.id(*result_id).iter_decoration() {
+                        match decoration {
+                            Instruction::Decorate { decoration: Decoration::DescriptorSet { descriptor_set }, .. } => set = *descriptor_set,
+                            Instruction::Decorate { decoration: Decoration::Binding { binding_point }, .. } => binding = *binding_point,
+                            _ => (),
+                        }
+                    }
+                    BlockKind::Uniform { set, binding }
+                }
+                _ => continue,
+            };
+
+            let parameters = reflect_members(&spirv, *ty, annotations)?;
+            let size = type_size(&spirv, *ty, 16).ok_or("Failed to reflect shader: block of unknown size")?;
+            blocks.push(ParameterBlock { kind, size: size.next_multiple_of(16), parameters });
+        }
+
+        Ok(ShaderParameters { blocks })
+    }
+
+    pub fn push_constants(&self) -> Option<&ParameterBlock> {
+        self.blocks.iter().find(|block| block.kind == BlockKind::PushConstants)
+    }
+
+    /// Uniform blocks of descriptor set 0.
+    pub fn uniforms(&self) -> impl Iterator<Item = (u32, &ParameterBlock)> {
+        self.blocks.iter().filter_map(|block| match block.kind {
+            BlockKind::Uniform { set: 0, binding } => Some((binding, block)),
+            _ => None,
+        })
+    }
+
+    /// Members with an annotation, the ones shown in the parameter panel and parameter files.
+    pub fn annotated(&self) -> impl Iterator<Item = &ShaderParameter> {
+        self.blocks.iter()
+            .flat_map(|block| &block.parameters)
+            .filter(|parameter| parameter.annotation.is_some())
+    }
+}
+
+fn has_decoration(spirv: &Spirv, id: Id, matches: impl Fn(&Decoration) -> bool) -> bool {
+    spirv.id(id).iter_decoration().any(|instruction| match instruction {
+        Instruction::Decorate { decoration, .. } => matches(decoration),
+        _ => false,
+    })
+}
+
+fn reflect_members(
+    spirv: &Spirv,
+    struct_id: Id,
+    annotations: &HashMap<String, ParameterAnnotation>,
+) -> Result<Vec<ShaderParameter>, String> {
+    let Instruction::TypeStruct { member_types, .. } = spirv.id(struct_id).instruction() else {
+        return Err("Failed to reflect shader: block is not a struct".to_string());
+    };
+
+    let mut parameters = Vec::new();
+    for (member, member_type) in spirv.id(struct_id).iter_members().zip(member_types) {
+        let name = member.iter_name().find_map(|instruction| match instruction {
+            Instruction::MemberName { name, .. } => Some(name.clone()),
+            _ => None,
+        });
+        let offset = member.iter_decoration().find_map(|instruction| match instruction {
+            Instruction::MemberDecorate { decoration: Decoration::Offset { byte_offset }, .. } => Some(*byte_offset),
+            _ => None,
+        });
+        let (Some(name), Some(offset), Some((kind, components))) = (name, offset, scalar_or_vector(spirv, *member_type)) else {
+            continue;
+        };
+
+        let annotation = annotations.get(&name).cloned();
+        if let Some(annotation) = &annotation {
+            if annotation.default.as_ref().is_some_and(|default| default.len() != components) {
+                return Err(format!("`{name}` has {components} components but a default with a different number of values"));
+            }
+            if annotation.colour && components < 3 {
+                return Err(format!("`{name}` is marked `@colour` but has fewer than 3 components"));
+            }
+        }
+        parameters.push(ShaderParameter { name, offset, kind, components, annotation });
+    }
+
+    Ok(parameters)
+}
+
+fn scalar_or_vector(spirv: &Spirv, id: Id) -> Option<(ScalarKind, usize)> {
+    match spirv.id(id).instruction() {
+        Instruction::TypeFloat { width: 32, .. } => Some((ScalarKind::Float, 1)),
+        Instruction::TypeInt { width: 32, signedness: 1, .. } => Some((ScalarKind::Int, 1)),
+        Instruction::TypeInt { width: 32, signedness: 0, .. } => Some((ScalarKind::Uint, 1)),
+        Instruction::TypeVector { component_type, component_count, .. } => {
+            scalar_or_vector(spirv, *component_type).map(|(kind, _)| (kind, *component_count as usize))
+        }
+        _ => None,
+    }
+}
+
+/// Bytes taken by a type in a block, up to the end of its last member for structs. Matrices
+/// take the stride of the member that holds them.
+fn type_size(spirv: &Spirv, id: Id, matrix_stride: u32) -> Option<u32> {
+    let info = spirv.id(id);
+    match info.instruction() {
+        Instruction::TypeFloat { width, .. } | Instruction::TypeInt { width, .. } => Some(width / 8),
+        Instruction::TypeVector { component_type, component_count, .. } => {
+            Some(type_size(spirv, *component_type, matrix_stride)? * component_count)
+        }
+        Instruction::TypeMatrix { column_count, .. } => Some(matrix_stride * column_count),
+        Instruction::TypeArray { length, .. } => {
+            let stride = info.iter_decoration().find_map(|instruction| match instruction {
+                Instruction::Decorate { decoration: Decoration::ArrayStride { array_stride }, .. } => Some(*array_stride),
+                _ => None,
+            })?;
+            let Instruction::Constant { value, .. } = spirv.id(*length).instruction() else {
+                return None;
+            };
+            Some(stride * value.first()?)
+        }
+        Instruction::TypeStruct { member_types, .. } => {
+            let mut end = 0;
+            for (member, member_type) in info.iter_members().zip(member_types) {
+                let mut offset = None;
+                let mut member_matrix_stride = 16;
+                for instruction in member.iter_decoration() {
+                    match instruction {
+                        Instruction::MemberDecorate { decoration: Decoration::Offset { byte_offset }, .. } => offset = Some(*byte_offset),
+                        Instruction::MemberDecorate { decoration: Decoration::MatrixStride { matrix_stride }, .. } => {
+                            member_matrix_stride = *matrix_stride;
+                        }
+                        _ => (),
+                    }
+                }
+                end = end.max(offset? + type_size(spirv, *member_type, member_matrix_stride)?);
+            }
+            Some(end)
+        }
+        _ => None,
+    }
+}
+
+/// Collects `@` tags from the comments after block members, such as
+/// `float exposure; // @range(0.1, 4) @default(1)` or `vec3 tint; // @colour @default(1, 1, 1)`.
+/// Tags attach to the member name, whichever block it is in. Comments that do not start with
+/// `@` are left alone.
+pub fn parse_annotations(
+    file_name: &str,
+    source: &str,
+    annotations: &mut HashMap<String, ParameterAnnotation>,
+) -> Result<(), String> {
+    for (line_index, line) in source.lines().enumerate() {
+        let Some((code, comment)) = line.split_once("//") else {
+            continue;
+        };
+        let comment = comment.trim();
+        if !comment.starts_with('@') {
+            continue;
+        }
+
+        let error = |message: String| format!("{file_name}:{}: {message}", line_index + 1);
+        let name = code.trim().strip_suffix(';')
+            .and_then(|declaration| declaration.split_whitespace().last())
+            .map(|name| name.split('[').next().unwrap_or(name))
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| error("parameter annotation without a member declaration before it".to_string()))?;
+
+        let annotation = parse_tags(comment).map_err(error)?;
+        annotations.insert(name.to_string(), annotation);
+    }
+
+    Ok(())
+}
+
+fn parse_tags(comment: &str) -> Result<ParameterAnnotation, String> {
+    let mut annotation = ParameterAnnotation::default();
+    let mut rest = comment.trim_start();
+    while let Some(tag) = rest.strip_prefix('@') {
+        let name_end = tag.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(tag.len());
+        let (name, after_name) = tag.split_at(name_end);
+        let (arguments, after_tag) = match after_name.strip_prefix('(') {
+            Some(arguments) => {
+                let (arguments, after) = arguments.split_once(')').ok_or_else(|| format!("`@{name}` is missing a `)`"))?;
+                let values = arguments.split(',')
+                    .map(|value| value.trim().parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("`@{name}` has an invalid number: {e}"))?;
+                (values, after)
+            }
+            None => (Vec::new(), after_name),
+        };
+
+        match (name, arguments.as_slice()) {
+            ("range", [min, max]) if min < max => annotation.range = Some([*min, *max]),
+            ("range", _) => return Err("`@range` takes a minimum and a larger maximum".to_string()),
+            ("default", values) if (1..=4).contains(&values.len()) => annotation.default = Some(values.to_vec()),
+            ("default", _) => return Err("`@default` takes 1 to 4 values".to_string()),
+            ("colour", []) => annotation.colour = true,
+            ("colour", _) => return Err("`@colour` takes no values".to_string()),
+            _ => return Err(format!("unknown parameter annotation `@{name}`")),
+        }
+        rest = after_tag.trim_start();
+    }
+
+    if !rest.is_empty() {
+        return Err(format!("expected a parameter annotation, found `{rest}`"));
+    }
+    Ok(annotation)
+}
+
+/// Parameter values by name, shared by every shader of a window. Names a shader does not
+/// declare are kept for the next one that does.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterStore {
+    values: BTreeMap<String, Vec<f32>>,
+}
+
+impl ParameterStore {
+    pub fn get(&self, name: &str) -> Option<&[f32]> {
+        self.values.get(name).map(Vec::as_slice)
+    }
+
+    /// Sets a value and returns whether it changed.
+    pub fn set(&mut self, name: &str, value: &[f32]) -> bool {
+        if self.get(name) == Some(value) {
+            return false;
+        }
+        self.values.insert(name.to_string(), value.to_vec());
+        true
+    }
+}
+
+/// Value of the parameter `name` in `store`, or its annotated default, or zero.
+pub fn resolve_value(store: &ParameterStore, name: &str, annotation: Option<&ParameterAnnotation>) -> [f32; 4] {
+    let values = store.get(name).or(annotation.and_then(|annotation| annotation.default.as_deref())).unwrap_or(&[]);
+    let mut value = [0.0; 4];
+    for (component, stored) in value.iter_mut().zip(values) {
+        *component = *stored;
+    }
+    value
+}
+
+#[derive(Debug)]
+pub enum ParameterFileError {
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, message: String },
+    InvalidValue { path: PathBuf, name: String },
+    Serialize { path: PathBuf, message: String },
+}
+
+impl Display for ParameterFileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParameterFileError::Io { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            ParameterFileError::Parse { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+            ParameterFileError::InvalidValue { path, name } => {
+                write!(f, "{}: `{}` needs 1 to 4 values", path.display(), name)
+            }
+            ParameterFileError::Serialize { path, message } => {
+                write!(f, "{}: failed to serialize parameters: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParameterFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParameterFileError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a `.ron` map from parameter names to their values.
+pub fn load_parameters(path: &Path) -> Result<ParameterStore, ParameterFileError> {
+    let contents = fs::read_to_string(path).map_err(|source| ParameterFileError::Io { path: path.to_path_buf(), source })?;
+    let values: BTreeMap<String, Vec<f32>> = ron::from_str(&contents)
+        .map_err(|e| ParameterFileError::Parse { path: path.to_path_buf(), message: e.to_string() })?;
+    if let Some((name, _)) = values.iter().find(|(_, value)| !(1..=4).contains(&value.len())) {
+        return Err(ParameterFileError::InvalidValue { path: path.to_path_buf(), name: name.clone() });
+    }
+
+    Ok(ParameterStore { values })
+}
+
+/// Writes every annotated parameter of `shaders` with its value in `store`, or its default.
+pub fn save_parameters<'a>(
+    path: &Path,
+    shaders: impl IntoIterator<Item = &'a ShaderParameters>,
+    store: &ParameterStore,
+) -> Result<(), ParameterFileError> {
+    let mut values = BTreeMap::new();
+    for parameter in shaders.into_iter().flat_map(ShaderParameters::annotated) {
+        values.entry(parameter.name.clone()).or_insert_with(|| parameter.value(store)[..parameter.components].to_vec());
+    }
+
+    let contents = ron::ser::to_string_pretty(&values, PrettyConfig::default())
+        .map_err(|e| ParameterFileError::Serialize { path: path.to_path_buf(), message: e.to_string() })?;
+    fs::write(path, contents).map_err(|source| ParameterFileError::Io { path: path.to_path_buf(), source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shaderc::{CompileOptions, Compiler, EnvVersion, ShaderKind, TargetEnv};
+    use crate::test_support::TestDirectory;
+
+    /// Push constants use the std430 layout, uniform blocks std140, so the same types land
+    /// at different offsets.
+    const SHADER: &str = "#version 450
+layout(local_size_x = 1) in;
+
+struct Light {
+    vec3 colour;
+    float intensity;
+};
+
+layout(push_constant) uniform Parameters {
+    float exposure; // @range(0.1, 4) @default(1)
+    vec3 tint; // @colour @default(1, 0.5, 0.25)
+    int steps; // @default(3)
+    float weights[3];
+    uint flags;
+} parameters;
+
+layout(set = 0, binding = 7) uniform Extra {
+    vec2 offset;
+    Light light;
+    float levels[2];
+    vec3 direction; // @default(0, 0, 1)
+} extra;
+
+void main() {
+    float unused = parameters.exposure + extra.offset.x;
+}
+";
+
+    fn reflect(source: &str) -> Result<ShaderParameters, String> {
+        let compiler = Compiler::new().unwrap();
+        let mut options = CompileOptions::new().unwrap();
+        options.set_target_env(TargetEnv::Vulkan, EnvVersion::Vulkan1_0 as u32);
+        let artifact = compiler.compile_into_spirv(source, ShaderKind::Compute, "test.comp", "main", Some(&options)).unwrap();
+
+        let mut annotations = HashMap::new();
+        parse_annotations("test.comp", source, &mut annotations)?;
+        ShaderParameters::reflect(artifact.as_binary(), &annotations)
+    }
+
+    fn layout(block: &ParameterBlock) -> Vec<(&str, u32, ScalarKind, usize)> {
+        block.parameters.iter()
+            .map(|parameter| (parameter.name.as_str(), parameter.offset, parameter.kind, parameter.components))
+            .collect()
+    }
+
+    fn tag_error(comment: &str) -> String {
+        parse_tags(comment).unwrap_err()
+    }
+
+    #[test]
+    fn tags_are_parsed() {
+        assert_eq!(parse_tags("@range(0.1, 4) @default(1)"), Ok(ParameterAnnotation {
+            range: Some([0.1, 4.0]),
+            default: Some(vec![1.0]),
+            colour: false,
+        }));
+        assert_eq!(parse_tags("@colour  @default( 1, 0.5,0.25 )"), Ok(ParameterAnnotation {
+            range: None,
+            default: Some(vec![1.0, 0.5, 0.25]),
+            colour: true,
+        }));
+    }
+
+    #[test]
+    fn malformed_tags_are_rejected() {
+        assert_eq!(tag_error("@range(1, 0)"), "`@range` takes a minimum and a larger maximum");
+        assert_eq!(tag_error("@range(1)"), "`@range` takes a minimum and a larger maximum");
+        assert_eq!(tag_error("@default(1, 2, 3, 4, 5)"), "`@default` takes 1 to 4 values");
+        assert_eq!(tag_error("@colour(1)"), "`@colour` takes no values");
+        assert_eq!(tag_error("@range(0, 1"), "`@range` is missing a `)`");
+        assert_eq!(tag_error("@slider"), "unknown parameter annotation `@slider`");
+        assert_eq!(tag_error("@default(1) and more"), "expected a parameter annotation, found `and more`");
+        assert!(tag_error("@default()").starts_with("`@default` has an invalid number"));
+        assert!(tag_error("@range(0, one)").starts_with("`@range` has an invalid number"));
+    }
+
+    #[test]
+    fn annotations_attach_to_member_names() {
+        let source = "
+            float exposure; // @default(2)
+            float samples[4]; // @range(1, 64)
+            vec3 plain; // not a tag
+            int counter;
+        ";
+        let mut annotations = HashMap::new();
+        parse_annotations("test.comp", source, &mut annotations).unwrap();
+
+        let mut names: Vec<&str> = annotations.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["exposure", "samples"]);
+        assert_eq!(annotations["exposure"].default, Some(vec![2.0]));
+    }
+
+    #[test]
+    fn annotation_errors_name_the_line() {
+        let mut annotations = HashMap::new();
+        let error = parse_annotations("test.comp", "float exposure;\n// @default(1)\n", &mut annotations).unwrap_err();
+        assert_eq!(error, "test.comp:2: parameter annotation without a member declaration before it");
+
+        let error = parse_annotations("test.comp", "\n\nfloat exposure; // @range(2, 1)", &mut annotations).unwrap_err();
+        assert_eq!(error, "test.comp:3: `@range` takes a minimum and a larger maximum");
+    }
+
+    #[test]
+    fn push_constants_follow_std430() {
+        let parameters = reflect(SHADER).unwrap();
+        let block = parameters.push_constants().unwrap();
+        // vec3 aligns to 16 bytes, the float array has a 4 byte stride and is not listed.
+        assert_eq!(layout(block), [
+            ("exposure", 0, ScalarKind::Float, 1),
+            ("tint", 16, ScalarKind::Float, 3),
+            ("steps", 28, ScalarKind::Int, 1),
+            ("flags", 44, ScalarKind::Uint, 1),
+        ]);
+        assert_eq!(block.size, 48);
+    }
+
+    #[test]
+    fn uniform_blocks_follow_std140() {
+        let parameters = reflect(SHADER).unwrap();
+        let uniforms: Vec<(u32, &ParameterBlock)> = parameters.uniforms().collect();
+        assert_eq!(uniforms.len(), 1);
+        let (binding, block) = uniforms[0];
+        assert_eq!(binding, 7);
+        // The struct takes 16 bytes at 16, the array elements have a 16 byte stride.
+        assert_eq!(layout(block), [("offset", 0, ScalarKind::Float, 2), ("direction", 64, ScalarKind::Float, 3)]);
+        assert_eq!(block.size, 80);
+    }
+
+    #[test]
+    fn annotations_must_fit_the_member() {
+        let source = SHADER.replace("vec3 tint; // @colour @default(1, 0.5, 0.25)", "vec3 tint; // @default(1, 0.5)");
+        assert_eq!(reflect(&source).unwrap_err(), "`tint` has 3 components but a default with a different number of values");
+
+        let source = SHADER.replace("int steps; // @default(3)", "int steps; // @colour");
+        assert_eq!(reflect(&source).unwrap_err(), "`steps` is marked `@colour` but has fewer than 3 components");
+    }
+
+    #[test]
+    fn encoding_writes_each_member_in_its_type() {
+        let parameters = reflect(SHADER).unwrap();
+        let mut store = ParameterStore::default();
+        store.set("tint", &[0.2, 0.4, 0.6]);
+        store.set("steps", &[7.0]);
+        let bytes = parameters.push_constants().unwrap().encode(&store, &[("flags", [5.0, 0.0, 0.0, 0.0])]);
+
+        let float = |offset: usize| f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let word = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        assert_eq!(bytes.len(), 48);
+        // Annotated default, stored values and the builtin.
+        assert_eq!(float(0), 1.0);
+        assert_eq!([float(16), float(20), float(24)], [0.2, 0.4, 0.6]);
+        assert_eq!(i32::from_le_bytes(bytes[28..32].try_into().unwrap()), 7);
+        assert_eq!(word(44), 5);
+        // Padding and the unlisted array stay zero.
+        assert!(bytes[4..16].iter().chain(&bytes[32..44]).all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn saved_parameters_load_back() {
+        let parameters = reflect(SHADER).unwrap();
+        let mut store = ParameterStore::default();
+        store.set("tint", &[0.2, 0.4, 0.6]);
+        store.set("unknown", &[9.0]);
+
+        let directory = TestDirectory::new();
+        let path = directory.path().join("parameters.ron");
+        save_parameters(&path, [&parameters], &store).unwrap();
+
+        // Every annotated member is written, unannotated and undeclared names are not.
+        let mut expected = ParameterStore::default();
+        expected.set("exposure", &[1.0]);
+        expected.set("tint", &[0.2, 0.4, 0.6]);
+        expected.set("steps", &[3.0]);
+        expected.set("direction", &[0.0, 0.0, 1.0]);
+        assert_eq!(load_parameters(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn parameter_files_need_one_to_four_values() {
+        let directory = TestDirectory::new();
+        let path = directory.file("parameters.ron", "{\"exposure\": [1.0], \"tint\": [1.0, 1.0, 1.0, 1.0, 1.0]}");
+        assert!(matches!(load_parameters(&path).unwrap_err(), ParameterFileError::InvalidValue { name, .. } if name == "tint"));
+    }
+}
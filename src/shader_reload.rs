@@ -0,0 +1,315 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::OnceLock;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use shaderc::{CompileOptions, Compiler, EnvVersion, IncludeType, ResolvedInclude, ShaderKind, TargetEnv};
+use crate::shader_parameters::{parse_annotations, ParameterAnnotation, ShaderParameters};
+use crate::shadertoy_import::{import_shadertoy, ShadertoyPass, ShadertoyProject};
+
+/// Directory the compute shaders are compiled from, same as the build time `shader!` path.
+pub const SHADER_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders");
+
+/// Entry file of the signed distance function compute shader.
+pub const COMPUTE_SHADER_FILE: &str = "shapes_cs.glsl";
+
+/// Entry file of the raymarched 3D view of the scene.
+pub const RAYMARCH_SHADER_FILE: &str = "raymarch_cs.glsl";
+
+/// Entry file of the path traced 3D view of the scene.
+pub const PATH_TRACE_SHADER_FILE: &str = "path_trace_cs.glsl";
+
+/// Include that wraps a Shadertoy `mainImage` into a compute entry point.
+pub const SHADERTOY_SHADER_FILE: &str = "shadertoy.glsl";
+
+/// The scene shaders as they were built. `shader!` keeps the SPIR-V it generates to itself,
+/// so they are compiled once more from these for reflection.
+const EMBEDDED_SCENE_SHADERS: [(&str, &str); 14] = [
+    (COMPUTE_SHADER_FILE, include_str!("shaders/shapes_cs.glsl")),
+    (RAYMARCH_SHADER_FILE, include_str!("shaders/raymarch_cs.glsl")),
+    (PATH_TRACE_SHADER_FILE, include_str!("shaders/path_trace_cs.glsl")),
+    ("material.glsl", include_str!("shaders/material.glsl")),
+    ("palette.glsl", include_str!("shaders/palette.glsl")),
+    ("parameters.glsl", include_str!("shaders/parameters.glsl")),
+    ("raymarch.glsl", include_str!("shaders/raymarch.glsl")),
+    ("scene.glsl", include_str!("shaders/scene.glsl")),
+    ("sdf_2d.glsl", include_str!("shaders/sdf_2d.glsl")),
+    ("sdf_3d.glsl", include_str!("shaders/sdf_3d.glsl")),
+    ("sdf_domain.glsl", include_str!("shaders/sdf_domain.glsl")),
+    ("sdf_operators.glsl", include_str!("shaders/sdf_operators.glsl")),
+    ("shadertoy_inputs.glsl", include_str!("shaders/shadertoy_inputs.glsl")),
+    ("workgroup_size.glsl", include_str!("shaders/workgroup_size.glsl")),
+];
+
+/// Watches the shader directory and reports when any file in it changes.
+pub struct ShaderWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl ShaderWatcher {
+    pub fn new(directory: &Path) -> notify::Result<ShaderWatcher> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(directory, RecursiveMode::Recursive)?;
+
+        Ok(ShaderWatcher {
+            watcher,
+            events,
+        })
+    }
+
+    /// Also watches a single file outside the shader directory, such as a pasted Shadertoy shader.
+    pub fn watch_file(&mut self, path: &Path) -> notify::Result<()> {
+        self.watcher.watch(path, RecursiveMode::NonRecursive)
+    }
+
+    /// Drains pending file events and returns `true` if a shader was modified.
+    /// Editors usually write several events per save, they are collapsed into one reload.
+    pub fn poll_changed(&self) -> bool {
+        let mut changed = false;
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+                        changed = true;
+                    }
+                }
+                Err(e) => eprintln!("Shader watcher error: {e}"),
+            }
+        }
+
+        changed
+    }
+}
+
+/// Where the compute shader source comes from.
+pub enum ComputeShaderSource {
+    /// The signed distance function scene shaders, `shapes_cs.glsl`, `raymarch_cs.glsl`
+    /// and `path_trace_cs.glsl`.
+    Scene,
+    /// A pasted Shadertoy shader that defines `mainImage`.
+    Shadertoy(PathBuf),
+    /// A Shadertoy JSON export with an Image pass and optional buffer passes.
+    ShadertoyExport(PathBuf),
+}
+
+/// SPIR-V of one compute shader and the blocks reflected from it.
+pub struct CompiledModule {
+    pub words: Vec<u32>,
+    pub parameters: ShaderParameters,
+}
+
+/// SPIR-V for the active shader source.
+pub enum CompiledComputeShader {
+    /// The 2D, the raymarched and the path traced scene shader.
+    Scene {
+        flat: CompiledModule,
+        raymarch: CompiledModule,
+        path_trace: CompiledModule,
+    },
+    Single(CompiledModule),
+    /// One module per buffer pass in execution order, then the Image pass.
+    Shadertoy {
+        project: Box<ShadertoyProject>,
+        buffers: Vec<CompiledModule>,
+        image: CompiledModule,
+    },
+}
+
+impl CompiledComputeShader {
+    /// Blocks of every module, in the order they run or by render mode.
+    pub fn parameters(&self) -> Vec<&ShaderParameters> {
+        match self {
+            CompiledComputeShader::Scene { flat, raymarch, path_trace } => {
+                vec![&flat.parameters, &raymarch.parameters, &path_trace.parameters]
+            }
+            CompiledComputeShader::Single(module) => vec![&module.parameters],
+            CompiledComputeShader::Shadertoy { buffers, image, .. } => {
+                buffers.iter().chain([image]).map(|module| &module.parameters).collect()
+            }
+        }
+    }
+}
+
+/// Where shader sources and their includes are read from.
+enum ShaderFiles<'a> {
+    Directory(&'a Path),
+    /// `EMBEDDED_SCENE_SHADERS`, which are always there.
+    Embedded,
+}
+
+impl ShaderFiles<'_> {
+    /// The source of `file_name` and the name compiler errors give it. Errors start with the
+    /// file name.
+    fn read(&self, file_name: &str) -> Result<(String, String), String> {
+        match self {
+            ShaderFiles::Directory(directory) => {
+                let path = directory.join(file_name);
+                let source = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+                Ok((path.display().to_string(), source))
+            }
+            ShaderFiles::Embedded => EMBEDDED_SCENE_SHADERS.iter()
+                .find(|(name, _)| *name == file_name)
+                .map(|(name, source)| (name.to_string(), source.to_string()))
+                .ok_or_else(|| format!("{file_name}: not a built in shader")),
+        }
+    }
+}
+
+/// The built in scene shaders compiled from their embedded sources, for the blocks and
+/// annotations of the pipelines made from the `shader!` modules. Compiled on first use.
+pub fn built_in_scene_shaders() -> &'static CompiledComputeShader {
+    static BUILT_IN: OnceLock<CompiledComputeShader> = OnceLock::new();
+    BUILT_IN.get_or_init(|| {
+        compile_scene_shaders(&ShaderFiles::Embedded).expect("the built in scene shaders compiled at build time")
+    })
+}
+
+/// Annotations of the built in scene shaders, read without compiling them.
+pub fn built_in_annotations() -> HashMap<String, ParameterAnnotation> {
+    let mut annotations = HashMap::new();
+    for (file_name, source) in EMBEDDED_SCENE_SHADERS {
+        parse_annotations(file_name, source, &mut annotations).expect("the built in annotations are valid");
+    }
+    annotations
+}
+
+impl ComputeShaderSource {
+    /// Picks the Shadertoy source kind from the extension, `.json` files are exports.
+    pub fn shadertoy(path: &Path) -> ComputeShaderSource {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => ComputeShaderSource::ShadertoyExport(path.to_path_buf()),
+            _ => ComputeShaderSource::Shadertoy(path.to_path_buf()),
+        }
+    }
+
+    /// File outside the shader directory that has to be watched for hot reload.
+    pub fn external_file(&self) -> Option<&Path> {
+        match self {
+            ComputeShaderSource::Scene => None,
+            ComputeShaderSource::Shadertoy(path) | ComputeShaderSource::ShadertoyExport(path) => Some(path),
+        }
+    }
+
+    /// Compiles the shader from disk into SPIR-V words, includes resolve from `directory`.
+    /// The error holds the compiler output so it can be shown to the user as is.
+    pub fn compile(&self, directory: &Path) -> Result<CompiledComputeShader, String> {
+        match self {
+            ComputeShaderSource::Scene => compile_scene_shaders(&ShaderFiles::Directory(directory)),
+            ComputeShaderSource::Shadertoy(path) => {
+                // `#line` keeps compiler errors pointing at lines in the pasted file.
+                let source = format!(
+                    "#version 450\n#include \"{SHADERTOY_SHADER_FILE}\"\n#line 1\n{}",
+                    read_source(path)?,
+                );
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                compile_compute_shader(&ShaderFiles::Directory(directory), &source, &file_name).map(CompiledComputeShader::Single)
+            }
+            ComputeShaderSource::ShadertoyExport(path) => {
+                let project = import_shadertoy(path).map_err(|e| e.to_string())?;
+                let buffers = project.buffers.iter()
+                    .map(|pass| compile_shadertoy_pass(directory, &project.common, pass, true))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let image = compile_shadertoy_pass(directory, &project.common, &project.image, false)?;
+
+                Ok(CompiledComputeShader::Shadertoy {
+                    project: Box::new(project),
+                    buffers,
+                    image,
+                })
+            }
+        }
+    }
+}
+
+fn compile_scene_shaders(files: &ShaderFiles) -> Result<CompiledComputeShader, String> {
+    let file_names = [COMPUTE_SHADER_FILE, RAYMARCH_SHADER_FILE, PATH_TRACE_SHADER_FILE];
+    let [flat, raymarch, path_trace] = file_names.map(|file_name| {
+        let (_, source) = files.read(file_name).map_err(|e| format!("Failed to read {e}"))?;
+        compile_compute_shader(files, &source, file_name)
+    });
+
+    Ok(CompiledComputeShader::Scene {
+        flat: flat?,
+        raymarch: raymarch?,
+        path_trace: path_trace?,
+    })
+}
+
+/// Compiles one pass of an imported shader with the Common code in front of it.
+/// Line directives name the tab each line came from, so errors read like on Shadertoy.
+fn compile_shadertoy_pass(
+    directory: &Path,
+    common: &str,
+    pass: &ShadertoyPass,
+    buffer_pass: bool,
+) -> Result<CompiledModule, String> {
+    let buffer_define = if buffer_pass { "#define SHADERTOY_BUFFER_PASS\n" } else { "" };
+    let source = format!(
+        "#version 450\n#define SHADERTOY_CHANNELS\n{buffer_define}#include \"{SHADERTOY_SHADER_FILE}\"\n#line 1 \"Common\"\n{common}\n#line 1 \"{}\"\n{}",
+        pass.name,
+        pass.code,
+    );
+    compile_compute_shader(&ShaderFiles::Directory(directory), &source, &pass.name)
+}
+
+fn read_source(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))
+}
+
+/// Compiles to SPIR-V and reflects its blocks, with the annotations of the source and of
+/// every file it includes.
+fn compile_compute_shader(
+    files: &ShaderFiles,
+    source: &str,
+    file_name: &str,
+) -> Result<CompiledModule, String> {
+    let included = RefCell::new(Vec::new());
+    let compiler = Compiler::new().ok_or("Failed to create the shader compiler")?;
+    let mut options = CompileOptions::new().ok_or("Failed to create the shader compile options")?;
+    options.set_target_env(TargetEnv::Vulkan, EnvVersion::Vulkan1_0 as u32);
+    options.set_include_callback(|requested, include_type, _, _| {
+        let include = resolve_include(files, requested, include_type)?;
+        included.borrow_mut().push((include.resolved_name.clone(), include.content.clone()));
+        Ok(include)
+    });
+
+    let artifact = compiler
+        .compile_into_spirv(
+            source,
+            ShaderKind::Compute,
+            file_name,
+            "main",
+            Some(&options),
+        )
+        .map_err(|e| e.to_string())?;
+    drop(options);
+
+    let mut annotations = HashMap::new();
+    parse_annotations(file_name, source, &mut annotations)?;
+    for (included_name, content) in included.into_inner() {
+        parse_annotations(&included_name, &content, &mut annotations)?;
+    }
+
+    let words = artifact.as_binary().to_vec();
+    let parameters = ShaderParameters::reflect(&words, &annotations)?;
+    Ok(CompiledModule { words, parameters })
+}
+
+/// Resolves `#include` directives against the shader files. All shader includes live next
+/// to each other so relative and standard includes are treated the same.
+fn resolve_include(
+    files: &ShaderFiles,
+    requested: &str,
+    _include_type: IncludeType,
+) -> Result<ResolvedInclude, String> {
+    let (resolved_name, content) = files.read(requested).map_err(|e| format!("Failed to include {e}"))?;
+
+    Ok(ResolvedInclude {
+        resolved_name,
+        content,
+    })
+}
@@ -0,0 +1,66 @@
+#ifndef MATERIAL_GLSL
+#define MATERIAL_GLSL
+
+// Material table of the scene, indexed by the material ID `scene_signed_distance_function`
+// returns. Include after `scene.glsl`.
+
+// Pattern kinds, keep in sync with `scene.rs`.
+#define PATTERN_NONE 0u
+#define PATTERN_CHECKER 1u
+#define PATTERN_STRIPES 2u
+#define PATTERN_NOISE 3u
+
+// header:         x pattern kind.
+// properties:     roughness, metallic, transmission and refractive index.
+// pattern_params: x checker size, stripe width or noise scale, yzw the unit stripe direction.
+struct Material {
+    uvec4 header;
+    vec4 albedo;
+    vec4 emission;
+    vec4 properties;
+    vec4 pattern_colour;
+    vec4 pattern_params;
+};
+
+layout(set = 0, binding = 5) readonly buffer Materials {
+    Material materials[];
+} material_table;
+
+float lattice_value(in ivec3 cell) {
+    return float(hash(uint(cell.x) ^ hash(uint(cell.y) ^ hash(uint(cell.z)))) & 0xffffu) / 65535.0f;
+}
+
+// Smooth value noise between 0 and 1, with the integer hash of `gradient_noise`.
+float value_noise(in vec3 point) {
+    const vec3 cell = floor(point);
+    const vec3 f = point - cell;
+    const vec3 u = f * f * (3.0f - 2.0f * f);
+    const ivec3 i = ivec3(cell);
+
+    const float bottom_front = mix(lattice_value(i), lattice_value(i + ivec3(1, 0, 0)), u.x);
+    const float top_front = mix(lattice_value(i + ivec3(0, 1, 0)), lattice_value(i + ivec3(1, 1, 0)), u.x);
+    const float bottom_back = mix(lattice_value(i + ivec3(0, 0, 1)), lattice_value(i + ivec3(1, 0, 1)), u.x);
+    const float top_back = mix(lattice_value(i + ivec3(0, 1, 1)), lattice_value(i + ivec3(1, 1, 1)), u.x);
+    return mix(mix(bottom_front, top_front, u.y), mix(bottom_back, top_back, u.y), u.z);
+}
+
+// Share of the pattern colour at `point`, 0 keeps the albedo.
+float pattern_weight(in Material material, in vec3 point) {
+    const vec4 p = material.pattern_params;
+
+    switch (material.header.x) {
+        case PATTERN_CHECKER:
+            return mod(floor(point.x / p.x) + floor(point.y / p.x) + floor(point.z / p.x), 2.0f);
+        case PATTERN_STRIPES:
+            return step(1.0f, mod(dot(point, p.yzw) / p.x, 2.0f));
+        case PATTERN_NOISE:
+            return value_noise(point / p.x);
+    }
+    return 0.0f;
+}
+
+vec3 material_albedo(in Material material, in vec3 point) {
+    return mix(material.albedo.rgb, material.pattern_colour.rgb, pattern_weight(material, point));
+}
+
+#endif
@@ -0,0 +1,74 @@
+#ifndef PALETTE_GLSL
+#define PALETTE_GLSL
+
+// Palette of the shapes without a material, picked at runtime. Keep in sync with `palette.rs`.
+
+#define MAX_GRADIENT_STOPS 16
+
+// Palette kinds.
+#define PALETTE_COSINE 0u
+#define PALETTE_GRADIENT 1u
+
+// header: x palette kind, y gradient stop count.
+// a to d: cosine palette coefficients.
+// stops:  rgb colour and position of the gradient stops, in ascending order.
+struct Palette {
+    uvec4 header;
+    vec4 a;
+    vec4 b;
+    vec4 c;
+    vec4 d;
+    vec4 stops[MAX_GRADIENT_STOPS];
+};
+
+// Switching palettes blends from `previous` to `current` by `blend.x`. A switch during a blend
+// starts from the mix on screen, `older` blended to `previous` by `blend.y`.
+layout(set = 0, binding = 6) uniform Palettes {
+    Palette previous;
+    Palette current;
+    Palette older;
+    vec4 blend;
+} palettes;
+
+// Cosine gradient, see https://iquilezles.org/articles/palettes/
+vec3 cosine_palette(in Palette palette, in float t) {
+    return palette.a.rgb + palette.b.rgb * cos(6.28318f * (palette.c.rgb*t+palette.d.rgb));
+}
+
+// Repeats every unit of `t`, blending from the last stop around to the first.
+vec3 gradient_palette(in Palette palette, in float t) {
+    const uint count = palette.header.y;
+    const float x = fract(t);
+
+    uint next = 0u;
+    while (next < count && palette.stops[next].w <= x) {
+        next++;
+    }
+
+    const vec4 before = next == 0u ? palette.stops[count - 1u] - vec4(0.0f, 0.0f, 0.0f, 1.0f) : palette.stops[next - 1u];
+    const vec4 after = next == count ? palette.stops[0] + vec4(0.0f, 0.0f, 0.0f, 1.0f) : palette.stops[next];
+    const float span = after.w - before.w;
+    const float weight = span > 0.0f ? (x - before.w) / span : 0.0f;
+    return mix(before.rgb, after.rgb, weight);
+}
+
+vec3 evaluate_palette(in Palette palette, in float t) {
+    if (palette.header.x == PALETTE_GRADIENT) {
+        return gradient_palette(palette, t);
+    }
+    return cosine_palette(palette, t);
+}
+
+vec3 palette(in float t) {
+    const vec3 current = evaluate_palette(palettes.current, t);
+    if (palettes.blend.x >= 1.0f) {
+        return current;
+    }
+    vec3 previous = evaluate_palette(palettes.previous, t);
+    if (palettes.blend.y < 1.0f) {
+        previous = mix(evaluate_palette(palettes.older, t), previous, palettes.blend.y);
+    }
+    return mix(previous, current, palettes.blend.x);
+}
+
+#endif
@@ -0,0 +1,16 @@
+#ifndef PARAMETERS_GLSL
+#define PARAMETERS_GLSL
+
+// Tunable values of the scene shaders. The block is reflected at runtime and filled from the
+// parameter store, so members can be added here without touching the Rust side. A comment
+// starting with `@` after a member gives it a control in the parameter panel and an entry in
+// parameter files: `@range(min, max)`, `@default(values)` and `@colour`, see
+// `shader_parameters.rs`. `cpu_evaluator.rs` mirrors the members it uses by name.
+layout(set = 0, binding = 7) uniform Parameters {
+    float line_glow;      // @range(0.001, 0.1) @default(0.015)
+    float line_frequency; // @range(1, 32) @default(8)
+    float exposure;       // @range(0.1, 4) @default(1)
+    vec3 tint;            // @colour @default(1, 1, 1)
+} parameters;
+
+#endif
@@ -0,0 +1,165 @@
+#version 450
+
+#include "workgroup_size.glsl"
+
+layout(set = 0, binding = 0, rgba8) uniform writeonly image2D img;
+
+// Sum of every sample taken so far, averaged into `img` after each one.
+layout(set = 0, binding = 4, rgba32f) uniform image2D accumulation;
+
+// Camera as in `raymarch_cs.glsl`. `sample_index` counts the samples already in `accumulation`,
+// 0 starts a new image.
+layout(push_constant) uniform PushConstants {
+    vec3 camera_position;
+    float camera_yaw;
+    float camera_pitch;
+    float camera_focal_scale;
+    uint node_count;
+    uint sample_index;
+} push_constants;
+
+#define SCENE_NODE_COUNT push_constants.node_count
+#define SCENE_3D
+#include "scene.glsl"
+#include "palette.glsl"
+#include "material.glsl"
+#include "raymarch.glsl"
+#include "parameters.glsl"
+
+#define MAX_BOUNCES 8
+#define RUSSIAN_ROULETTE_BOUNCE 3
+
+// The sun is a bright lobe in the environment around `SUN_DIRECTION`.
+#define SUN_SHARPNESS 64.0f
+#define SUN_STRENGTH 12.0f
+
+uint rng_state;
+
+// PCG hash, see https://www.reedbeta.com/blog/hash-functions-for-gpu-rendering/
+uint pcg_hash(in uint value) {
+    const uint state = value * 747796405u + 2891336453u;
+    const uint word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Uniform in [0, 1), from the top 24 bits so the conversion is exact.
+float random_float() {
+    rng_state = pcg_hash(rng_state);
+    return float(rng_state >> 8u) / 16777216.0f;
+}
+
+vec3 random_unit_vector() {
+    const float z = random_float() * 2.0f - 1.0f;
+    const float angle = random_float() * TAU;
+    const float radius = sqrt(max(1.0f - z * z, 0.0f));
+    return vec3(radius * cos(angle), radius * sin(angle), z);
+}
+
+vec3 environment_light(in vec3 direction) {
+    const float sun = pow(max(dot(direction, normalize(SUN_DIRECTION)), 0.0f), SUN_SHARPNESS);
+    return sky_colour(direction) + SUN_COLOUR * SUN_STRENGTH * sun;
+}
+
+// Schlick's approximation of the share of light a dielectric reflects.
+float reflectance(in float cosine, in float refraction_ratio) {
+    float r0 = (1.0f - refraction_ratio) / (1.0f + refraction_ratio);
+    r0 = r0 * r0;
+    return r0 + (1.0f - r0) * pow(1.0f - cosine, 5.0f);
+}
+
+// Follows one path from the camera and returns the light it carries back.
+vec3 trace_path(in vec3 origin, in vec3 direction) {
+    vec3 radiance = vec3(0.0f);
+    vec3 throughput = vec3(1.0f);
+    float side = 1.0f;
+
+    for (int bounce = 0; bounce < MAX_BOUNCES; bounce++) {
+        const float t = march(origin, direction, side);
+        if (t < 0.0f) {
+            radiance += throughput * environment_light(direction);
+            break;
+        }
+
+        const vec3 point = origin + direction * t;
+        const vec3 outward_normal = surface_normal(point);
+        const bool front_face = dot(direction, outward_normal) < 0.0f;
+        const vec3 normal = front_face ? outward_normal : -outward_normal;
+        const SurfaceMaterial material = surface_material(point);
+        radiance += throughput * material.emission;
+
+        // Picks one lobe of the material at random: glass, metal, or a dielectric coat
+        // over a diffuse base.
+        const float cosine = min(dot(-direction, normal), 1.0f);
+        const vec3 fuzz = material.roughness * random_unit_vector();
+        if (random_float() < material.transmission) {
+            const float refraction_ratio = front_face ? 1.0f / material.refractive_index : material.refractive_index;
+            const float sine = sqrt(1.0f - cosine * cosine);
+            if (refraction_ratio * sine > 1.0f || reflectance(cosine, refraction_ratio) > random_float()) {
+                direction = reflect(direction, normal);
+            } else {
+                direction = refract(direction, normal, refraction_ratio);
+            }
+            throughput *= material.albedo;
+        } else if (random_float() < material.metallic) {
+            direction = normalize(reflect(direction, normal) + fuzz);
+            if (dot(direction, normal) <= 0.0f) {
+                break;
+            }
+            throughput *= material.albedo;
+        } else if (reflectance(cosine, 1.0f / material.refractive_index) > random_float()) {
+            direction = normalize(reflect(direction, normal) + fuzz);
+            if (dot(direction, normal) <= 0.0f) {
+                break;
+            }
+        } else {
+            direction = normalize(normal + random_unit_vector());
+            throughput *= material.albedo;
+        }
+
+        // Leave the surface on the side the new direction points to.
+        const float exit_side = dot(direction, normal) > 0.0f ? 1.0f : -1.0f;
+        origin = point + normal * exit_side * 4.0f * SURFACE_EPSILON;
+        side = world_signed_distance_function(origin) < 0.0f ? -1.0f : 1.0f;
+
+        // Dim paths end early, the survivors carry their share of the light.
+        if (bounce >= RUSSIAN_ROULETTE_BOUNCE) {
+            const float survival = max(throughput.x, max(throughput.y, throughput.z));
+            if (random_float() >= survival) {
+                break;
+            }
+            throughput /= survival;
+        }
+    }
+
+    return radiance;
+}
+
+void main() {
+    const ivec2 pos = ivec2(gl_GlobalInvocationID.xy);
+    const ivec2 dims = ivec2(imageSize(img));
+    if (any(greaterThanEqual(pos, dims))) {
+        return;
+    }
+
+    const uint sample_index = push_constants.sample_index;
+    rng_state = pcg_hash(uint(pos.x) + pcg_hash(uint(pos.y) + pcg_hash(sample_index)));
+
+    // Every sample looks through a random point of the pixel, which also smooths the edges.
+    const vec2 jitter = vec2(random_float(), random_float()) - 0.5f;
+    const vec2 screen_pos = ((vec2(pos.xy) + jitter) * 2.0f - vec2(dims.xy)) / float(min(dims.x, dims.y));
+    const vec3 direction = camera_ray_direction(screen_pos, push_constants.camera_yaw, push_constants.camera_pitch, push_constants.camera_focal_scale);
+
+    vec3 colour = trace_path(push_constants.camera_position, direction);
+    if (any(isnan(colour)) || any(isinf(colour))) {
+        colour = vec3(0.0f);
+    }
+
+    vec3 sum = colour;
+    if (sample_index > 0u) {
+        sum += imageLoad(accumulation, pos).rgb;
+    }
+    imageStore(accumulation, pos, vec4(sum, 1.0f));
+    // Exposure and tint only change the shown image, not the samples added up so far.
+    const vec3 average = sum / float(sample_index + 1u) * parameters.exposure * parameters.tint;
+    imageStore(img, pos, vec4(pow(average, vec3(1.0f / 2.2f)), 1.0f));
+}
@@ -0,0 +1,107 @@
+#ifndef RAYMARCH_GLSL
+#define RAYMARCH_GLSL
+
+// Sphere tracing of the scene over a ground plane, shared by the raymarched and the path
+// traced view. Include after `scene.glsl`, `palette.glsl` and `material.glsl`.
+
+#define MAX_MARCH_STEPS 160
+#define MAX_RAY_DISTANCE 60.0f
+#define SURFACE_EPSILON 1e-3f
+
+// The scene stands on a ground plane below the origin.
+#define GROUND_HEIGHT -1.0f
+
+#define SUN_DIRECTION vec3(-0.4f, 0.8f, 0.45f)
+#define SUN_COLOUR vec3(1.0f, 0.95f, 0.85f)
+#define SKY_COLOUR vec3(0.45f, 0.6f, 0.85f)
+#define HORIZON_COLOUR vec3(0.8f, 0.85f, 0.9f)
+
+float ground_signed_distance_function(in vec3 point) {
+    return point.y - GROUND_HEIGHT;
+}
+
+float world_signed_distance_function(in vec3 point) {
+    return min(scene_signed_distance_function(point), ground_signed_distance_function(point));
+}
+
+// Perspective fly camera, see `Camera3D` in `camera.rs`. A yaw of 0 looks down -z and turns
+// left as it grows, pitch looks up. The focal scale is the tangent of half the vertical field of view.
+vec3 camera_ray_direction(in vec2 screen_pos, in float yaw, in float pitch, in float focal_scale) {
+    const vec3 forward = vec3(-sin(yaw) * cos(pitch), sin(pitch), -cos(yaw) * cos(pitch));
+    const vec3 right = vec3(cos(yaw), 0.0f, -sin(yaw));
+    const vec3 up = cross(right, forward);
+    return normalize(forward + (right * screen_pos.x + up * screen_pos.y) * focal_scale);
+}
+
+// Distance along the ray to the first surface, negative if it hits nothing. A `side` of -1
+// marches inside solids, towards where the distance turns positive again.
+// The hit threshold grows with distance so far surfaces take fewer steps.
+float march(in vec3 origin, in vec3 direction, in float side) {
+    float t = 0.0f;
+    for (int i = 0; i < MAX_MARCH_STEPS; i++) {
+        const float signed_distance = side * world_signed_distance_function(origin + direction * t);
+        if (signed_distance < SURFACE_EPSILON * max(t, 1.0f)) {
+            return t;
+        }
+        t += signed_distance;
+        if (t > MAX_RAY_DISTANCE) {
+            break;
+        }
+    }
+    return -1.0f;
+}
+
+// Normalized gradient of the distance field from four samples on a tetrahedron.
+vec3 surface_normal(in vec3 point) {
+    const vec2 e = vec2(1.0f, -1.0f) * 0.5773f * 5e-4f;
+    return normalize(
+        e.xyy * world_signed_distance_function(point + e.xyy) +
+        e.yyx * world_signed_distance_function(point + e.yyx) +
+        e.yxy * world_signed_distance_function(point + e.yxy) +
+        e.xxx * world_signed_distance_function(point + e.xxx)
+    );
+}
+
+vec3 sky_colour(in vec3 direction) {
+    return mix(HORIZON_COLOUR, SKY_COLOUR, clamp(direction.y * 2.0f, 0.0f, 1.0f));
+}
+
+// Roughness of the ground and of shapes without a material, a Blinn-Phong exponent of 48.
+#define DEFAULT_ROUGHNESS 0.4472136f
+#define DEFAULT_REFRACTIVE_INDEX 1.5f
+
+// Material properties at a point of the world, from the material table or the defaults.
+struct SurfaceMaterial {
+    vec3 albedo;
+    vec3 emission;
+    float roughness;
+    float metallic;
+    float transmission;
+    float refractive_index;
+};
+
+// Checkered ground, the scene material, or the scene coloured by the palette without one.
+SurfaceMaterial surface_material(in vec3 point) {
+    uint material_id;
+    const float scene_distance = scene_signed_distance_function(point, material_id);
+    vec3 albedo;
+    if (ground_signed_distance_function(point) < scene_distance) {
+        const float checker = mod(floor(point.x) + floor(point.z), 2.0f);
+        albedo = vec3(0.35f + 0.15f * checker);
+    } else if (material_id == NO_MATERIAL) {
+        albedo = palette(length(point.xy) + iTime);
+    } else {
+        const Material material = material_table.materials[material_id];
+        const vec4 properties = material.properties;
+        return SurfaceMaterial(material_albedo(material, point), material.emission.rgb, properties.x, properties.y, properties.z, properties.w);
+    }
+    return SurfaceMaterial(albedo, vec3(0.0f), DEFAULT_ROUGHNESS, 0.0f, 0.0f, DEFAULT_REFRACTIVE_INDEX);
+}
+
+// Blinn-Phong exponent of about the same highlight as a microfacet surface of `roughness`.
+float specular_exponent(in float roughness) {
+    const float alpha = roughness * roughness;
+    return 2.0f / max(alpha * alpha, 1e-4f) - 2.0f;
+}
+
+#endif
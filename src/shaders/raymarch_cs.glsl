@@ -0,0 +1,108 @@
+#version 450
+
+#include "workgroup_size.glsl"
+
+layout(set = 0, binding = 0, rgba8) uniform writeonly image2D img;
+
+// Perspective fly camera, see `camera_ray_direction` in `raymarch.glsl`.
+layout(push_constant) uniform PushConstants {
+    vec3 camera_position;
+    float camera_yaw;
+    float camera_pitch;
+    float camera_focal_scale;
+    uint node_count;
+} push_constants;
+
+#define SCENE_NODE_COUNT push_constants.node_count
+#define SCENE_3D
+#include "scene.glsl"
+#include "palette.glsl"
+#include "material.glsl"
+#include "raymarch.glsl"
+#include "parameters.glsl"
+
+#define SHADOW_STEPS 64
+#define SHADOW_SOFTNESS 8.0f
+#define OCCLUSION_SAMPLES 5
+
+// Highlight strength of surfaces that are not metallic, metals tint it with their albedo.
+#define SPECULAR_STRENGTH 0.5f
+
+// Penumbra from the closest miss along the shadow ray, 0 in full shadow and 1 when lit.
+float soft_shadow(in vec3 origin, in vec3 direction, in float min_t, in float max_t) {
+    float light = 1.0f;
+    float t = min_t;
+    for (int i = 0; i < SHADOW_STEPS && t < max_t; i++) {
+        const float signed_distance = world_signed_distance_function(origin + direction * t);
+        if (signed_distance < SURFACE_EPSILON) {
+            return 0.0f;
+        }
+        light = min(light, SHADOW_SOFTNESS * signed_distance / t);
+        t += clamp(signed_distance, 0.01f, 0.25f);
+    }
+    return clamp(light, 0.0f, 1.0f);
+}
+
+// Compares the distance field with the distance along the normal, nearby surfaces make it smaller.
+float ambient_occlusion(in vec3 point, in vec3 normal) {
+    float occlusion = 0.0f;
+    float weight = 1.0f;
+    for (int i = 0; i < OCCLUSION_SAMPLES; i++) {
+        const float h = 0.02f + 0.1f * float(i);
+        occlusion += (h - world_signed_distance_function(point + normal * h)) * weight;
+        weight *= 0.8f;
+    }
+    return clamp(1.0f - 2.0f * occlusion, 0.0f, 1.0f);
+}
+
+// Lambert diffuse and Blinn-Phong specular from the sun, plus sky light as ambient and the
+// material's emission. Metals also mirror the sky, transmission is left to the path tracer.
+vec3 shade(in vec3 point, in vec3 view_direction) {
+    const vec3 normal = surface_normal(point);
+    const vec3 light_direction = normalize(SUN_DIRECTION);
+    const vec3 half_vector = normalize(light_direction - view_direction);
+    const SurfaceMaterial material = surface_material(point);
+
+    // Sharper highlights are brighter, so every roughness reflects about as much light.
+    const float exponent = specular_exponent(material.roughness);
+    const float normalization = (exponent + 8.0f) / (specular_exponent(DEFAULT_ROUGHNESS) + 8.0f);
+
+    const float diffuse = max(dot(normal, light_direction), 0.0f);
+    const float specular = diffuse > 0.0f ? normalization * pow(max(dot(normal, half_vector), 0.0f), exponent) : 0.0f;
+    const float shadow = soft_shadow(point + normal * 2.0f * SURFACE_EPSILON, light_direction, 0.02f, 20.0f);
+    const float occlusion = ambient_occlusion(point, normal);
+    const float sky = 0.5f + 0.5f * normal.y;
+
+    const vec3 diffuse_colour = material.albedo * (1.0f - material.metallic);
+    const vec3 specular_colour = mix(vec3(SPECULAR_STRENGTH), material.albedo, material.metallic);
+    const vec3 mirrored_sky = sky_colour(reflect(view_direction, normal)) * material.metallic * (1.0f - material.roughness);
+    return diffuse_colour * (SUN_COLOUR * diffuse * shadow + SKY_COLOUR * 0.35f * sky * occlusion)
+        + SUN_COLOUR * specular_colour * specular * shadow
+        + material.albedo * mirrored_sky * occlusion
+        + material.emission;
+}
+
+void main() {
+    const ivec2 pos = ivec2(gl_GlobalInvocationID.xy);
+    const ivec2 dims = ivec2(imageSize(img));
+    if (any(greaterThanEqual(pos, dims))) {
+        return;
+    }
+
+    // Same centred screen coordinates as the 2D shader, y up.
+    const vec2 screen_pos = (vec2(pos.xy) * 2.0f - vec2(dims.xy)) / float(min(dims.x, dims.y));
+    const vec3 origin = push_constants.camera_position;
+    const vec3 direction = camera_ray_direction(screen_pos, push_constants.camera_yaw, push_constants.camera_pitch, push_constants.camera_focal_scale);
+
+    vec3 colour = sky_colour(direction);
+    const float t = march(origin, direction, 1.0f);
+    if (t >= 0.0f) {
+        // Distant surfaces fade into the sky.
+        const float fog = 1.0f - exp(-0.002f * t * t);
+        colour = mix(shade(origin + direction * t, direction), colour, fog);
+    }
+
+    // Lighting is linear, the image is plain UNORM so it is gamma encoded here.
+    colour *= parameters.exposure * parameters.tint;
+    imageStore(img, pos, vec4(pow(colour, vec3(1.0f / 2.2f)), 1.0f));
+}
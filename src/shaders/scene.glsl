@@ -0,0 +1,345 @@
+#ifndef SCENE_GLSL
+#define SCENE_GLSL
+
+// Scene program shared by the 2D and the raymarched compute shaders. The including file
+// defines `SCENE_NODE_COUNT`, the number of nodes to run, usually from its push constants,
+// and `SCENE_3D` if it evaluates the scene in 3D. The 2D view shows the z = 0 plane, where
+// 2D shapes keep their exact distances and extrusions are skipped.
+
+#define MAX_DISTANCE 1e10f
+
+// Primitive kinds, keep in sync with `scene.rs`.
+#define PRIMITIVE_EQUILATERAL_TRIANGLE 0u
+#define PRIMITIVE_CIRCLE 1u
+#define PRIMITIVE_BOX 2u
+#define PRIMITIVE_ROUNDED_BOX 3u
+#define PRIMITIVE_SEGMENT 4u
+#define PRIMITIVE_ARC 5u
+#define PRIMITIVE_ELLIPSE 6u
+#define PRIMITIVE_REGULAR_POLYGON 7u
+#define PRIMITIVE_STAR 8u
+#define PRIMITIVE_HEXAGON 9u
+#define PRIMITIVE_VESICA 10u
+#define PRIMITIVE_QUADRATIC_BEZIER 11u
+#define PRIMITIVE_POLYGON 12u
+#define PRIMITIVE_SPHERE 20u
+#define PRIMITIVE_BOX_3D 21u
+#define PRIMITIVE_ROUND_BOX_3D 22u
+#define PRIMITIVE_TORUS 23u
+#define PRIMITIVE_CAPSULE 24u
+#define PRIMITIVE_CYLINDER 25u
+#define PRIMITIVE_CONE 26u
+#define PRIMITIVE_PLANE 27u
+#define PRIMITIVE_OCTAHEDRON 28u
+
+// Operator kinds, keep in sync with `scene.rs`.
+#define OPERATOR_UNION 100u
+#define OPERATOR_INTERSECTION 101u
+#define OPERATOR_SUBTRACTION 102u
+#define OPERATOR_SMOOTH_UNION 103u
+#define OPERATOR_SMOOTH_INTERSECTION 104u
+#define OPERATOR_SMOOTH_SUBTRACTION 105u
+#define OPERATOR_ONION 110u
+#define OPERATOR_ROUND 111u
+#define OPERATOR_ANNULAR 112u
+#define OPERATOR_EXTRUDE 113u
+#define OPERATOR_MATERIAL 114u
+
+// Domain operator kinds, keep in sync with `scene.rs`.
+#define DOMAIN_TRANSLATE 200u
+#define DOMAIN_ROTATE 201u
+#define DOMAIN_SCALE 202u
+#define DOMAIN_REPEAT 203u
+#define DOMAIN_REPEAT_LIMITED 204u
+#define DOMAIN_POLAR_REPEAT 205u
+#define DOMAIN_MIRROR 206u
+#define DOMAIN_TWIST 207u
+#define DOMAIN_WARP 208u
+#define DOMAIN_REVOLVE 209u
+#define DOMAIN_END 255u
+
+#define FIRST_PRIMITIVE_3D PRIMITIVE_SPHERE
+#define FIRST_OPERATOR OPERATOR_UNION
+#define FIRST_DOMAIN_OPERATOR DOMAIN_TRANSLATE
+#define MAX_STACK_DEPTH 16
+#define MAX_DOMAIN_DEPTH 16
+
+// Scene nodes are stored in postfix order. Primitives push a distance on the stack,
+// operators pop their operands and push the result.
+//
+// Primitives - header: x kind, y first polygon vertex, z polygon vertex count, w sides or points.
+//              transform: xy translation, z rotation, w uniform scale.
+//              3D primitives: transform xyz translation, w uniform scale, rotation a unit quaternion.
+// Operators  - header: x kind, y blend or layers. params[0].x: radius, thickness or half depth.
+//              Materials: header y material ID, an index into the material table.
+// Domains    - header: x kind, y polar repetitions. params[0]: operator parameters.
+//              A domain node transforms the point for every node up to its `DOMAIN_END`.
+//              2D domains transform xy and leave z alone, scaling is uniform.
+struct SceneNode {
+    uvec4 header;
+    vec4 transform;
+    vec4 rotation;
+    vec4 params[2];
+};
+
+layout(set = 0, binding = 1) readonly buffer Scene {
+    SceneNode nodes[];
+} scene;
+
+layout(set = 0, binding = 2) readonly buffer PolygonVertices {
+    vec2 vertices[];
+} polygon_vertices;
+
+#include "shadertoy_inputs.glsl"
+
+#define SDF_POLYGON_VERTICES polygon_vertices.vertices
+#include "sdf_2d.glsl"
+#include "sdf_3d.glsl"
+#include "sdf_operators.glsl"
+
+float shape_signed_distance_function(in SceneNode primitive, in vec2 point) {
+    const vec4 p0 = primitive.params[0];
+    const vec4 p1 = primitive.params[1];
+
+    float signed_distance = MAX_DISTANCE;
+    switch (primitive.header.x) {
+        case PRIMITIVE_EQUILATERAL_TRIANGLE:
+            signed_distance = equalateral_triangle_signed_distance_function(point, p0.x);
+            break;
+        case PRIMITIVE_CIRCLE:
+            signed_distance = circle_signed_distance_function(point, p0.x);
+            break;
+        case PRIMITIVE_BOX:
+            signed_distance = box_signed_distance_function(point, p0.xy);
+            break;
+        case PRIMITIVE_ROUNDED_BOX:
+            signed_distance = rounded_box_signed_distance_function(point, p0.xy, p1);
+            break;
+        case PRIMITIVE_SEGMENT:
+            signed_distance = segment_signed_distance_function(point, p0.xy, p0.zw, p1.x);
+            break;
+        case PRIMITIVE_ARC:
+            signed_distance = arc_signed_distance_function(point, p0.x, p0.y, p0.z);
+            break;
+        case PRIMITIVE_ELLIPSE:
+            signed_distance = ellipse_signed_distance_function(point, p0.xy);
+            break;
+        case PRIMITIVE_REGULAR_POLYGON:
+            signed_distance = regular_polygon_signed_distance_function(point, p0.x, primitive.header.w);
+            break;
+        case PRIMITIVE_STAR:
+            signed_distance = star_signed_distance_function(point, p0.x, primitive.header.w, p0.y);
+            break;
+        case PRIMITIVE_HEXAGON:
+            signed_distance = hexagon_signed_distance_function(point, p0.x);
+            break;
+        case PRIMITIVE_VESICA:
+            signed_distance = vesica_signed_distance_function(point, p0.x, p0.y);
+            break;
+        case PRIMITIVE_QUADRATIC_BEZIER:
+            signed_distance = quadratic_bezier_signed_distance_function(point, p0.xy, p0.zw, p1.xy, p1.z);
+            break;
+        case PRIMITIVE_POLYGON:
+            signed_distance = polygon_signed_distance_function(point, primitive.header.y, primitive.header.z);
+            break;
+    }
+
+    return signed_distance;
+}
+
+float solid_signed_distance_function(in SceneNode primitive, in vec3 point) {
+    const vec4 p0 = primitive.params[0];
+    const vec4 p1 = primitive.params[1];
+
+    float signed_distance = MAX_DISTANCE;
+    switch (primitive.header.x) {
+        case PRIMITIVE_SPHERE:
+            signed_distance = sphere_signed_distance_function(point, p0.x);
+            break;
+        case PRIMITIVE_BOX_3D:
+            signed_distance = box_3d_signed_distance_function(point, p0.xyz);
+            break;
+        case PRIMITIVE_ROUND_BOX_3D:
+            signed_distance = round_box_3d_signed_distance_function(point, p0.xyz, p0.w);
+            break;
+        case PRIMITIVE_TORUS:
+            signed_distance = torus_signed_distance_function(point, p0.x, p0.y);
+            break;
+        case PRIMITIVE_CAPSULE:
+            signed_distance = capsule_signed_distance_function(point, p0.xyz, p1.xyz, p1.w);
+            break;
+        case PRIMITIVE_CYLINDER:
+            signed_distance = cylinder_signed_distance_function(point, p0.x, p0.y);
+            break;
+        case PRIMITIVE_CONE:
+            signed_distance = cone_signed_distance_function(point, p0.x, p0.y, p0.z);
+            break;
+        case PRIMITIVE_PLANE:
+            signed_distance = plane_signed_distance_function(point, p0.xyz, p0.w);
+            break;
+        case PRIMITIVE_OCTAHEDRON:
+            signed_distance = octahedron_signed_distance_function(point, p0.x);
+            break;
+    }
+
+    return signed_distance;
+}
+
+// Material ID of shapes without a material.
+#define NO_MATERIAL 0xffffffffu
+
+float primitive_signed_distance_function(in SceneNode primitive, in vec3 point) {
+    const float scale = primitive.transform.w;
+    if (primitive.header.x >= FIRST_PRIMITIVE_3D) {
+        const vec3 local = scale_3d_domain(rotate_3d_domain(translate_3d_domain(point, primitive.transform.xyz), primitive.rotation), scale);
+        return solid_signed_distance_function(primitive, local) * scale;
+    }
+
+    const vec2 local = scale_domain(rotate_domain(translate_domain(point.xy, primitive.transform.xy), primitive.transform.z), scale);
+    return shape_signed_distance_function(primitive, local) * scale;
+}
+
+float binary_operator(in SceneNode operator, in float a, in float b) {
+    const float radius = operator.params[0].x;
+    const uint blend = operator.header.y;
+
+    switch (operator.header.x) {
+        case OPERATOR_UNION:
+            return union_operator(a, b);
+        case OPERATOR_INTERSECTION:
+            return intersection_operator(a, b);
+        case OPERATOR_SUBTRACTION:
+            return subtraction_operator(a, b);
+        case OPERATOR_SMOOTH_UNION:
+            return smooth_union_operator(a, b, radius, blend);
+        case OPERATOR_SMOOTH_INTERSECTION:
+            return smooth_intersection_operator(a, b, radius, blend);
+        case OPERATOR_SMOOTH_SUBTRACTION:
+            return smooth_subtraction_operator(a, b, radius, blend);
+    }
+    return a;
+}
+
+float modifier_operator(in SceneNode operator, in float signed_distance, in vec3 point) {
+    const float thickness = operator.params[0].x;
+
+    switch (operator.header.x) {
+        case OPERATOR_ONION:
+            return onion_operator(signed_distance, thickness, operator.header.y);
+        case OPERATOR_ROUND:
+            return round_operator(signed_distance, thickness);
+        case OPERATOR_ANNULAR:
+            return annular_operator(signed_distance, thickness);
+#ifdef SCENE_3D
+        case OPERATOR_EXTRUDE:
+            return extrusion_operator(signed_distance, point.z, thickness);
+#endif
+    }
+    return signed_distance;
+}
+
+// The material of whichever shape decides the distance, the nearer one for unions and the
+// farther one for intersections and subtractions.
+uint binary_operator_material(in SceneNode operator, in float a, in float b, in uint material_a, in uint material_b) {
+    switch (operator.header.x) {
+        case OPERATOR_INTERSECTION:
+        case OPERATOR_SMOOTH_INTERSECTION:
+            return a > b ? material_a : material_b;
+        case OPERATOR_SUBTRACTION:
+        case OPERATOR_SMOOTH_SUBTRACTION:
+            return a > -b ? material_a : material_b;
+    }
+    return a < b ? material_a : material_b;
+}
+
+vec2 planar_domain_operator(in SceneNode operator, in vec2 point) {
+    const vec4 p0 = operator.params[0];
+
+    switch (operator.header.x) {
+        case DOMAIN_TRANSLATE:
+            return translate_domain(point, p0.xy);
+        case DOMAIN_ROTATE:
+            return rotate_domain(point, p0.x);
+        case DOMAIN_REPEAT:
+            return repeat_domain(point, p0.xy);
+        case DOMAIN_REPEAT_LIMITED:
+            return repeat_limited_domain(point, p0.xy, p0.zw);
+        case DOMAIN_POLAR_REPEAT:
+            return polar_repeat_domain(point, operator.header.y);
+        case DOMAIN_MIRROR:
+            return mirror_domain(point, p0.xy);
+        case DOMAIN_TWIST:
+            return twist_domain(point, p0.x);
+        case DOMAIN_WARP:
+            return warp_domain(point, p0.x, p0.y, p0.z * iTime);
+    }
+    return point;
+}
+
+vec3 domain_operator(in SceneNode operator, in vec3 point) {
+    switch (operator.header.x) {
+        case DOMAIN_SCALE:
+            return scale_3d_domain(point, operator.params[0].x);
+        case DOMAIN_REVOLVE:
+            return revolution_domain(point, operator.params[0].x);
+    }
+    return vec3(planar_domain_operator(operator, point.xy), point.z);
+}
+
+float scene_signed_distance_function(in vec3 point, out uint material) {
+    float stack[MAX_STACK_DEPTH];
+    uint material_stack[MAX_STACK_DEPTH];
+    uint depth = 0u;
+
+    // Parent space point and the factor that converts distances back into it.
+    vec4 domain_stack[MAX_DOMAIN_DEPTH];
+    uint domain_depth = 0u;
+
+    for (uint i = 0u; i < SCENE_NODE_COUNT; i++) {
+        const SceneNode node = scene.nodes[i];
+        const uint kind = node.header.x;
+
+        if (kind < FIRST_OPERATOR) {
+            stack[depth] = primitive_signed_distance_function(node, point);
+            material_stack[depth] = NO_MATERIAL;
+            depth++;
+        } else if (kind <= OPERATOR_SMOOTH_SUBTRACTION) {
+            depth--;
+            material_stack[depth - 1u] = binary_operator_material(node, stack[depth - 1u], stack[depth], material_stack[depth - 1u], material_stack[depth]);
+            stack[depth - 1u] = binary_operator(node, stack[depth - 1u], stack[depth]);
+        } else if (kind == OPERATOR_MATERIAL) {
+            if (material_stack[depth - 1u] == NO_MATERIAL) {
+                material_stack[depth - 1u] = node.header.y;
+            }
+        } else if (kind < FIRST_DOMAIN_OPERATOR) {
+            stack[depth - 1u] = modifier_operator(node, stack[depth - 1u], point);
+        } else if (kind == DOMAIN_END) {
+            domain_depth--;
+            point = domain_stack[domain_depth].xyz;
+            stack[depth - 1u] *= domain_stack[domain_depth].w;
+        } else {
+            const float distance_factor = kind == DOMAIN_SCALE ? node.params[0].x : 1.0;
+            domain_stack[domain_depth] = vec4(point, distance_factor);
+            domain_depth++;
+            point = domain_operator(node, point);
+        }
+    }
+
+    material = depth > 0u ? material_stack[0] : NO_MATERIAL;
+    return depth > 0u ? stack[0] : MAX_DISTANCE;
+}
+
+float scene_signed_distance_function(in vec3 point) {
+    uint material;
+    return scene_signed_distance_function(point, material);
+}
+
+float scene_signed_distance_function(in vec2 point, out uint material) {
+    return scene_signed_distance_function(vec3(point, 0.0), material);
+}
+
+float scene_signed_distance_function(in vec2 point) {
+    return scene_signed_distance_function(vec3(point, 0.0));
+}
+
+#endif
@@ -0,0 +1,204 @@
+#ifndef SDF_2D_GLSL
+#define SDF_2D_GLSL
+
+// 2D signed distance functions, most are adapted from https://iquilezles.org/articles/distfunctions2d/.
+// Every function expects the point in the local space of the shape, centred on the origin.
+
+#include "sdf_domain.glsl"
+
+#define ROOT_3 1.73205080757f
+#define PI 3.14159265359f
+
+float dot2(in vec2 v) {
+    return dot(v, v);
+}
+
+float equalateral_triangle_signed_distance_function(in vec2 point, in float radius) {
+    const float k = ROOT_3;
+    point = mirror_domain(point, vec2(1.0, 0.0));
+    point.x -= radius;
+    point.y = point.y + radius / k;
+
+    if(point.x + k * point.y > 0.0) {
+        point = vec2(point.x - k * point.y, -k * point.x - point.y) / 2.0;
+    }
+
+    point.x -= clamp(point.x, -2.0 * radius, 0.0);
+    return -length(point) * sign(point.y);
+}
+
+float circle_signed_distance_function(in vec2 point, in float radius) {
+    return length(point) - radius;
+}
+
+float box_signed_distance_function(in vec2 point, in vec2 half_extents) {
+    const vec2 d = abs(point) - half_extents;
+    return length(max(d, 0.0)) + min(max(d.x, d.y), 0.0);
+}
+
+// Corner radii are ordered top right, bottom right, top left, bottom left.
+float rounded_box_signed_distance_function(in vec2 point, in vec2 half_extents, in vec4 corner_radii) {
+    corner_radii.xy = (point.x > 0.0) ? corner_radii.xy : corner_radii.zw;
+    corner_radii.x = (point.y > 0.0) ? corner_radii.x : corner_radii.y;
+    const vec2 q = abs(point) - half_extents + corner_radii.x;
+    return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - corner_radii.x;
+}
+
+float segment_signed_distance_function(in vec2 point, in vec2 start, in vec2 end, in float thickness) {
+    const vec2 pa = point - start;
+    const vec2 ba = end - start;
+    const float h = clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0);
+    return length(pa - ba * h) - thickness;
+}
+
+// The arc is symmetric around the y axis and spans `aperture` radians to each side.
+float arc_signed_distance_function(in vec2 point, in float aperture, in float radius, in float thickness) {
+    const vec2 sc = vec2(sin(aperture), cos(aperture));
+    point.x = abs(point.x);
+    return ((sc.y * point.x > sc.x * point.y) ? length(point - sc * radius) : abs(length(point) - radius)) - thickness;
+}
+
+float ellipse_signed_distance_function(in vec2 point, in vec2 radii) {
+    if (abs(radii.x - radii.y) < 1e-5) {
+        return circle_signed_distance_function(point, radii.x);
+    }
+
+    point = abs(point);
+    if (point.x > point.y) {
+        point = point.yx;
+        radii = radii.yx;
+    }
+
+    const float l = radii.y * radii.y - radii.x * radii.x;
+    const float m = radii.x * point.x / l;
+    const float m2 = m * m;
+    const float n = radii.y * point.y / l;
+    const float n2 = n * n;
+    const float c = (m2 + n2 - 1.0) / 3.0;
+    const float c3 = c * c * c;
+    const float q = c3 + m2 * n2 * 2.0;
+    const float d = c3 + m2 * n2;
+    const float g = m + m * n2;
+
+    float co;
+    if (d < 0.0) {
+        const float h = acos(q / c3) / 3.0;
+        const float s = cos(h);
+        const float t = sin(h) * ROOT_3;
+        const float rx = sqrt(-c * (s + t + 2.0) + m2);
+        const float ry = sqrt(-c * (s - t + 2.0) + m2);
+        co = (ry + sign(l) * rx + abs(g) / (rx * ry) - m) / 2.0;
+    } else {
+        const float h = 2.0 * m * n * sqrt(d);
+        const float s = sign(q + h) * pow(abs(q + h), 1.0 / 3.0);
+        const float u = sign(q - h) * pow(abs(q - h), 1.0 / 3.0);
+        const float rx = -s - u - c * 4.0 + 2.0 * m2;
+        const float ry = (s - u) * ROOT_3;
+        const float rm = sqrt(rx * rx + ry * ry);
+        co = (ry / sqrt(rm - rx) + 2.0 * g / rm - m) / 2.0;
+    }
+
+    const vec2 r = radii * vec2(co, sqrt(1.0 - co * co));
+    return length(r - point) * sign(point.y - r.y);
+}
+
+float regular_polygon_signed_distance_function(in vec2 point, in float radius, in uint sides) {
+    const float an = PI / float(sides);
+    const vec2 acs = vec2(cos(an), sin(an));
+    const float bn = mod(atan(point.x, point.y), 2.0 * an) - an;
+    point = length(point) * vec2(cos(bn), abs(sin(bn)));
+    point -= radius * acs;
+    point.y += clamp(-point.y, 0.0, radius * acs.y);
+    return length(point) * sign(point.x);
+}
+
+// `density` is between 2 and `points`, lower values give sharper tips.
+float star_signed_distance_function(in vec2 point, in float radius, in uint points, in float density) {
+    const float an = PI / float(points);
+    const float en = PI / density;
+    const vec2 acs = vec2(cos(an), sin(an));
+    const vec2 ecs = vec2(cos(en), sin(en));
+    const float bn = mod(atan(point.x, point.y), 2.0 * an) - an;
+    point = length(point) * vec2(cos(bn), abs(sin(bn)));
+    point -= radius * acs;
+    point += ecs * clamp(-dot(point, ecs), 0.0, radius * acs.y / ecs.y);
+    return length(point) * sign(point.x);
+}
+
+float hexagon_signed_distance_function(in vec2 point, in float radius) {
+    const vec3 k = vec3(-0.866025404, 0.5, 0.577350269);
+    point = abs(point);
+    point -= 2.0 * min(dot(k.xy, point), 0.0) * k.xy;
+    point -= vec2(clamp(point.x, -k.z * radius, k.z * radius), radius);
+    return length(point) * sign(point.y);
+}
+
+// Intersection of two circles of `radius` whose centres are `offset` away from the origin.
+float vesica_signed_distance_function(in vec2 point, in float radius, in float offset) {
+    point = abs(point);
+    const float b = sqrt(radius * radius - offset * offset);
+    return ((point.y - b) * offset > point.x * b) ? length(point - vec2(0.0, b)) : length(point - vec2(-offset, 0.0)) - radius;
+}
+
+float quadratic_bezier_signed_distance_function(in vec2 point, in vec2 start, in vec2 control, in vec2 end, in float thickness) {
+    const vec2 a = control - start;
+    const vec2 b = start - 2.0 * control + end;
+    const vec2 c = a * 2.0;
+    const vec2 d = start - point;
+
+    // A straight, evenly spaced curve has no quadratic term.
+    if (dot(b, b) < 1e-8) {
+        return segment_signed_distance_function(point, start, end, thickness);
+    }
+
+    const float kk = 1.0 / dot(b, b);
+    const float kx = kk * dot(a, b);
+    const float ky = kk * (2.0 * dot(a, a) + dot(d, b)) / 3.0;
+    const float kz = kk * dot(d, a);
+    const float p = ky - kx * kx;
+    const float p3 = p * p * p;
+    const float q = kx * (2.0 * kx * kx - 3.0 * ky) + kz;
+    float h = q * q + 4.0 * p3;
+
+    float result;
+    if (h >= 0.0) {
+        h = sqrt(h);
+        const vec2 x = (vec2(h, -h) - q) / 2.0;
+        const vec2 uv = sign(x) * pow(abs(x), vec2(1.0 / 3.0));
+        const float t = clamp(uv.x + uv.y - kx, 0.0, 1.0);
+        result = dot2(d + (c + b * t) * t);
+    } else {
+        const float z = sqrt(-p);
+        const float v = acos(q / (p * z * 2.0)) / 3.0;
+        const float m = cos(v);
+        const float n = sin(v) * ROOT_3;
+        const vec3 t = clamp(vec3(m + m, -n - m, n - m) * z - kx, 0.0, 1.0);
+        result = min(dot2(d + (c + b * t.x) * t.x), dot2(d + (c + b * t.y) * t.y));
+    }
+
+    return sqrt(result) - thickness;
+}
+
+#ifdef SDF_POLYGON_VERTICES
+// Closed polygon made of `count` vertices starting at `first` in the array named by `SDF_POLYGON_VERTICES`.
+float polygon_signed_distance_function(in vec2 point, in uint first, in uint count) {
+    float d = dot2(point - SDF_POLYGON_VERTICES[first]);
+    float s = 1.0;
+    for (uint i = 0u, j = count - 1u; i < count; j = i, i++) {
+        const vec2 vi = SDF_POLYGON_VERTICES[first + i];
+        const vec2 vj = SDF_POLYGON_VERTICES[first + j];
+        const vec2 e = vj - vi;
+        const vec2 w = point - vi;
+        const vec2 b = w - e * clamp(dot(w, e) / dot(e, e), 0.0, 1.0);
+        d = min(d, dot(b, b));
+
+        const bvec3 c = bvec3(point.y >= vi.y, point.y < vj.y, e.x * w.y > e.y * w.x);
+        if (all(c) || all(not(c))) {
+            s *= -1.0;
+        }
+    }
+    return s * sqrt(d);
+}
+#endif
+
+#endif
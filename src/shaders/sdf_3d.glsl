@@ -0,0 +1,102 @@
+#ifndef SDF_3D_GLSL
+#define SDF_3D_GLSL
+
+// 3D signed distance functions, adapted from https://iquilezles.org/articles/distfunctions/.
+// Like the 2D ones they expect the point in the local space of the shape, centred on the origin.
+// Shapes with an axis of symmetry, such as the torus, cylinder and cone, are built around y.
+
+#include "sdf_2d.glsl"
+
+float sphere_signed_distance_function(in vec3 point, in float radius) {
+    return length(point) - radius;
+}
+
+float box_3d_signed_distance_function(in vec3 point, in vec3 half_extents) {
+    const vec3 q = abs(point) - half_extents;
+    return length(max(q, 0.0)) + min(max(q.x, max(q.y, q.z)), 0.0);
+}
+
+float round_box_3d_signed_distance_function(in vec3 point, in vec3 half_extents, in float radius) {
+    return box_3d_signed_distance_function(point, half_extents - radius) - radius;
+}
+
+// A ring of `major_radius` around the y axis with a tube of `minor_radius`.
+float torus_signed_distance_function(in vec3 point, in float major_radius, in float minor_radius) {
+    const vec2 q = vec2(length(point.xz) - major_radius, point.y);
+    return length(q) - minor_radius;
+}
+
+float capsule_signed_distance_function(in vec3 point, in vec3 start, in vec3 end, in float radius) {
+    const vec3 pa = point - start;
+    const vec3 ba = end - start;
+    const float h = clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0);
+    return length(pa - ba * h) - radius;
+}
+
+float cylinder_signed_distance_function(in vec3 point, in float radius, in float half_height) {
+    const vec2 d = abs(vec2(length(point.xz), point.y)) - vec2(radius, half_height);
+    return min(max(d.x, d.y), 0.0) + length(max(d, 0.0));
+}
+
+// Capped cone between `bottom_radius` at -half_height and `top_radius` at +half_height.
+float cone_signed_distance_function(in vec3 point, in float half_height, in float bottom_radius, in float top_radius) {
+    const vec2 q = vec2(length(point.xz), point.y);
+    const vec2 k1 = vec2(top_radius, half_height);
+    const vec2 k2 = vec2(top_radius - bottom_radius, 2.0 * half_height);
+    const vec2 ca = vec2(q.x - min(q.x, (q.y < 0.0) ? bottom_radius : top_radius), abs(q.y) - half_height);
+    const vec2 cb = q - k1 + k2 * clamp(dot(k1 - q, k2) / dot2(k2), 0.0, 1.0);
+    const float s = (cb.x < 0.0 && ca.y < 0.0) ? -1.0 : 1.0;
+    return s * sqrt(min(dot2(ca), dot2(cb)));
+}
+
+// Half space below the plane through `normal * offset`, `normal` has to be a unit vector.
+float plane_signed_distance_function(in vec3 point, in vec3 normal, in float offset) {
+    return dot(point, normal) - offset;
+}
+
+float octahedron_signed_distance_function(in vec3 point, in float radius) {
+    point = abs(point);
+    const float m = point.x + point.y + point.z - radius;
+
+    vec3 q;
+    if (3.0 * point.x < m) {
+        q = point.xyz;
+    } else if (3.0 * point.y < m) {
+        q = point.yzx;
+    } else if (3.0 * point.z < m) {
+        q = point.zxy;
+    } else {
+        return m * 0.57735027;
+    }
+
+    const float k = clamp(0.5 * (q.z - q.y + radius), 0.0, radius);
+    return length(vec3(q.x, q.y - radius + k, q.z - k));
+}
+
+// Turns the distance of a 2D shape in the xy plane into a slab reaching `half_depth` along z.
+float extrusion_operator(in float signed_distance, in float z, in float half_depth) {
+    const vec2 w = vec2(signed_distance, abs(z) - half_depth);
+    return min(max(w.x, w.y), 0.0) + length(max(w, 0.0));
+}
+
+// Sweeps the xy plane around the y axis, the 2D shape is evaluated at the returned point.
+// `radius` moves the profile away from the axis before it is swept.
+vec3 revolution_domain(in vec3 point, in float radius) {
+    return vec3(length(point.xz) - radius, point.y, 0.0);
+}
+
+vec3 translate_3d_domain(in vec3 point, in vec3 offset) {
+    return point - offset;
+}
+
+// Rotates by the inverse of the unit quaternion `rotation`, xyz is the vector part.
+vec3 rotate_3d_domain(in vec3 point, in vec4 rotation) {
+    const vec3 u = -rotation.xyz;
+    return point + 2.0 * cross(u, cross(u, point) + rotation.w * point);
+}
+
+vec3 scale_3d_domain(in vec3 point, in float factor) {
+    return point / factor;
+}
+
+#endif
@@ -0,0 +1,88 @@
+#ifndef SDF_DOMAIN_GLSL
+#define SDF_DOMAIN_GLSL
+
+// Space transforms applied to a point before a distance is evaluated. Each function maps a
+// point from the parent space into the local space of the wrapped shape.
+
+#define TAU 6.28318530718f
+
+vec2 rotate(in vec2 point, in float angle) {
+    const float c = cos(angle);
+    const float s = sin(angle);
+    return mat2(c, s, -s, c) * point;
+}
+
+vec2 translate_domain(in vec2 point, in vec2 offset) {
+    return point - offset;
+}
+
+vec2 rotate_domain(in vec2 point, in float angle) {
+    return rotate(point, -angle);
+}
+
+// The distance evaluated in the scaled space has to be multiplied by `factor` afterwards.
+vec2 scale_domain(in vec2 point, in float factor) {
+    return point / factor;
+}
+
+// `floor(x + 0.5)` instead of `round`, which may round halves either way.
+vec2 repeat_domain(in vec2 point, in vec2 period) {
+    return point - period * floor(point / period + 0.5);
+}
+
+// Repeats `limit` cells to each side of the origin along both axes.
+vec2 repeat_limited_domain(in vec2 point, in vec2 period, in vec2 limit) {
+    return point - period * clamp(floor(point / period + 0.5), -limit, limit);
+}
+
+vec2 polar_repeat_domain(in vec2 point, in uint count) {
+    const float sector = TAU / float(count);
+    const float angle = mod(atan(point.y, point.x) + sector * 0.5, sector) - sector * 0.5;
+    return length(point) * vec2(cos(angle), sin(angle));
+}
+
+// Reflects the half plane behind the unit `normal` onto the half plane in front of it.
+vec2 mirror_domain(in vec2 point, in vec2 normal) {
+    return point - 2.0 * min(dot(point, normal), 0.0) * normal;
+}
+
+vec2 twist_domain(in vec2 point, in float strength) {
+    return rotate(point, strength * length(point));
+}
+
+// Integer hash from https://nullprogram.com/blog/2018/07/31/, used instead of `sin` based hashes
+// so every device and the CPU agree on the noise.
+uint hash(in uint x) {
+    x ^= x >> 16;
+    x *= 0x7feb352du;
+    x ^= x >> 15;
+    x *= 0x846ca68bu;
+    x ^= x >> 16;
+    return x;
+}
+
+vec2 noise_gradient(in ivec2 cell) {
+    const uint h = hash(uint(cell.x) ^ hash(uint(cell.y)));
+    return vec2(float(h & 0xffffu), float(h >> 16)) / 65535.0 * 2.0 - 1.0;
+}
+
+float gradient_noise(in vec2 point) {
+    const vec2 cell = floor(point);
+    const vec2 f = point - cell;
+    const vec2 u = f * f * (3.0 - 2.0 * f);
+    const ivec2 i = ivec2(cell);
+
+    const float a = dot(noise_gradient(i), f);
+    const float b = dot(noise_gradient(i + ivec2(1, 0)), f - vec2(1.0, 0.0));
+    const float c = dot(noise_gradient(i + ivec2(0, 1)), f - vec2(0.0, 1.0));
+    const float d = dot(noise_gradient(i + ivec2(1, 1)), f - vec2(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+// Offsets the point by two decorrelated noise lookups, `phase` scrolls the noise over time.
+vec2 warp_domain(in vec2 point, in float amplitude, in float frequency, in float phase) {
+    const vec2 q = point * frequency + phase;
+    return point + amplitude * vec2(gradient_noise(q), gradient_noise(q + vec2(5.2, 1.3)));
+}
+
+#endif
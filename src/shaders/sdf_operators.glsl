@@ -0,0 +1,61 @@
+#ifndef SDF_OPERATORS_GLSL
+#define SDF_OPERATORS_GLSL
+
+// Boolean and modifier operators on signed distances, see https://iquilezles.org/articles/smin/.
+
+// Blend curves for the smooth operators, keep in sync with `Blend` in `scene.rs`.
+#define BLEND_POLYNOMIAL 0u
+#define BLEND_EXPONENTIAL 1u
+
+float union_operator(in float a, in float b) {
+    return min(a, b);
+}
+
+float intersection_operator(in float a, in float b) {
+    return max(a, b);
+}
+
+// Removes `b` from `a`.
+float subtraction_operator(in float a, in float b) {
+    return max(a, -b);
+}
+
+float smooth_minimum(in float a, in float b, in float radius, in uint blend) {
+    if (blend == BLEND_EXPONENTIAL) {
+        return -radius * log2(exp2(-a / radius) + exp2(-b / radius));
+    }
+
+    const float h = max(radius - abs(a - b), 0.0) / radius;
+    return min(a, b) - h * h * radius * 0.25;
+}
+
+float smooth_union_operator(in float a, in float b, in float radius, in uint blend) {
+    return smooth_minimum(a, b, radius, blend);
+}
+
+float smooth_intersection_operator(in float a, in float b, in float radius, in uint blend) {
+    return -smooth_minimum(-a, -b, radius, blend);
+}
+
+float smooth_subtraction_operator(in float a, in float b, in float radius, in uint blend) {
+    return -smooth_minimum(-a, b, radius, blend);
+}
+
+float annular_operator(in float signed_distance, in float thickness) {
+    return abs(signed_distance) - thickness;
+}
+
+float round_operator(in float signed_distance, in float radius) {
+    return signed_distance - radius;
+}
+
+// Each layer splits every surface in two, so `layers` gives 2^layers concentric outlines.
+float onion_operator(in float signed_distance, in float thickness, in uint layers) {
+    for (uint i = 0u; i < layers; i++) {
+        signed_distance = annular_operator(signed_distance, thickness);
+        thickness *= 0.5;
+    }
+    return signed_distance;
+}
+
+#endif
@@ -0,0 +1,51 @@
+#ifndef SHADERTOY_GLSL
+#define SHADERTOY_GLSL
+
+// Runs a Shadertoy `mainImage` in the compute pipeline. The importing file provides
+// `mainImage`, this include supplies the inputs and the compute entry point.
+//
+// Imported multipass shaders define `SHADERTOY_CHANNELS` to get `iChannel0` to `iChannel3`,
+// and buffer passes also define `SHADERTOY_BUFFER_PASS` to write a float image with alpha.
+#include "shadertoy_inputs.glsl"
+
+#include "workgroup_size.glsl"
+
+#ifdef SHADERTOY_BUFFER_PASS
+layout(set = 0, binding = 0, rgba16f) uniform writeonly image2D img;
+#else
+layout(set = 0, binding = 0, rgba8) uniform writeonly image2D img;
+#endif
+
+#ifdef SHADERTOY_CHANNELS
+layout(set = 0, binding = 4) uniform sampler2D iChannel0;
+layout(set = 0, binding = 5) uniform sampler2D iChannel1;
+layout(set = 0, binding = 6) uniform sampler2D iChannel2;
+layout(set = 0, binding = 7) uniform sampler2D iChannel3;
+
+// std140 gives both arrays a 16 byte stride, keep in sync with `ShadertoyChannelInputs`.
+layout(set = 0, binding = 8) uniform ShadertoyChannels {
+    vec3 iChannelResolution[4];
+    float iChannelTime[4];
+};
+#endif
+
+void mainImage(out vec4 fragColor, in vec2 fragCoord);
+
+void main() {
+    const ivec2 pos = ivec2(gl_GlobalInvocationID.xy);
+    if (any(greaterThanEqual(pos, imageSize(img)))) {
+        return;
+    }
+
+    // Image row 0 ends up at the bottom of the window, which matches the
+    // bottom left origin of `fragCoord` on Shadertoy. Pixel centers sit on .5.
+    vec4 colour = vec4(0.0f);
+    mainImage(colour, vec2(pos) + 0.5f);
+#ifdef SHADERTOY_BUFFER_PASS
+    imageStore(img, pos, colour);
+#else
+    imageStore(img, pos, vec4(colour.rgb, 1.0f));
+#endif
+}
+
+#endif
@@ -0,0 +1,25 @@
+#ifndef SHADERTOY_INPUTS_GLSL
+#define SHADERTOY_INPUTS_GLSL
+
+// Shadertoy compatible inputs, shared by the scene shader and pasted `mainImage` shaders.
+// The block has no instance name so the members are globals, as on Shadertoy.
+layout(set = 0, binding = 3) uniform ShadertoyInputs {
+    // Viewport resolution in pixels, z is the pixel aspect ratio.
+    vec3 iResolution;
+    // Playback time in seconds, the time the timeline is evaluated at. The window's playback
+    // controls pause, scrub, loop and speed it up, headless rendering uses frame / fps. Path
+    // tracing holds it at the time the image started.
+    float iTime;
+    // xy: position while the left button is held, zw: click position.
+    // z is negative once released, w is only positive on the frame of the click.
+    vec4 iMouse;
+    // Not on Shadertoy. xyz: left, right and middle button, 1 while down. w: lines scrolled since start.
+    vec4 iMouseButtons;
+    // Year, month (from 0), day and seconds since midnight in UTC.
+    vec4 iDate;
+    float iTimeDelta;
+    float iFrameRate;
+    int iFrame;
+};
+
+#endif
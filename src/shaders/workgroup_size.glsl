@@ -0,0 +1,10 @@
+#ifndef WORKGROUP_SIZE_GLSL
+#define WORKGROUP_SIZE_GLSL
+
+// Workgroup size of the compute shaders, set per device through specialization constants 0 and 1
+// and 8x8 by default. Dispatches round the image size up to whole workgroups, so every shader has
+// to skip invocations outside the image.
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+layout(local_size_x_id = 0, local_size_y_id = 1) in;
+
+#endif
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Number of `iChannel` inputs every pass has.
+pub const SHADERTOY_CHANNEL_COUNT: usize = 4;
+
+/// Shadertoy exports come either wrapped in a `Shader` object, bare, or as a list of shaders.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ShadertoyExport {
+    Wrapped {
+        #[serde(rename = "Shader")]
+        shader: ExportedShader,
+    },
+    Bare(ExportedShader),
+    List(Vec<ExportedShader>),
+}
+
+#[derive(Deserialize)]
+struct ExportedShader {
+    #[serde(default)]
+    info: ExportedInfo,
+    renderpass: Vec<ExportedPass>,
+}
+
+#[derive(Default, Deserialize)]
+struct ExportedInfo {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct ExportedPass {
+    #[serde(default)]
+    inputs: Vec<ExportedInput>,
+    #[serde(default)]
+    outputs: Vec<ExportedOutput>,
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Deserialize)]
+struct ExportedInput {
+    id: Value,
+    #[serde(alias = "type")]
+    ctype: String,
+    channel: usize,
+    #[serde(default)]
+    sampler: ExportedSampler,
+}
+
+#[derive(Deserialize)]
+struct ExportedOutput {
+    id: Value,
+}
+
+#[derive(Deserialize)]
+struct ExportedSampler {
+    #[serde(default = "default_filter")]
+    filter: String,
+    #[serde(default = "default_wrap")]
+    wrap: String,
+}
+
+impl Default for ExportedSampler {
+    fn default() -> Self {
+        ExportedSampler {
+            filter: default_filter(),
+            wrap: default_wrap(),
+        }
+    }
+}
+
+fn default_filter() -> String {
+    "linear".to_string()
+}
+
+fn default_wrap() -> String {
+    "clamp".to_string()
+}
+
+/// Ids are numbers in older exports and strings in newer ones.
+fn id_string(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelFilter {
+    Nearest,
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelWrap {
+    Clamp,
+    Repeat,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShadertoyChannel {
+    /// Nothing bound or an unsupported input, reads as black.
+    Empty,
+    /// Output of a buffer pass, by index into `ShadertoyProject::buffers`.
+    Buffer { buffer: usize, filter: ChannelFilter, wrap: ChannelWrap },
+}
+
+#[derive(Clone, Debug)]
+pub struct ShadertoyPass {
+    pub name: String,
+    pub code: String,
+    pub channels: [ShadertoyChannel; SHADERTOY_CHANNEL_COUNT],
+}
+
+/// A Shadertoy shader ready to compile: the Image pass and Buffer A to D in execution order.
+#[derive(Clone, Debug)]
+pub struct ShadertoyProject {
+    pub name: String,
+    /// Code of the Common tab, shared by every pass.
+    pub common: String,
+    pub buffers: Vec<ShadertoyPass>,
+    pub image: ShadertoyPass,
+    /// Inputs and passes that were skipped, the shader still runs without them.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum ShadertoyImportError {
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, message: String },
+    MissingImagePass { path: PathBuf },
+}
+
+impl Display for ShadertoyImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShadertoyImportError::Io { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            ShadertoyImportError::Parse { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+            ShadertoyImportError::MissingImagePass { path } => {
+                write!(f, "{}: the export has no Image pass", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShadertoyImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShadertoyImportError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a Shadertoy JSON export from disk. Buffer inputs are linked to the buffer
+/// passes that write them, everything else becomes an empty channel and a warning.
+pub fn import_shadertoy(path: &Path) -> Result<ShadertoyProject, ShadertoyImportError> {
+    let contents = fs::read_to_string(path).map_err(|source| ShadertoyImportError::Io { path: path.to_path_buf(), source })?;
+    let export: ShadertoyExport = serde_json::from_str(&contents)
+        .map_err(|e| ShadertoyImportError::Parse { path: path.to_path_buf(), message: e.to_string() })?;
+
+    let mut warnings = Vec::new();
+    let shader = match export {
+        ShadertoyExport::Wrapped { shader } | ShadertoyExport::Bare(shader) => shader,
+        ShadertoyExport::List(shaders) => {
+            if shaders.len() > 1 {
+                warnings.push(format!("the export holds {} shaders, only the first one is used", shaders.len()));
+            }
+            shaders.into_iter().next().ok_or(ShadertoyImportError::MissingImagePass { path: path.to_path_buf() })?
+        }
+    };
+
+    let mut common = String::new();
+    let mut image = None;
+    let mut buffers = Vec::new();
+    for pass in shader.renderpass {
+        match pass.kind.as_str() {
+            "image" => image = Some(pass),
+            "buffer" => buffers.push(pass),
+            "common" => {
+                common.push_str(&pass.code);
+                common.push('\n');
+            }
+            kind => warnings.push(format!("`{}` is a {} pass which is not supported, it is skipped", pass.name, kind)),
+        }
+    }
+
+    let image = image.ok_or(ShadertoyImportError::MissingImagePass { path: path.to_path_buf() })?;
+
+    // Shadertoy always runs the buffers in A to D order, whatever the order in the export.
+    buffers.sort_by_key(|pass| buffer_letter_index(&pass.name).unwrap_or(usize::MAX));
+
+    let buffer_indices: HashMap<String, usize> = buffers.iter()
+        .enumerate()
+        .flat_map(|(index, pass)| pass.outputs.iter().map(move |output| (id_string(&output.id), index)))
+        .collect();
+
+    let buffers = buffers.into_iter()
+        .map(|pass| link_pass(pass, &buffer_indices, &mut warnings))
+        .collect();
+    let image = link_pass(image, &buffer_indices, &mut warnings);
+
+    Ok(ShadertoyProject {
+        name: shader.info.name,
+        common,
+        buffers,
+        image,
+        warnings,
+    })
+}
+
+/// `Buffer A` to `Buffer D` map to 0 to 3.
+fn buffer_letter_index(name: &str) -> Option<usize> {
+    match name.trim().strip_prefix("Buffer ")? {
+        "A" => Some(0),
+        "B" => Some(1),
+        "C" => Some(2),
+        "D" => Some(3),
+        _ => None,
+    }
+}
+
+fn link_pass(
+    pass: ExportedPass,
+    buffer_indices: &HashMap<String, usize>,
+    warnings: &mut Vec<String>,
+) -> ShadertoyPass {
+    let mut channels = [
+        ShadertoyChannel::Empty,
+        ShadertoyChannel::Empty,
+        ShadertoyChannel::Empty,
+        ShadertoyChannel::Empty,
+    ];
+
+    for input in &pass.inputs {
+        if input.channel >= SHADERTOY_CHANNEL_COUNT {
+            warnings.push(format!("`{}` has an input on channel {}, only 0 to 3 exist", pass.name, input.channel));
+            continue;
+        }
+
+        if input.ctype != "buffer" {
+            warnings.push(format!(
+                "`{}` iChannel{}: {} inputs are not supported, the channel reads black",
+                pass.name, input.channel, input.ctype,
+            ));
+            continue;
+        }
+
+        let Some(&buffer) = buffer_indices.get(&id_string(&input.id)) else {
+            warnings.push(format!(
+                "`{}` iChannel{}: no buffer pass writes output {}, the channel reads black",
+                pass.name, input.channel, id_string(&input.id),
+            ));
+            continue;
+        };
+
+        let filter = match input.sampler.filter.as_str() {
+            "nearest" => ChannelFilter::Nearest,
+            // Buffers have no mip levels, so mipmap filtering samples the base level.
+            _ => ChannelFilter::Linear,
+        };
+        let wrap = match input.sampler.wrap.as_str() {
+            "repeat" => ChannelWrap::Repeat,
+            _ => ChannelWrap::Clamp,
+        };
+
+        channels[input.channel] = ShadertoyChannel::Buffer { buffer, filter, wrap };
+    }
+
+    ShadertoyPass {
+        name: pass.name,
+        code: pass.code,
+        channels,
+    }
+}
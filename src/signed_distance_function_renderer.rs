@@ -1,67 +1,643 @@
 use crate::application::Application;
 use std::sync::Arc;
-use std::time::Instant;
-use rand::Rng;
-use vulkano::buffer::{BufferContents};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use clap::ValueEnum;
+use vulkano::buffer::allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo};
+use vulkano::buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer};
 use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
 use vulkano::command_buffer::{
-    AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer,
+    AutoCommandBufferBuilder, ClearColorImageInfo, CommandBufferUsage, CopyImageToBufferInfo,
+    PrimaryAutoCommandBuffer,
 };
 use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
 use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
 use vulkano::device::Queue;
 use vulkano::format::Format;
 use vulkano::image::{ImageAccess, ImageUsage, StorageImage};
-use vulkano::memory::allocator::{ MemoryAllocator};
-use vulkano::pipeline::{ComputePipeline, Pipeline, PipelineBindPoint};
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator};
+use vulkano::pipeline::{ComputePipeline, Pipeline, PipelineBindPoint, PipelineLayout};
+use vulkano::sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode};
+use vulkano::shader::ShaderModule;
 
 use vulkano::sync::GpuFuture;
 use vulkano_util::renderer::DeviceImageView;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::Window;
+use crate::camera::{Camera2D, Camera3D};
+use crate::palette::{GpuPalettes, PaletteSelection};
+use crate::parameter_panel::{PanelTargets, ParameterPanel};
 use crate::render_pass::RenderPassPlaceOverFrame;
+use crate::scene::{GpuMaterial, GpuSceneNode, Scene};
+use crate::shader_parameters::{ParameterStore, ShaderParameter, ShaderParameters};
+use crate::shader_reload::{built_in_scene_shaders, CompiledComputeShader, CompiledModule};
+use crate::shadertoy_import::{ChannelFilter, ChannelWrap, ShadertoyChannel, ShadertoyProject, SHADERTOY_CHANNEL_COUNT};
+use crate::timeline::Playback;
+
+/// Float format of imported Shadertoy buffers. Unlike 32 bit floats it can always be
+/// filtered linearly and written from a compute shader.
+const SHADERTOY_BUFFER_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+/// Workgroup size the shaders declare in `workgroup_size.glsl`.
+pub const DEFAULT_WORKGROUP_SIZE: [u32; 2] = [8, 8];
+
+/// Samples per pixel the path tracer accumulates before it stops.
+pub const DEFAULT_SAMPLE_TARGET: u32 = 256;
+
+/// Sums of path traced samples, too many to keep in 16 bit floats.
+const ACCUMULATION_FORMAT: Format = Format::R32G32B32A32_SFLOAT;
+
+/// Which compute shader draws the scene.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum RenderMode {
+    /// The distance field of the 2D scene, `shapes_cs.glsl`.
+    #[default]
+    #[value(name = "2d")]
+    Flat,
+    /// The scene sphere traced in 3D with lighting, 2D shapes become slabs, `raymarch_cs.glsl`.
+    #[value(name = "3d")]
+    Raymarch,
+    /// The 3D view path traced with materials, one more sample per frame, `path_trace_cs.glsl`.
+    #[value(name = "path")]
+    PathTrace,
+}
+
+impl RenderMode {
+    /// The mode after this one, going round all of them.
+    pub fn next(self) -> RenderMode {
+        match self {
+            RenderMode::Flat => RenderMode::Raymarch,
+            RenderMode::Raymarch => RenderMode::PathTrace,
+            RenderMode::PathTrace => RenderMode::Flat,
+        }
+    }
+}
+
+/// std140 layout of `ShadertoyChannels` in `shadertoy.glsl`, both arrays have a 16 byte stride.
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct ShadertoyChannelInputs {
+    channel_resolution: [[f32; 4]; SHADERTOY_CHANNEL_COUNT],
+    channel_time: [[f32; 4]; SHADERTOY_CHANNEL_COUNT],
+}
+
+struct ShadertoyPassPipeline {
+    shader: Arc<ShaderModule>,
+    parameters: ShaderParameters,
+    pipeline: Arc<ComputePipeline>,
+    channels: Vec<(ShadertoyChannel, Arc<Sampler>)>,
+}
+
+/// Pipelines and images of an imported Shadertoy shader. Every buffer pass owns two
+/// images, one written this frame and one holding the previous frame for feedback.
+struct ShadertoyPasses {
+    buffers: Vec<ShadertoyPassPipeline>,
+    buffer_images: Vec<[DeviceImageView; 2]>,
+    image: ShadertoyPassPipeline,
+    empty_channel: DeviceImageView,
+    current: usize,
+    needs_clear: bool,
+}
 
 pub struct SimpleVulkanRendererComputePipeline {
     compute_queue: Arc<Queue>,
+    scene_shader: Arc<ShaderModule>,
     initialize_compute_pipeline: Arc<ComputePipeline>,
+    raymarch_shader: Arc<ShaderModule>,
+    raymarch_compute_pipeline: Arc<ComputePipeline>,
+    path_trace_shader: Arc<ShaderModule>,
+    path_trace_compute_pipeline: Arc<ComputePipeline>,
+    /// Reflected blocks of the three scene shaders, in the same order.
+    scene_parameters: [ShaderParameters; 3],
+    parameter_store: ParameterStore,
+    render_mode: RenderMode,
+    /// A single shader from `--shader` or the editor replaced the flat scene shader. It runs
+    /// in place of every scene shader, so the render mode stays `Flat`.
+    single_shader: bool,
+    workgroup_size: [u32; 2],
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
     descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
-    time: Instant,
-    image: DeviceImageView
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    uniform_buffer_allocator: SubbufferAllocator,
+    time: f64,
+    fixed_frame_rate: Option<f64>,
+    previous_time: f32,
+    frame: i32,
+    mouse: [f32; 4],
+    mouse_buttons: [f32; 4],
+    render_scale: f32,
+    camera: Camera2D,
+    camera_3d: Camera3D,
+    palettes: GpuPalettes,
+    image: DeviceImageView,
+    accumulation_image: DeviceImageView,
+    sample_count: u32,
+    sample_target: u32,
+    samples_per_frame: u32,
+    accumulation_time: f32,
+    scene_buffer: Subbuffer<[GpuSceneNode]>,
+    polygon_vertex_buffer: Subbuffer<[[f32; 2]]>,
+    material_buffer: Subbuffer<[GpuMaterial]>,
+    node_count: u32,
+    shadertoy_passes: Option<ShadertoyPasses>,
 }
 
 impl SimpleVulkanRendererComputePipeline {
     pub fn new(
         app: &Application,
         compute_queue: Arc<Queue>,
-        size: [u32; 2]
+        size: [u32; 2],
+        scene: &Scene,
     ) -> SimpleVulkanRendererComputePipeline {
 
         let memory_allocator = app.context.memory_allocator();
-        let initialize_compute_pipeline: Arc<ComputePipeline> = {
-            let shader = triangle_sdf_compute::load(compute_queue.device().clone()).unwrap();
-            ComputePipeline::new(
-                compute_queue.device().clone(),
-                shader.entry_point("main").unwrap(),
-                &(),
-                None,
-                |_| {},
-            ).unwrap()
-        };
+        let scene_shader = triangle_sdf_compute::load(compute_queue.device().clone()).unwrap();
+        let initialize_compute_pipeline = ComputePipeline::new(
+            compute_queue.device().clone(),
+            scene_shader.entry_point("main").unwrap(),
+            &workgroup_specialization_constants(DEFAULT_WORKGROUP_SIZE),
+            None,
+            |_| {},
+        ).unwrap();
 
-        let image = StorageImage::general_purpose_image_view(
-            memory_allocator,
-            compute_queue.clone(),
-            size,
-            Format::R8G8B8A8_UNORM,
-            ImageUsage::SAMPLED | ImageUsage::STORAGE | ImageUsage::TRANSFER_DST,
+        let raymarch_shader = raymarch_sdf_compute::load(compute_queue.device().clone()).unwrap();
+        let raymarch_compute_pipeline = ComputePipeline::new(
+            compute_queue.device().clone(),
+            raymarch_shader.entry_point("main").unwrap(),
+            &workgroup_specialization_constants(DEFAULT_WORKGROUP_SIZE),
+            None,
+            |_| {},
+        ).unwrap();
+
+        let path_trace_shader = path_trace_sdf_compute::load(compute_queue.device().clone()).unwrap();
+        let path_trace_compute_pipeline = ComputePipeline::new(
+            compute_queue.device().clone(),
+            path_trace_shader.entry_point("main").unwrap(),
+            &workgroup_specialization_constants(DEFAULT_WORKGROUP_SIZE),
+            None,
+            |_| {},
         ).unwrap();
 
+        let CompiledComputeShader::Scene { flat, raymarch, path_trace } = built_in_scene_shaders() else {
+            unreachable!("the built in shaders are scene shaders");
+        };
+        let scene_parameters = [flat, raymarch, path_trace].map(|module| module.parameters.clone());
+
+        let image = create_color_image(memory_allocator, compute_queue.clone(), size);
+        let accumulation_image = create_accumulation_image(memory_allocator, compute_queue.clone(), size);
+
+        let encoded_scene = scene.encode();
+        let node_count = encoded_scene.nodes.len() as u32;
+        let scene_buffer = create_storage_buffer(memory_allocator, encoded_scene.nodes);
+        let polygon_vertex_buffer = create_storage_buffer(memory_allocator, encoded_scene.vertices);
+        let material_buffer = create_storage_buffer(memory_allocator, encoded_scene.materials);
+
         SimpleVulkanRendererComputePipeline {
             compute_queue,
+            scene_shader,
             initialize_compute_pipeline,
-            time: Instant::now(),
+            raymarch_shader,
+            raymarch_compute_pipeline,
+            path_trace_shader,
+            path_trace_compute_pipeline,
+            scene_parameters,
+            parameter_store: ParameterStore::default(),
+            render_mode: RenderMode::default(),
+            single_shader: false,
+            workgroup_size: DEFAULT_WORKGROUP_SIZE,
+            uniform_buffer_allocator: SubbufferAllocator::new(
+                memory_allocator.clone(),
+                SubbufferAllocatorCreateInfo {
+                    buffer_usage: BufferUsage::UNIFORM_BUFFER,
+                    ..Default::default()
+                },
+            ),
+            time: 0.0,
+            fixed_frame_rate: None,
+            previous_time: 0.0,
+            frame: 0,
+            mouse: [0.0; 4],
+            mouse_buttons: [0.0; 4],
+            render_scale: 1.0,
+            camera: Camera2D::default(),
+            camera_3d: Camera3D::default(),
+            palettes: GpuPalettes::default(),
             command_buffer_allocator: app.command_buffer_allocator.clone(),
             descriptor_set_allocator: app.descriptor_set_allocator.clone(),
+            memory_allocator: memory_allocator.clone(),
             image,
+            accumulation_image,
+            sample_count: 0,
+            sample_target: DEFAULT_SAMPLE_TARGET,
+            samples_per_frame: 1,
+            accumulation_time: 0.0,
+            scene_buffer,
+            polygon_vertex_buffer,
+            material_buffer,
+            node_count,
+            shadertoy_passes: None,
+        }
+    }
+
+    /// Swaps in freshly compiled shaders. On error the last good pipelines keep running.
+    pub fn apply_compiled_shader(&mut self, compiled: &CompiledComputeShader) -> Result<(), String> {
+        match compiled {
+            CompiledComputeShader::Scene { flat, raymarch, path_trace } => {
+                self.reload_scene_shaders(flat, raymarch, path_trace)?;
+                self.single_shader = false;
+            }
+            CompiledComputeShader::Single(module) => {
+                self.reload_shader(module)?;
+                self.single_shader = true;
+                self.render_mode = RenderMode::Flat;
+            }
+            CompiledComputeShader::Shadertoy { project, buffers, image } => {
+                self.load_shadertoy_passes(project, buffers, image)?;
+                self.single_shader = false;
+                self.render_mode = RenderMode::Flat;
+            }
+        }
+
+        self.reset_accumulation();
+        Ok(())
+    }
+
+    /// Swaps in a compute pipeline built from freshly compiled SPIR-V. The layout comes from
+    /// the shader, descriptor sets and push constants follow the reflected blocks, so the shader
+    /// may add or change them.
+    fn reload_shader(&mut self, module: &CompiledModule) -> Result<(), String> {
+        let shader = self.create_shader_module(&module.words)?;
+        self.initialize_compute_pipeline = self.create_compute_pipeline(&shader, None)?;
+        self.scene_shader = shader;
+        self.scene_parameters[0] = module.parameters.clone();
+        self.shadertoy_passes = None;
+
+        Ok(())
+    }
+
+    /// Swaps in all scene shaders, or none if one of them fails.
+    fn reload_scene_shaders(&mut self, flat: &CompiledModule, raymarch: &CompiledModule, path_trace: &CompiledModule) -> Result<(), String> {
+        let raymarch_shader = self.create_shader_module(&raymarch.words)?;
+        let raymarch_compute_pipeline = self.create_compute_pipeline(&raymarch_shader, None)?;
+        let path_trace_shader = self.create_shader_module(&path_trace.words)?;
+        let path_trace_compute_pipeline = self.create_compute_pipeline(&path_trace_shader, None)?;
+        self.reload_shader(flat)?;
+        self.raymarch_shader = raymarch_shader;
+        self.raymarch_compute_pipeline = raymarch_compute_pipeline;
+        self.path_trace_shader = path_trace_shader;
+        self.path_trace_compute_pipeline = path_trace_compute_pipeline;
+        self.scene_parameters[1] = raymarch.parameters.clone();
+        self.scene_parameters[2] = path_trace.parameters.clone();
+
+        Ok(())
+    }
+
+    /// Builds the pipelines of an imported Shadertoy shader and gives every buffer pass
+    /// a pair of images the size of the output image, cleared on the first frame.
+    fn load_shadertoy_passes(
+        &mut self,
+        project: &ShadertoyProject,
+        buffers: &[CompiledModule],
+        image: &CompiledModule,
+    ) -> Result<(), String> {
+        let mut buffer_pipelines = Vec::new();
+        for (pass, module) in project.buffers.iter().zip(buffers) {
+            buffer_pipelines.push(
+                self.create_shadertoy_pass_pipeline(module, &pass.channels)
+                    .map_err(|e| format!("{}: {e}", pass.name))?,
+            );
+        }
+
+        let image_pipeline = self.create_shadertoy_pass_pipeline(image, &project.image.channels)
+            .map_err(|e| format!("{}: {e}", project.image.name))?;
+
+        self.shadertoy_passes = Some(ShadertoyPasses {
+            buffer_images: self.create_shadertoy_buffer_images(buffer_pipelines.len()),
+            buffers: buffer_pipelines,
+            image: image_pipeline,
+            empty_channel: self.create_shadertoy_buffer_image([1, 1]),
+            current: 0,
+            needs_clear: true,
+        });
+
+        Ok(())
+    }
+
+    fn create_shadertoy_pass_pipeline(
+        &self,
+        module: &CompiledModule,
+        channels: &[ShadertoyChannel; SHADERTOY_CHANNEL_COUNT],
+    ) -> Result<ShadertoyPassPipeline, String> {
+        let shader = self.create_shader_module(&module.words)?;
+        Ok(ShadertoyPassPipeline {
+            pipeline: self.create_compute_pipeline(&shader, None)?,
+            shader,
+            parameters: module.parameters.clone(),
+            channels: self.create_channel_samplers(channels),
+        })
+    }
+
+    /// A pair of images the size of the output image for every buffer pass.
+    fn create_shadertoy_buffer_images(&self, buffer_count: usize) -> Vec<[DeviceImageView; 2]> {
+        let size = self.image.image().dimensions().width_height();
+        (0..buffer_count)
+            .map(|_| [self.create_shadertoy_buffer_image(size), self.create_shadertoy_buffer_image(size)])
+            .collect()
+    }
+
+    fn create_shadertoy_buffer_image(&self, size: [u32; 2]) -> DeviceImageView {
+        StorageImage::general_purpose_image_view(
+            self.memory_allocator.as_ref(),
+            self.compute_queue.clone(),
+            size,
+            SHADERTOY_BUFFER_FORMAT,
+            ImageUsage::SAMPLED | ImageUsage::STORAGE | ImageUsage::TRANSFER_DST,
+        ).unwrap()
+    }
+
+    fn create_shader_module(&self, words: &[u32]) -> Result<Arc<ShaderModule>, String> {
+        // Safety: the words come straight out of shaderc which only emits valid SPIR-V.
+        unsafe { ShaderModule::from_words(self.compute_queue.device().clone(), words) }
+            .map_err(|e| format!("Failed to create shader module: {e}"))
+    }
+
+    /// Creates a compute pipeline specialized to the current workgroup size, either against
+    /// an existing layout or with a layout made from the shader itself.
+    fn create_compute_pipeline(
+        &self,
+        shader: &Arc<ShaderModule>,
+        layout: Option<Arc<PipelineLayout>>,
+    ) -> Result<Arc<ComputePipeline>, String> {
+        let device = self.compute_queue.device().clone();
+        let entry_point = shader.entry_point("main")
+            .ok_or("Shader has no `main` entry point")?;
+        let specialization_constants = workgroup_specialization_constants(self.workgroup_size);
+
+        match layout {
+            Some(layout) => ComputePipeline::with_pipeline_layout(device, entry_point, &specialization_constants, layout, None),
+            None => ComputePipeline::new(device, entry_point, &specialization_constants, None, |_| {}),
+        }.map_err(|e| format!("Failed to create compute pipeline: {e}"))
+    }
+
+    /// Rebuilds every pipeline with a new workgroup size. Fails without changing anything
+    /// if the device does not support the size.
+    pub fn set_workgroup_size(&mut self, size: [u32; 2]) -> Result<(), String> {
+        let properties = self.compute_queue.device().physical_device().properties();
+        let max_size = properties.max_compute_work_group_size;
+        let max_invocations = properties.max_compute_work_group_invocations;
+        if size[0] == 0 || size[1] == 0 || size[0] > max_size[0] || size[1] > max_size[1] {
+            return Err(format!(
+                "workgroup size {}x{} is outside the device limit of {}x{}",
+                size[0], size[1], max_size[0], max_size[1],
+            ));
+        }
+        if size[0] as u64 * size[1] as u64 > max_invocations as u64 {
+            return Err(format!(
+                "workgroup size {}x{} has more than the {} invocations the device supports",
+                size[0], size[1], max_invocations,
+            ));
+        }
+
+        let previous_size = self.workgroup_size;
+        self.workgroup_size = size;
+        let result = self.respecialize_pipelines();
+        if result.is_err() {
+            self.workgroup_size = previous_size;
+        }
+        result
+    }
+
+    fn respecialize_pipelines(&mut self) -> Result<(), String> {
+        let scene_pipeline = self.create_compute_pipeline(
+            &self.scene_shader,
+            Some(self.initialize_compute_pipeline.layout().clone()),
+        )?;
+        let raymarch_pipeline = self.create_compute_pipeline(
+            &self.raymarch_shader,
+            Some(self.raymarch_compute_pipeline.layout().clone()),
+        )?;
+        let path_trace_pipeline = self.create_compute_pipeline(
+            &self.path_trace_shader,
+            Some(self.path_trace_compute_pipeline.layout().clone()),
+        )?;
+
+        let shadertoy_pipelines = match &self.shadertoy_passes {
+            Some(passes) => passes.buffers.iter()
+                .chain([&passes.image])
+                .map(|pass| self.create_compute_pipeline(&pass.shader, None))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+
+        self.initialize_compute_pipeline = scene_pipeline;
+        self.raymarch_compute_pipeline = raymarch_pipeline;
+        self.path_trace_compute_pipeline = path_trace_pipeline;
+        if let Some(passes) = self.shadertoy_passes.as_mut() {
+            let passes = passes.buffers.iter_mut().chain([&mut passes.image]);
+            for (pass, pipeline) in passes.zip(shadertoy_pipelines) {
+                pass.pipeline = pipeline;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_channel_samplers(
+        &self,
+        channels: &[ShadertoyChannel; SHADERTOY_CHANNEL_COUNT],
+    ) -> Vec<(ShadertoyChannel, Arc<Sampler>)> {
+        channels.iter()
+            .map(|channel| {
+                let (filter, wrap) = match channel {
+                    ShadertoyChannel::Buffer { filter, wrap, .. } => (*filter, *wrap),
+                    ShadertoyChannel::Empty => (ChannelFilter::Nearest, ChannelWrap::Clamp),
+                };
+                let filter = match filter {
+                    ChannelFilter::Nearest => Filter::Nearest,
+                    ChannelFilter::Linear => Filter::Linear,
+                };
+                let address_mode = match wrap {
+                    ChannelWrap::Clamp => SamplerAddressMode::ClampToEdge,
+                    ChannelWrap::Repeat => SamplerAddressMode::Repeat,
+                };
+                let sampler = Sampler::new(
+                    self.compute_queue.device().clone(),
+                    SamplerCreateInfo {
+                        mag_filter: filter,
+                        min_filter: filter,
+                        address_mode: [address_mode; 3],
+                        mipmap_mode: SamplerMipmapMode::Nearest,
+                        ..Default::default()
+                    },
+                ).unwrap();
+
+                (channel.clone(), sampler)
+            })
+            .collect()
+    }
+
+    /// Takes time from the frame index instead of the wall clock, so every run renders
+    /// the same frames. `iDate` then counts from the Unix epoch as well.
+    pub fn set_fixed_frame_rate(&mut self, frame_rate: f64) {
+        self.fixed_frame_rate = Some(frame_rate);
+    }
+
+    /// Scene time of the next frames without a fixed frame rate, in seconds. Windows set it
+    /// from their playback, so it may stand still or jump back.
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Uploads an edited scene in place of the current one. Shadertoy shaders ignore it.
+    pub fn set_scene(&mut self, scene: &Scene) {
+        let encoded_scene = scene.encode();
+        self.node_count = encoded_scene.nodes.len() as u32;
+        self.scene_buffer = create_storage_buffer(&self.memory_allocator, encoded_scene.nodes);
+        self.polygon_vertex_buffer = create_storage_buffer(&self.memory_allocator, encoded_scene.vertices);
+        self.material_buffer = create_storage_buffer(&self.memory_allocator, encoded_scene.materials);
+        self.reset_accumulation();
+    }
+
+    /// Sets the Shadertoy style `iMouse` for the next frames, in window pixels, and
+    /// `iMouseButtons`. The position is scaled to image pixels with the render scale.
+    pub fn set_mouse(&mut self, mouse: [f32; 4], buttons: [f32; 4]) {
+        self.mouse = mouse.map(|value| value * self.render_scale);
+        self.mouse_buttons = buttons;
+    }
+
+    /// View used by the scene shader for the next frames. Shadertoy shaders ignore it.
+    pub fn set_camera(&mut self, camera: Camera2D) {
+        self.camera = camera;
+    }
+
+    /// View used by the raymarched and path traced scene shaders for the next frames.
+    /// Moving the camera starts the path traced image over.
+    pub fn set_camera_3d(&mut self, camera: Camera3D) {
+        if camera != self.camera_3d {
+            self.reset_accumulation();
+        }
+        self.camera_3d = camera;
+    }
+
+    /// Palette of the scene shapes without a material, for the next frames. A change starts
+    /// the path traced image over.
+    pub fn set_palettes(&mut self, palettes: GpuPalettes) {
+        if palettes != self.palettes {
+            self.reset_accumulation();
+        }
+        self.palettes = palettes;
+    }
+
+    /// Switches between the scene shaders. They all write the same color image, so nothing
+    /// else changes. Single and Shadertoy shaders ignore it and stay in `Flat`.
+    pub fn set_render_mode(&mut self, mode: RenderMode) {
+        if !self.runs_scene_shaders() {
+            return;
+        }
+        if mode != self.render_mode {
+            self.reset_accumulation();
+        }
+        self.render_mode = mode;
+    }
+
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    /// Whether the built in or edited scene shaders run, rather than a single or Shadertoy shader.
+    pub fn runs_scene_shaders(&self) -> bool {
+        !self.single_shader && self.shadertoy_passes.is_none()
+    }
+
+    /// Values of the shader parameters, replacing all earlier ones.
+    pub fn set_parameter_store(&mut self, store: ParameterStore) {
+        if store != self.parameter_store {
+            self.reset_accumulation();
+        }
+        self.parameter_store = store;
+    }
+
+    pub fn parameter_store(&self) -> &ParameterStore {
+        &self.parameter_store
+    }
+
+    /// Sets one shader parameter for the next frames. A change starts the path traced image over.
+    pub fn set_parameter(&mut self, name: &str, value: &[f32]) {
+        if self.parameter_store.set(name, value) {
+            self.reset_accumulation();
+        }
+    }
+
+    /// Annotated parameters of the shaders that run in the current mode, each name once.
+    pub fn annotated_parameters(&self) -> Vec<ShaderParameter> {
+        let shaders: Vec<&ShaderParameters> = match &self.shadertoy_passes {
+            Some(passes) => passes.buffers.iter().chain([&passes.image]).map(|pass| &pass.parameters).collect(),
+            None => vec![self.active_scene_parameters()],
+        };
+
+        let mut parameters: Vec<ShaderParameter> = Vec::new();
+        for parameter in shaders.into_iter().flat_map(ShaderParameters::annotated) {
+            if !parameters.iter().any(|known| known.name == parameter.name) {
+                parameters.push(parameter.clone());
+            }
+        }
+        parameters
+    }
+
+    fn active_scene_parameters(&self) -> &ShaderParameters {
+        match self.render_mode {
+            RenderMode::Flat => &self.scene_parameters[0],
+            RenderMode::Raymarch => &self.scene_parameters[1],
+            RenderMode::PathTrace => &self.scene_parameters[2],
+        }
+    }
+
+    /// Samples per pixel after which the path tracer stops and keeps showing its image.
+    pub fn set_sample_target(&mut self, samples: u32) {
+        self.sample_target = samples;
+    }
+
+    /// How many samples the path tracer adds per frame. The window takes one per frame to
+    /// stay responsive, headless rendering takes all of them at once.
+    pub fn set_samples_per_frame(&mut self, samples: u32) {
+        self.samples_per_frame = samples.max(1);
+    }
+
+    /// Throws away the path traced samples, the next frame starts a new image.
+    pub fn reset_accumulation(&mut self) {
+        self.sample_count = 0;
+    }
+
+    /// Samples taken and the target, while the path tracer runs the scene.
+    pub fn path_trace_progress(&self) -> Option<[u32; 2]> {
+        match self.render_mode {
+            RenderMode::PathTrace if self.shadertoy_passes.is_none() => Some([self.sample_count, self.sample_target]),
+            _ => None,
+        }
+    }
+
+    /// Renders at `scale` times the window resolution, the image is stretched over the window.
+    /// Takes effect on the next `resize`.
+    pub fn set_render_scale(&mut self, scale: f32) {
+        self.render_scale = scale;
+    }
+
+    /// Reallocates the color image when the scaled window size no longer matches it.
+    /// Descriptor sets are built every dispatch, so the next frame binds the new image.
+    /// Shadertoy buffers are resized too and start out black again, as on Shadertoy.
+    pub fn resize(&mut self, window_size: [u32; 2]) {
+        let size = window_size.map(|length| ((length as f32 * self.render_scale).round() as u32).max(1));
+        if size == self.image.image().dimensions().width_height() {
+            return;
+        }
+
+        self.image = create_color_image(&self.memory_allocator, self.compute_queue.clone(), size);
+        self.accumulation_image = create_accumulation_image(&self.memory_allocator, self.compute_queue.clone(), size);
+        self.reset_accumulation();
+        if let Some(buffer_count) = self.shadertoy_passes.as_ref().map(|passes| passes.buffers.len()) {
+            let buffer_images = self.create_shadertoy_buffer_images(buffer_count);
+            let passes = self.shadertoy_passes.as_mut().unwrap();
+            passes.buffer_images = buffer_images;
+            passes.needs_clear = true;
         }
     }
 
@@ -84,62 +660,476 @@ impl SimpleVulkanRendererComputePipeline {
 
         let command_buffer = builder.build().unwrap();
         let finished = before_future.then_execute(self.compute_queue.clone(), command_buffer).unwrap();
-        let after_pipeline = finished.then_signal_fence_and_flush().unwrap().boxed();
-        after_pipeline
+        finished.then_signal_fence_and_flush().unwrap().boxed()
+    }
+
+    /// Copies the color image back to the host once `before_future` is done and waits for it.
+    /// Pixels are RGBA8 in image row order, row 0 is the bottom of the window.
+    pub fn download_color_image(
+        &self,
+        before_future: Box<dyn GpuFuture>,
+    ) -> Vec<u8> {
+        let [width, height] = self.image.image().dimensions().width_height();
+        let download_buffer = Buffer::new_slice::<u8>(
+            self.memory_allocator.as_ref(),
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Download,
+                ..Default::default()
+            },
+            width as u64 * height as u64 * 4,
+        ).unwrap();
+
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &self.command_buffer_allocator,
+            self.compute_queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit
+        ).unwrap();
+        builder.copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
+            self.image.image().clone(),
+            download_buffer.clone(),
+        )).unwrap();
+
+        let command_buffer = builder.build().unwrap();
+        before_future.then_execute(self.compute_queue.clone(), command_buffer).unwrap()
+            .then_signal_fence_and_flush().unwrap()
+            .wait(None).unwrap();
+
+        let pixels = download_buffer.read().unwrap();
+        pixels.to_vec()
     }
 
     fn dispatch(
         &mut self,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer, Arc<StandardCommandBufferAllocator>>,
     ) {
-        // Resize image if needed.
         let img_dims = self.image.image().dimensions().width_height();
-        let pipeline_layout = self.initialize_compute_pipeline.layout();
-        let desc_layout = pipeline_layout.set_layouts().get(0).unwrap();
+
+        // Shadertoy inputs are written into a fresh uniform buffer every frame.
+        let (current_time, date) = match self.fixed_frame_rate {
+            Some(frame_rate) => {
+                let time = self.frame as f64 / frame_rate;
+                (time as f32, UNIX_EPOCH + Duration::from_secs_f64(time))
+            }
+            None => (self.time as f32, SystemTime::now()),
+        };
+        let time_delta = current_time - self.previous_time;
+
+        // Animated scenes hold still while path traced samples add up.
+        let path_tracing = self.render_mode == RenderMode::PathTrace && self.runs_scene_shaders();
+        if path_tracing && self.sample_count == 0 {
+            self.accumulation_time = current_time;
+        }
+
+        let shadertoy_inputs = self.uniform_buffer_allocator.allocate_sized().unwrap();
+        *shadertoy_inputs.write().unwrap() = triangle_sdf_compute::ShadertoyInputs {
+            iResolution: [img_dims[0] as f32, img_dims[1] as f32, 1.0],
+            iTime: if path_tracing { self.accumulation_time } else { current_time },
+            iMouse: self.mouse,
+            iMouseButtons: self.mouse_buttons,
+            iDate: shadertoy_date(date),
+            iTimeDelta: time_delta,
+            iFrameRate: if time_delta > 0.0 { 1.0 / time_delta } else { 0.0 },
+            iFrame: self.frame,
+        };
+        self.previous_time = current_time;
+        self.frame += 1;
+
+        if self.shadertoy_passes.is_some() {
+            self.dispatch_shadertoy_passes(builder, shadertoy_inputs, current_time);
+            return;
+        }
+
+        match self.render_mode {
+            RenderMode::Flat => {
+                self.dispatch_scene(builder, self.initialize_compute_pipeline.clone(), shadertoy_inputs);
+            }
+            RenderMode::Raymarch => {
+                self.dispatch_scene(builder, self.raymarch_compute_pipeline.clone(), shadertoy_inputs);
+            }
+            RenderMode::PathTrace => {
+                // Once the target is reached the color image keeps the finished image.
+                let samples = self.samples_per_frame.min(self.sample_target.saturating_sub(self.sample_count));
+                for _ in 0..samples {
+                    self.dispatch_scene(builder, self.path_trace_compute_pipeline.clone(), shadertoy_inputs.clone());
+                    self.sample_count += 1;
+                }
+            }
+        }
+    }
+
+    /// Push constant values the renderer owns, by the names the scene shaders give them.
+    /// Integers are exact as floats well past any scene size or sample count.
+    fn builtin_parameters(&self) -> [(&'static str, [f32; 4]); 9] {
+        let camera = &self.camera;
+        let camera_3d = &self.camera_3d;
+        let [x, y, z] = camera_3d.position;
+        [
+            ("camera_offset", [camera.offset[0], camera.offset[1], 0.0, 0.0]),
+            ("camera_zoom", [camera.zoom, 0.0, 0.0, 0.0]),
+            ("camera_rotation", [camera.rotation, 0.0, 0.0, 0.0]),
+            ("camera_position", [x, y, z, 0.0]),
+            ("camera_yaw", [camera_3d.yaw, 0.0, 0.0, 0.0]),
+            ("camera_pitch", [camera_3d.pitch, 0.0, 0.0, 0.0]),
+            ("camera_focal_scale", [camera_3d.focal_scale(), 0.0, 0.0, 0.0]),
+            ("node_count", [self.node_count as f32, 0.0, 0.0, 0.0]),
+            ("sample_index", [self.sample_count as f32, 0.0, 0.0, 0.0]),
+        ]
+    }
+
+    /// Uniform buffers for the blocks of `parameters` that none of `writes` binds, such as
+    /// `Parameters` in `parameters.glsl`, filled from the parameter store.
+    fn parameter_uniform_writes(&self, parameters: &ShaderParameters, writes: &[WriteDescriptorSet]) -> Vec<WriteDescriptorSet> {
+        parameters.uniforms()
+            .filter(|(binding, _)| writes.iter().all(|write| write.binding() != *binding))
+            .map(|(binding, block)| {
+                let bytes = block.encode(&self.parameter_store, &[]);
+                let buffer = self.uniform_buffer_allocator.allocate_slice::<u8>(bytes.len() as u64).unwrap();
+                buffer.write().unwrap().copy_from_slice(&bytes);
+                WriteDescriptorSet::buffer(binding, buffer)
+            })
+            .collect()
+    }
+
+    /// Pushes the push constant block of `parameters` word by word, as far as the layout has
+    /// push constant ranges.
+    fn push_parameter_constants(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer, Arc<StandardCommandBufferAllocator>>,
+        pipeline_layout: &Arc<PipelineLayout>,
+        parameters: &ShaderParameters,
+    ) {
+        let Some(block) = parameters.push_constants() else {
+            return;
+        };
+
+        let mut bytes = block.encode(&self.parameter_store, &self.builtin_parameters());
+        for range in pipeline_layout.push_constant_ranges() {
+            bytes.resize(bytes.len().max((range.offset + range.size) as usize), 0);
+            for offset in (range.offset..range.offset + range.size).step_by(4) {
+                let start = offset as usize;
+                let word = u32::from_le_bytes([bytes[start], bytes[start + 1], bytes[start + 2], bytes[start + 3]]);
+                builder.push_constants(pipeline_layout.clone(), offset, word);
+            }
+        }
+    }
+
+    /// Runs one of the scene shaders, which share the scene buffers and the inputs.
+    /// Only the path tracer binds the accumulation image.
+    fn dispatch_scene(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer, Arc<StandardCommandBufferAllocator>>,
+        pipeline: Arc<ComputePipeline>,
+        shadertoy_inputs: Subbuffer<triangle_sdf_compute::ShadertoyInputs>,
+    ) {
+        let img_dims = self.image.image().dimensions().width_height();
+        let palettes = self.uniform_buffer_allocator.allocate_sized().unwrap();
+        *palettes.write().unwrap() = self.palettes;
+
+        let pipeline_layout = pipeline.layout();
+        let desc_layout = pipeline_layout.set_layouts().first().unwrap();
+        let mut writes = vec![
+            WriteDescriptorSet::image_view(0, self.image.clone()),
+            WriteDescriptorSet::buffer(1, self.scene_buffer.clone()),
+            WriteDescriptorSet::buffer(2, self.polygon_vertex_buffer.clone()),
+            WriteDescriptorSet::buffer(3, shadertoy_inputs),
+            WriteDescriptorSet::image_view(4, self.accumulation_image.clone()),
+            WriteDescriptorSet::buffer(5, self.material_buffer.clone()),
+            WriteDescriptorSet::buffer(6, palettes),
+        ];
+        let parameters = self.active_scene_parameters();
+        writes.extend(self.parameter_uniform_writes(parameters, &writes));
+        writes.retain(|write| desc_layout.bindings().contains_key(&write.binding()));
+
         let persistent_descriptor_set = match PersistentDescriptorSet::new(
             &self.descriptor_set_allocator,
             desc_layout.clone(),
-            [
-                WriteDescriptorSet::image_view(0, self.image.clone())
-            ],
+            writes,
         ) {
             Ok(x) => x,
             Err(e) => panic!("Failed to bind descriptor sets: {}", e),
         };
 
-        let current_time = self.time.elapsed().as_secs_f32();
+        // Rounded up, the shader skips the invocations outside the image.
+        let dispatch_count_x = img_dims[0].div_ceil(self.workgroup_size[0]);
+        let dispatch_count_y = img_dims[1].div_ceil(self.workgroup_size[1]);
+
+        builder.bind_pipeline_compute(pipeline.clone())
+            .bind_descriptor_sets(PipelineBindPoint::Compute, pipeline_layout.clone(), 0, persistent_descriptor_set);
+        self.push_parameter_constants(builder, pipeline_layout, parameters);
+        builder.dispatch([dispatch_count_x, dispatch_count_y, 1]).unwrap();
+    }
+
+    /// Runs the buffer passes in order and then the Image pass into the output image.
+    /// A pass reading a buffer that already ran this frame sees the new contents,
+    /// reading itself or a later buffer gives the previous frame, like on Shadertoy.
+    fn dispatch_shadertoy_passes(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer, Arc<StandardCommandBufferAllocator>>,
+        shadertoy_inputs: Subbuffer<triangle_sdf_compute::ShadertoyInputs>,
+        current_time: f32,
+    ) {
+        let passes = self.shadertoy_passes.as_mut().unwrap();
+        passes.current = 1 - passes.current;
+
+        // Buffers start out black, as on Shadertoy.
+        if passes.needs_clear {
+            let images = passes.buffer_images.iter().flatten().chain([&passes.empty_channel]);
+            for image in images {
+                builder.clear_color_image(ClearColorImageInfo::image(image.image().clone())).unwrap();
+            }
+            passes.needs_clear = false;
+        }
+
+        let passes = self.shadertoy_passes.as_ref().unwrap();
+        let channel_image = |channel: &ShadertoyChannel, pass_index: usize| match channel {
+            ShadertoyChannel::Buffer { buffer, .. } if *buffer < pass_index => passes.buffer_images[*buffer][passes.current].clone(),
+            ShadertoyChannel::Buffer { buffer, .. } => passes.buffer_images[*buffer][1 - passes.current].clone(),
+            ShadertoyChannel::Empty => passes.empty_channel.clone(),
+        };
+
+        for (pass_index, pass) in passes.buffers.iter().enumerate() {
+            let channel_images = pass.channels.iter().map(|(channel, _)| channel_image(channel, pass_index)).collect();
+            let output = passes.buffer_images[pass_index][passes.current].clone();
+            self.dispatch_shadertoy_pass(builder, pass, output, channel_images, shadertoy_inputs.clone(), current_time);
+        }
+
+        let channel_images = passes.image.channels.iter().map(|(channel, _)| channel_image(channel, passes.buffers.len())).collect();
+        self.dispatch_shadertoy_pass(builder, &passes.image, self.image.clone(), channel_images, shadertoy_inputs, current_time);
+    }
+
+    fn dispatch_shadertoy_pass(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer, Arc<StandardCommandBufferAllocator>>,
+        pass: &ShadertoyPassPipeline,
+        output: DeviceImageView,
+        channel_images: Vec<DeviceImageView>,
+        shadertoy_inputs: Subbuffer<triangle_sdf_compute::ShadertoyInputs>,
+        current_time: f32,
+    ) {
+        let img_dims = output.image().dimensions().width_height();
 
-        let push_constants = triangle_sdf_compute::PushConstants {
-            time: current_time,
+        // Unused channels have no resolution, as on Shadertoy.
+        let mut channel_inputs = ShadertoyChannelInputs {
+            channel_resolution: [[0.0; 4]; SHADERTOY_CHANNEL_COUNT],
+            channel_time: [[current_time, 0.0, 0.0, 0.0]; SHADERTOY_CHANNEL_COUNT],
         };
+        for (index, (channel, _)) in pass.channels.iter().enumerate() {
+            if let ShadertoyChannel::Buffer { .. } = channel {
+                let [width, height] = channel_images[index].image().dimensions().width_height();
+                channel_inputs.channel_resolution[index] = [width as f32, height as f32, 1.0, 0.0];
+            }
+        }
+        let channel_buffer = self.uniform_buffer_allocator.allocate_sized().unwrap();
+        *channel_buffer.write().unwrap() = channel_inputs;
 
-        let dispatch_count_x = img_dims[0] / 8;
-        let dispatch_count_y = img_dims[1] / 8;
+        let mut writes = vec![
+            WriteDescriptorSet::image_view(0, output),
+            WriteDescriptorSet::buffer(3, shadertoy_inputs),
+            WriteDescriptorSet::buffer(8, channel_buffer),
+        ];
+        for (index, ((_, sampler), image)) in pass.channels.iter().zip(channel_images).enumerate() {
+            writes.push(WriteDescriptorSet::image_view_sampler(4 + index as u32, image, sampler.clone()));
+        }
+        writes.extend(self.parameter_uniform_writes(&pass.parameters, &writes));
+
+        // Each pass only declares what its code uses, unused bindings are left out.
+        let pipeline_layout = pass.pipeline.layout();
+        let desc_layout = pipeline_layout.set_layouts().first().unwrap();
+        writes.retain(|write| desc_layout.bindings().contains_key(&write.binding()));
+
+        let persistent_descriptor_set = match PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            desc_layout.clone(),
+            writes,
+        ) {
+            Ok(x) => x,
+            Err(e) => panic!("Failed to bind descriptor sets: {}", e),
+        };
 
-        builder.bind_pipeline_compute(self.initialize_compute_pipeline.clone())
-            .bind_descriptor_sets(PipelineBindPoint::Compute, pipeline_layout.clone(), 0, persistent_descriptor_set)
-            .push_constants(pipeline_layout.clone(), 0, push_constants)
-            .dispatch([dispatch_count_x, dispatch_count_y, 1])
+        builder.bind_pipeline_compute(pass.pipeline.clone())
+            .bind_descriptor_sets(PipelineBindPoint::Compute, pipeline_layout.clone(), 0, persistent_descriptor_set);
+        self.push_parameter_constants(builder, pipeline_layout, &pass.parameters);
+        builder.dispatch([img_dims[0].div_ceil(self.workgroup_size[0]), img_dims[1].div_ceil(self.workgroup_size[1]), 1])
             .unwrap();
     }
 }
 
+/// Shadertoy `iDate`: year, month from 0, day and seconds since midnight, in UTC.
+fn shadertoy_date(now: SystemTime) -> [f32; 4] {
+    let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let days = (since_epoch.as_secs() / 86_400) as i64;
+    let seconds = (since_epoch.as_secs() % 86_400) as f32 + since_epoch.subsec_nanos() as f32 * 1e-9;
+
+    // Civil date from days since 1970-01-01, see https://howardhinnant.github.io/date_algorithms.html
+    let shifted_days = days + 719_468;
+    let era = shifted_days.div_euclid(146_097);
+    let day_of_era = shifted_days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    [year as f32, (month - 1) as f32, day as f32, seconds]
+}
+
+/// Every compute shader includes `workgroup_size.glsl`, so the constants generated for the
+/// scene shader fit the Shadertoy passes too. Constant 0 is the width, 1 the height.
+fn workgroup_specialization_constants(size: [u32; 2]) -> triangle_sdf_compute::SpecializationConstants {
+    triangle_sdf_compute::SpecializationConstants {
+        constant_0: size[0],
+        constant_1: size[1],
+    }
+}
+
+/// RGBA8 image the compute shader writes and the render pass draws over the frame.
+fn create_color_image(
+    memory_allocator: &StandardMemoryAllocator,
+    compute_queue: Arc<Queue>,
+    size: [u32; 2],
+) -> DeviceImageView {
+    StorageImage::general_purpose_image_view(
+        memory_allocator,
+        compute_queue,
+        size,
+        Format::R8G8B8A8_UNORM,
+        ImageUsage::SAMPLED | ImageUsage::STORAGE | ImageUsage::TRANSFER_DST | ImageUsage::TRANSFER_SRC,
+    ).unwrap()
+}
+
+/// Float image the path tracer sums its samples in, never read outside the shader.
+fn create_accumulation_image(
+    memory_allocator: &StandardMemoryAllocator,
+    compute_queue: Arc<Queue>,
+    size: [u32; 2],
+) -> DeviceImageView {
+    StorageImage::general_purpose_image_view(
+        memory_allocator,
+        compute_queue,
+        size,
+        ACCUMULATION_FORMAT,
+        ImageUsage::STORAGE,
+    ).unwrap()
+}
+
+/// Uploads encoded scene data into a storage buffer. Empty data still gets one
+/// element since zero sized buffers are not allowed.
+fn create_storage_buffer<T: BufferContents + Default>(
+    memory_allocator: &Arc<StandardMemoryAllocator>,
+    mut elements: Vec<T>
+) -> Subbuffer<[T]> {
+    if elements.is_empty() {
+        elements.push(T::default());
+    }
+
+    Buffer::from_iter(
+        memory_allocator,
+        BufferCreateInfo {
+            usage: BufferUsage::STORAGE_BUFFER,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            usage: MemoryUsage::Upload,
+            ..Default::default()
+        },
+        elements,
+    ).unwrap()
+}
+
 pub struct SimpleVulkanRendererRenderPipeline {
+    /// The scene as loaded or edited, before its timeline is applied.
+    pub scene: Scene,
+    pub playback: Playback,
+    pub camera: Camera2D,
+    pub camera_3d: Camera3D,
+    pub palette: PaletteSelection,
     pub compute: SimpleVulkanRendererComputePipeline,
     pub place_over_frame: RenderPassPlaceOverFrame,
+    pub panel: ParameterPanel,
+
+    /// The scene last uploaded to `compute`, with the timeline applied.
+    animated_scene: Scene,
+    timeline_error: Option<String>,
 }
 
 impl SimpleVulkanRendererRenderPipeline {
     pub fn new(
         app: &Application,
+        event_loop: &EventLoopWindowTarget<()>,
         compute_queue: Arc<Queue>,
         graphics_queue: Arc<Queue>,
         size: [u32; 2],
-        swap_chain_format: Format
+        swap_chain_format: Format,
+        scene: &Scene,
     ) -> SimpleVulkanRendererRenderPipeline {
         SimpleVulkanRendererRenderPipeline {
-            compute: SimpleVulkanRendererComputePipeline::new(app, compute_queue, size),
-            place_over_frame: RenderPassPlaceOverFrame::new(app, graphics_queue, swap_chain_format),
+            scene: scene.clone(),
+            playback: Playback::default(),
+            camera: Camera2D::default(),
+            camera_3d: Camera3D::default(),
+            palette: PaletteSelection::default(),
+            compute: SimpleVulkanRendererComputePipeline::new(app, compute_queue, size, scene),
+            place_over_frame: RenderPassPlaceOverFrame::new(app, graphics_queue.clone(), swap_chain_format),
+            panel: ParameterPanel::new(app, event_loop, graphics_queue, swap_chain_format, scene),
+            animated_scene: scene.clone(),
+            timeline_error: None,
+        }
+    }
+
+    /// Runs the parameter panel of the window for this frame and uploads scene edits.
+    pub fn update_panel(&mut self, window: &Window) {
+        let targets = PanelTargets {
+            camera: &mut self.camera,
+            camera_3d: &mut self.camera_3d,
+            palette: &mut self.palette,
+            playback: &mut self.playback,
+            timeline: &self.scene.timeline,
+            compute: &mut self.compute,
+        };
+        if let Some(scene) = self.panel.update(window, targets) {
+            self.compute.set_scene(&scene);
+            self.animated_scene = scene.clone();
+            self.scene = scene;
+        }
+    }
+
+    /// Why the timeline could not be applied on the last update, if it could not.
+    pub fn timeline_error(&self) -> Option<&str> {
+        self.timeline_error.as_deref()
+    }
+
+    /// Advances the playback by `delta_time` seconds and applies the timeline at the new time,
+    /// ahead of the next dispatch. The scene is only uploaded again when a value changed, so a
+    /// paused timeline lets the path tracer converge. Where the animated scene is invalid the
+    /// window keeps the last valid one and reports the error, headless rendering stops instead.
+    pub fn update_timeline(&mut self, delta_time: f64) {
+        self.playback.advance(delta_time);
+        let time = self.playback.time();
+        self.compute.set_time(time);
+
+        match self.scene.timeline.animate_scene(&self.scene, time as f32) {
+            Ok(animated) => {
+                if let Some(scene) = animated.filter(|scene| *scene != self.animated_scene) {
+                    self.compute.set_scene(&scene);
+                    self.animated_scene = scene;
+                }
+                self.timeline_error = None;
+            }
+            Err(e) => {
+                // Printed once per error, not on every frame it lasts.
+                if self.timeline_error.as_ref() != Some(&e) {
+                    eprintln!("Timeline keeps the last valid scene: {e}");
+                }
+                self.timeline_error = Some(e);
+            }
+        }
+        for (name, value) in self.scene.timeline.shader_values(time as f32) {
+            self.compute.set_parameter(name, &value);
         }
     }
 }
@@ -150,4 +1140,20 @@ mod triangle_sdf_compute {
         include: ["src/shaders"],
         path: "src/shaders/shapes_cs.glsl",
     }
-}
\ No newline at end of file
+}
+
+mod raymarch_sdf_compute {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        include: ["src/shaders"],
+        path: "src/shaders/raymarch_cs.glsl",
+    }
+}
+
+mod path_trace_sdf_compute {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        include: ["src/shaders"],
+        path: "src/shaders/path_trace_cs.glsl",
+    }
+}
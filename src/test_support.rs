@@ -0,0 +1,25 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Directory for the files of one test under the system temporary directory, removed with
+/// everything in it when dropped. Every test gets its own, so tests running in parallel never
+/// share a file.
+pub struct TestDirectory(TempDir);
+
+impl TestDirectory {
+    pub fn new() -> TestDirectory {
+        TestDirectory(tempfile::Builder::new().prefix("simple-sdf-").tempdir().unwrap())
+    }
+
+    pub fn path(&self) -> &Path {
+        self.0.path()
+    }
+
+    /// Writes `contents` into the file `name` in the directory and returns its path.
+    pub fn file(&self, name: &str, contents: &str) -> PathBuf {
+        let path = self.path().join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
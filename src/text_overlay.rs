@@ -0,0 +1,104 @@
+use std::convert::Infallible;
+use embedded_graphics::mono_font::ascii::FONT_6X10;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+
+const MAX_COLUMNS: usize = 160;
+const MAX_LINES: usize = 60;
+const PADDING: u32 = 8;
+const BACKGROUND: [u8; 4] = [0, 0, 0, 200];
+
+/// Text colour of shader errors.
+pub const ERROR_FOREGROUND: Rgb888 = Rgb888::new(255, 120, 120);
+
+/// Text colour of status lines such as the path tracer progress.
+pub const STATUS_FOREGROUND: Rgb888 = Rgb888::new(220, 220, 220);
+
+/// CPU side RGBA8 image holding rasterized overlay text.
+pub struct TextOverlayImage {
+    pub dimensions: [u32; 2],
+    pub pixels: Vec<u8>,
+}
+
+impl TextOverlayImage {
+    fn new(dimensions: [u32; 2]) -> TextOverlayImage {
+        let pixel_count = (dimensions[0] * dimensions[1]) as usize;
+        TextOverlayImage {
+            dimensions,
+            pixels: BACKGROUND.repeat(pixel_count),
+        }
+    }
+}
+
+impl OriginDimensions for TextOverlayImage {
+    fn size(&self) -> Size {
+        Size::new(self.dimensions[0], self.dimensions[1])
+    }
+}
+
+impl DrawTarget for TextOverlayImage {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if point.x < 0 || point.y < 0 || point.x as u32 >= self.dimensions[0] || point.y as u32 >= self.dimensions[1] {
+                continue;
+            }
+
+            let index = (point.y as usize * self.dimensions[0] as usize + point.x as usize) * 4;
+            self.pixels[index..index + 4].copy_from_slice(&[color.r(), color.g(), color.b(), 255]);
+        }
+
+        Ok(())
+    }
+}
+
+/// Rasterizes `text` in `foreground` with a small monospace font onto a translucent background.
+/// Long lines are wrapped and very long messages are cut off.
+pub fn rasterize_text(text: &str, foreground: Rgb888) -> TextOverlayImage {
+    let mut lines: Vec<String> = text
+        .lines()
+        .flat_map(|line| {
+            let characters: Vec<char> = line.chars().collect();
+            if characters.is_empty() {
+                return vec![String::new()];
+            }
+            characters
+                .chunks(MAX_COLUMNS)
+                .map(|chunk| chunk.iter().collect())
+                .collect()
+        })
+        .collect();
+
+    if lines.len() > MAX_LINES {
+        lines.truncate(MAX_LINES - 1);
+        lines.push("...".to_string());
+    }
+
+    let character_size = FONT_6X10.character_size;
+    let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0).max(1) as u32;
+    let rows = lines.len().max(1) as u32;
+    let mut image = TextOverlayImage::new([
+        columns * character_size.width + PADDING * 2,
+        rows * character_size.height + PADDING * 2,
+    ]);
+
+    let style = MonoTextStyle::new(&FONT_6X10, foreground);
+    for (row, line) in lines.iter().enumerate() {
+        let position = Point::new(
+            PADDING as i32,
+            (PADDING + row as u32 * character_size.height) as i32,
+        );
+        Text::with_baseline(line, position, style, Baseline::Top)
+            .draw(&mut image)
+            .unwrap();
+    }
+
+    image
+}
@@ -0,0 +1,589 @@
+use std::f32::consts::FRAC_PI_2;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::scene::{InvalidNode, Scene};
+
+/// Most components a keyframe value can have, as many as a `vec4` shader parameter.
+const MAX_COMPONENTS: usize = 4;
+
+/// Steps of the search for the point of a bezier timing curve at a given time.
+const BEZIER_ITERATIONS: usize = 32;
+
+/// Keyframed tracks of scene numbers and shader parameters, saved with the scene. Tracks are
+/// evaluated at the scene time, the same time `iTime` shows.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    pub tracks: Vec<Track>,
+}
+
+/// Keyframes of one animated value, ordered by time. Before the first and after the last
+/// keyframe the value holds still.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Track {
+    pub target: TrackTarget,
+    pub keyframes: Vec<Keyframe>,
+}
+
+/// What a track animates.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TrackTarget {
+    /// A number or a list of numbers in the serialized scene, by JSON pointer, such as
+    /// `/nodes/0/Primitive/transform/rotation`. Paths follow the Scene section of the panel.
+    Scene(String),
+    /// An annotated shader parameter by name, such as `tint`.
+    Shader(String),
+}
+
+impl Display for TrackTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrackTarget::Scene(pointer) => write!(f, "scene {pointer}"),
+            TrackTarget::Shader(name) => write!(f, "shader {name}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// In seconds of scene time.
+    pub time: f32,
+    /// One to four components, the same number in every keyframe of a track.
+    pub value: Vec<f32>,
+    /// How the value moves from this keyframe to the next one.
+    #[serde(default)]
+    pub interpolation: Interpolation,
+}
+
+/// Maps the progress between two keyframes, from 0 to 1, to the share of the way the value
+/// has moved. Curves that overshoot, such as `Back`, move past both values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Holds the value until the next keyframe.
+    Step,
+    /// Timing curve from (0, 0) to (1, 1) with the control points `(x1, y1)` and `(x2, y2)`,
+    /// like CSS `cubic-bezier`. Both x lie between 0 and 1 so the curve never goes back in time.
+    Bezier(f32, f32, f32, f32),
+    Ease(Easing, EaseDirection),
+}
+
+/// The standard easing curves, see easings.net.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    Quad,
+    Cubic,
+    Quart,
+    Sine,
+    Expo,
+    Circ,
+    Back,
+}
+
+/// Which end of the move an easing curve slows down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EaseDirection {
+    In,
+    Out,
+    InOut,
+}
+
+impl Easing {
+    /// The ease in form of the curve, the other directions mirror it.
+    fn ease_in(self, progress: f32) -> f32 {
+        match self {
+            Easing::Quad => progress.powi(2),
+            Easing::Cubic => progress.powi(3),
+            Easing::Quart => progress.powi(4),
+            Easing::Sine => 1.0 - (progress * FRAC_PI_2).cos(),
+            Easing::Expo if progress <= 0.0 => 0.0,
+            Easing::Expo => 2.0f32.powf(10.0 * progress - 10.0),
+            Easing::Circ => 1.0 - (1.0 - progress * progress).max(0.0).sqrt(),
+            Easing::Back => {
+                const OVERSHOOT: f32 = 1.70158;
+                (OVERSHOOT + 1.0) * progress.powi(3) - OVERSHOOT * progress.powi(2)
+            }
+        }
+    }
+
+    fn apply(self, direction: EaseDirection, progress: f32) -> f32 {
+        match direction {
+            EaseDirection::In => self.ease_in(progress),
+            EaseDirection::Out => 1.0 - self.ease_in(1.0 - progress),
+            EaseDirection::InOut if progress < 0.5 => self.ease_in(2.0 * progress) / 2.0,
+            EaseDirection::InOut => 1.0 - self.ease_in(2.0 - 2.0 * progress) / 2.0,
+        }
+    }
+}
+
+impl Interpolation {
+    pub fn apply(self, progress: f32) -> f32 {
+        match self {
+            Interpolation::Linear => progress,
+            Interpolation::Step => 0.0,
+            Interpolation::Bezier(x1, y1, x2, y2) => {
+                let cubic = |a: f32, b: f32, t: f32| {
+                    let u = 1.0 - t;
+                    3.0 * u * u * t * a + 3.0 * u * t * t * b + t * t * t
+                };
+
+                // x grows with t when both control x lie in [0, 1], so halving the interval converges.
+                let (mut low, mut high) = (0.0f32, 1.0f32);
+                for _ in 0..BEZIER_ITERATIONS {
+                    let middle = (low + high) / 2.0;
+                    if cubic(x1, x2, middle) < progress {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+                cubic(y1, y2, (low + high) / 2.0)
+            }
+            Interpolation::Ease(easing, direction) => easing.apply(direction, progress),
+        }
+    }
+}
+
+impl Track {
+    /// The value at `time`, between the keyframes around it.
+    pub fn value_at(&self, time: f32) -> Vec<f32> {
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 {
+            return self.keyframes[0].value.clone();
+        }
+        let Some(to) = self.keyframes.get(next) else {
+            return self.keyframes[next - 1].value.clone();
+        };
+
+        let from = &self.keyframes[next - 1];
+        let share = from.interpolation.apply((time - from.time) / (to.time - from.time));
+        from.value.iter().zip(&to.value).map(|(from, to)| from + (to - from) * share).collect()
+    }
+
+    fn validate(&self, location: &str, scene: &Value) -> Result<(), InvalidNode> {
+        let invalid = |location: String, parameter, reason| InvalidNode { location, parameter, reason };
+        let Some(first) = self.keyframes.first() else {
+            return Err(invalid(location.to_string(), "keyframes", "must not be empty"));
+        };
+
+        for (index, keyframe) in self.keyframes.iter().enumerate() {
+            let location = format!("{location}.keyframes[{index}]");
+            if !keyframe.time.is_finite() {
+                return Err(invalid(location, "time", "must be a finite number"));
+            }
+            if index > 0 && keyframe.time <= self.keyframes[index - 1].time {
+                return Err(invalid(location, "time", "must be later than the previous keyframe"));
+            }
+            if keyframe.value.is_empty() || keyframe.value.len() > MAX_COMPONENTS {
+                return Err(invalid(location, "value", "must have one to four components"));
+            }
+            if keyframe.value.len() != first.value.len() {
+                return Err(invalid(location, "value", "must have as many components as the first keyframe"));
+            }
+            if !keyframe.value.iter().all(|component| component.is_finite()) {
+                return Err(invalid(location, "value", "must be finite numbers"));
+            }
+            if let Interpolation::Bezier(x1, y1, x2, y2) = keyframe.interpolation {
+                if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) || !y1.is_finite() || !y2.is_finite() {
+                    return Err(invalid(location, "interpolation", "bezier control points need x between 0 and 1"));
+                }
+            }
+        }
+
+        match &self.target {
+            TrackTarget::Scene(pointer) if pointer.starts_with("/timeline") => {
+                Err(invalid(location.to_string(), "target", "cannot animate the timeline itself"))
+            }
+            TrackTarget::Scene(pointer) => {
+                let mut target = scene.pointer(pointer).cloned()
+                    .ok_or_else(|| invalid(location.to_string(), "target", "does not name a value in the scene"))?;
+                write_numbers(&mut target, &first.value).map_err(|reason| invalid(location.to_string(), "target", reason))
+            }
+            TrackTarget::Shader(name) if name.is_empty() => {
+                Err(invalid(location.to_string(), "target", "must name a shader parameter"))
+            }
+            TrackTarget::Shader(_) => Ok(()),
+        }
+    }
+}
+
+impl Timeline {
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// Time of the last keyframe of any track.
+    pub fn duration(&self) -> f32 {
+        self.tracks.iter()
+            .filter_map(|track| track.keyframes.last())
+            .map(|keyframe| keyframe.time)
+            .fold(0.0, f32::max)
+    }
+
+    /// Checks the keyframes of every track and that scene targets name numbers of `scene`
+    /// with as many components as their keyframes.
+    pub fn validate(&self, scene: &Scene) -> Result<(), InvalidNode> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let scene = serde_json::to_value(scene).unwrap();
+        for (index, track) in self.tracks.iter().enumerate() {
+            track.validate(&format!("timeline.tracks[{index}]"), &scene)?;
+        }
+        Ok(())
+    }
+
+    /// `scene` with the scene tracks applied at `time`, or `None` when no track animates the scene.
+    /// Fails when the animated values make the scene invalid, such as a radius eased below zero.
+    pub fn animate_scene(&self, scene: &Scene, time: f32) -> Result<Option<Scene>, String> {
+        let mut pointers = self.tracks.iter().filter_map(|track| match &track.target {
+            TrackTarget::Scene(pointer) => Some((pointer, track)),
+            TrackTarget::Shader(_) => None,
+        }).peekable();
+        if pointers.peek().is_none() {
+            return Ok(None);
+        }
+
+        let mut value = serde_json::to_value(scene).unwrap();
+        for (pointer, track) in pointers {
+            let target = value.pointer_mut(pointer).ok_or_else(|| format!("`{pointer}` does not name a value in the scene"))?;
+            write_numbers(target, &track.value_at(time)).map_err(|reason| format!("`{pointer}` {reason}"))?;
+        }
+
+        let animated: Scene = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        animated.validate().map_err(|invalid| {
+            format!("`{}` has an invalid `{}`: {}", invalid.location, invalid.parameter, invalid.reason)
+        })?;
+        Ok(Some(animated))
+    }
+
+    /// Names and values of the shader parameters the timeline animates, at `time`.
+    pub fn shader_values(&self, time: f32) -> impl Iterator<Item = (&str, Vec<f32>)> {
+        self.tracks.iter().filter_map(move |track| match &track.target {
+            TrackTarget::Shader(name) => Some((name.as_str(), track.value_at(time))),
+            TrackTarget::Scene(_) => None,
+        })
+    }
+}
+
+/// Overwrites a serialized number, or a list of as many numbers as `values` has. Integers,
+/// such as the sides of a polygon, are rounded so the scene still deserializes.
+fn write_numbers(target: &mut Value, values: &[f32]) -> Result<(), &'static str> {
+    let write = |target: &mut Value, value: f32| {
+        *target = match target {
+            Value::Number(number) if number.is_u64() => Value::from(value.round().max(0.0) as u64),
+            Value::Number(number) if number.is_i64() => Value::from(value.round() as i64),
+            _ => Value::from(value as f64),
+        };
+    };
+
+    match target {
+        Value::Number(_) if values.len() == 1 => {
+            write(target, values[0]);
+            Ok(())
+        }
+        Value::Array(elements) if elements.iter().all(Value::is_number) => {
+            if elements.len() != values.len() {
+                return Err("has another number of components than the keyframes");
+            }
+            for (element, value) in elements.iter_mut().zip(values) {
+                write(element, *value);
+            }
+            Ok(())
+        }
+        Value::Number(_) => Err("has another number of components than the keyframes"),
+        _ => Err("does not name a number or a list of numbers"),
+    }
+}
+
+/// Play, pause, scrub, speed and loop region of the scene time of a window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Playback {
+    time: f64,
+    pub playing: bool,
+    /// 1 is real time.
+    pub speed: f32,
+    /// Start and end in seconds. Playback that passes the end wraps back to the start.
+    pub loop_region: Option<[f32; 2]>,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            time: 0.0,
+            playing: true,
+            speed: 1.0,
+            loop_region: None,
+        }
+    }
+}
+
+impl Playback {
+    /// Moves the time on by `delta_time` seconds of the wall clock while playing.
+    pub fn advance(&mut self, delta_time: f64) {
+        if !self.playing {
+            return;
+        }
+
+        self.time += delta_time * self.speed as f64;
+        if let Some([start, end]) = self.loop_region.map(|region| region.map(f64::from)) {
+            if end > start && self.time >= end {
+                self.time = start + (self.time - start).rem_euclid(end - start);
+            }
+        }
+    }
+
+    /// Jumps to `time`, in seconds.
+    pub fn scrub(&mut self, time: f64) {
+        self.time = time.max(0.0);
+    }
+
+    pub fn toggle(&mut self) {
+        self.playing = !self.playing;
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{SceneNode, Shape, Transform2D};
+
+    const EASINGS: [Easing; 7] = [
+        Easing::Quad,
+        Easing::Cubic,
+        Easing::Quart,
+        Easing::Sine,
+        Easing::Expo,
+        Easing::Circ,
+        Easing::Back,
+    ];
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "{actual} is not {expected}");
+    }
+
+    fn keyframe(time: f32, value: &[f32], interpolation: Interpolation) -> Keyframe {
+        Keyframe { time, value: value.to_vec(), interpolation }
+    }
+
+    fn track(target: TrackTarget, keyframes: Vec<Keyframe>) -> Track {
+        Track { target, keyframes }
+    }
+
+    /// A circle and a pentagon, with `tracks` on top.
+    fn scene(tracks: Vec<Track>) -> Scene {
+        let primitive = |shape| SceneNode::Primitive { shape, transform: Transform2D::default() };
+        Scene {
+            nodes: vec![
+                primitive(Shape::Circle { radius: 0.5 }),
+                primitive(Shape::RegularPolygon { radius: 0.5, sides: 5 }),
+            ],
+            materials: Vec::new(),
+            timeline: Timeline { tracks },
+        }
+    }
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        for easing in EASINGS {
+            for direction in [EaseDirection::In, EaseDirection::Out, EaseDirection::InOut] {
+                let interpolation = Interpolation::Ease(easing, direction);
+                assert_close(interpolation.apply(0.0), 0.0);
+                assert_close(interpolation.apply(1.0), 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn ease_out_and_in_out_mirror_ease_in() {
+        for easing in EASINGS {
+            let apply = |direction, progress| Interpolation::Ease(easing, direction).apply(progress);
+            for progress in [0.1, 0.25, 0.6, 0.9] {
+                assert_close(apply(EaseDirection::Out, progress), 1.0 - apply(EaseDirection::In, 1.0 - progress));
+                assert_close(apply(EaseDirection::InOut, progress), 1.0 - apply(EaseDirection::InOut, 1.0 - progress));
+            }
+            assert!(apply(EaseDirection::In, 0.5) < 0.5, "{easing:?} in is not slow at the start");
+            assert!(apply(EaseDirection::Out, 0.5) > 0.5, "{easing:?} out is not slow at the end");
+            assert_close(apply(EaseDirection::InOut, 0.5), 0.5);
+        }
+    }
+
+    #[test]
+    fn easing_values() {
+        let apply = |easing, direction, progress| Interpolation::Ease(easing, direction).apply(progress);
+        assert_close(apply(Easing::Quad, EaseDirection::In, 0.5), 0.25);
+        assert_close(apply(Easing::Quad, EaseDirection::Out, 0.5), 0.75);
+        assert_close(apply(Easing::Quad, EaseDirection::InOut, 0.25), 0.125);
+        assert_close(apply(Easing::Cubic, EaseDirection::In, 0.5), 0.125);
+        assert_close(apply(Easing::Sine, EaseDirection::In, 0.5), 1.0 - (0.5 * FRAC_PI_2).cos());
+        assert_close(apply(Easing::Expo, EaseDirection::In, 0.5), 2.0f32.powi(-5));
+        assert!(apply(Easing::Back, EaseDirection::In, 0.2) < 0.0, "back does not overshoot");
+    }
+
+    #[test]
+    fn bezier_runs_from_zero_to_one() {
+        let ease = Interpolation::Bezier(0.42, 0.0, 0.58, 1.0);
+        assert_close(ease.apply(0.0), 0.0);
+        assert_close(ease.apply(1.0), 1.0);
+        assert_close(ease.apply(0.5), 0.5);
+        assert!(ease.apply(0.2) < 0.2);
+
+        // Control points on the diagonal give a straight line.
+        let linear = Interpolation::Bezier(1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0);
+        for progress in [0.1, 0.3, 0.7] {
+            assert_close(linear.apply(progress), progress);
+        }
+    }
+
+    #[test]
+    fn step_holds_until_the_next_key() {
+        let track = track(TrackTarget::Shader("value".to_string()), vec![
+            keyframe(0.0, &[1.0], Interpolation::Step),
+            keyframe(1.0, &[4.0], Interpolation::Linear),
+            keyframe(2.0, &[6.0], Interpolation::Linear),
+        ]);
+        assert_eq!(track.value_at(0.0), [1.0]);
+        assert_eq!(track.value_at(0.99), [1.0]);
+        assert_eq!(track.value_at(1.0), [4.0]);
+        assert_eq!(track.value_at(1.5), [5.0]);
+    }
+
+    #[test]
+    fn value_holds_outside_the_keys() {
+        let track = track(TrackTarget::Shader("tint".to_string()), vec![
+            keyframe(1.0, &[0.0, 0.5, 1.0], Interpolation::Linear),
+            keyframe(3.0, &[1.0, 0.5, 0.0], Interpolation::Linear),
+        ]);
+        assert_eq!(track.value_at(-2.0), [0.0, 0.5, 1.0]);
+        assert_eq!(track.value_at(0.5), [0.0, 0.5, 1.0]);
+        assert_eq!(track.value_at(2.0), [0.5, 0.5, 0.5]);
+        assert_eq!(track.value_at(3.0), [1.0, 0.5, 0.0]);
+        assert_eq!(track.value_at(100.0), [1.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn playback_wraps_around_the_loop_region() {
+        let mut playback = Playback { loop_region: Some([1.0, 3.0]), ..Playback::default() };
+        playback.scrub(2.5);
+        playback.advance(1.0);
+        assert!((playback.time() - 1.5).abs() < 1e-9);
+
+        // Passing the end several times in one step still lands inside the region.
+        playback.advance(4.75);
+        assert!((playback.time() - 2.25).abs() < 1e-9);
+
+        playback.speed = 2.0;
+        playback.advance(0.25);
+        assert!((playback.time() - 2.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn playback_stands_still_while_paused() {
+        let mut playback = Playback::default();
+        playback.advance(0.5);
+        playback.toggle();
+        playback.advance(10.0);
+        assert_eq!(playback.time(), 0.5);
+
+        // Time before the loop region plays into it without wrapping.
+        playback.toggle();
+        playback.loop_region = Some([2.0, 4.0]);
+        playback.advance(1.0);
+        assert_eq!(playback.time(), 1.5);
+    }
+
+    #[test]
+    fn integer_fields_are_rounded() {
+        let sides = TrackTarget::Scene("/nodes/1/Primitive/shape/RegularPolygon/sides".to_string());
+        let scene = scene(vec![track(sides, vec![
+            keyframe(0.0, &[3.0], Interpolation::Linear),
+            keyframe(1.0, &[8.0], Interpolation::Linear),
+        ])]);
+        assert_eq!(scene.validate(), Ok(()));
+
+        let animated = scene.timeline.animate_scene(&scene, 0.52).unwrap().unwrap();
+        let SceneNode::Primitive { shape: Shape::RegularPolygon { sides, .. }, .. } = animated.nodes[1] else {
+            panic!("the polygon is gone");
+        };
+        assert_eq!(sides, 6);
+    }
+
+    #[test]
+    fn scene_values_follow_their_track() {
+        let radius = TrackTarget::Scene("/nodes/0/Primitive/shape/Circle/radius".to_string());
+        let translation = TrackTarget::Scene("/nodes/1/Primitive/transform/translation".to_string());
+        let scene = scene(vec![
+            track(radius, vec![keyframe(0.0, &[0.5], Interpolation::Linear), keyframe(2.0, &[1.5], Interpolation::Linear)]),
+            track(translation, vec![keyframe(0.0, &[0.0, 0.0], Interpolation::Linear), keyframe(1.0, &[1.0, -1.0], Interpolation::Linear)]),
+            track(TrackTarget::Shader("exposure".to_string()), vec![keyframe(0.0, &[2.0], Interpolation::Linear)]),
+        ]);
+        assert_eq!(scene.validate(), Ok(()));
+
+        let animated = scene.timeline.animate_scene(&scene, 1.0).unwrap().unwrap();
+        assert_eq!(animated.nodes[0], SceneNode::Primitive {
+            shape: Shape::Circle { radius: 1.0 },
+            transform: Transform2D::default(),
+        });
+        let SceneNode::Primitive { transform, .. } = &animated.nodes[1] else {
+            panic!("the polygon is gone");
+        };
+        assert_eq!(transform.translation, [1.0, -1.0]);
+        assert_eq!(scene.timeline.shader_values(1.0).collect::<Vec<_>>(), [("exposure", vec![2.0])]);
+    }
+
+    #[test]
+    fn scene_without_scene_tracks_is_not_animated() {
+        let scene = scene(vec![track(TrackTarget::Shader("exposure".to_string()), vec![keyframe(0.0, &[2.0], Interpolation::Linear)])]);
+        assert_eq!(scene.timeline.animate_scene(&scene, 0.0), Ok(None));
+    }
+
+    #[test]
+    fn invalid_animated_scene_is_an_error() {
+        let radius = TrackTarget::Scene("/nodes/0/Primitive/shape/Circle/radius".to_string());
+        let scene = scene(vec![track(radius, vec![
+            keyframe(0.0, &[0.5], Interpolation::Linear),
+            keyframe(1.0, &[-0.5], Interpolation::Linear),
+        ])]);
+        assert_eq!(scene.validate(), Ok(()));
+        assert!(scene.timeline.animate_scene(&scene, 0.25).is_ok());
+        assert!(scene.timeline.animate_scene(&scene, 0.75).unwrap_err().contains("radius"));
+    }
+
+    #[test]
+    fn bad_pointers_are_rejected() {
+        let rejection = |pointer: &str, value: &[f32]| {
+            let target = TrackTarget::Scene(pointer.to_string());
+            scene(vec![track(target, vec![keyframe(0.0, value, Interpolation::Linear)])]).validate().unwrap_err()
+        };
+
+        let missing = rejection("/nodes/2/Primitive/shape/Circle/radius", &[1.0]);
+        assert_eq!((missing.location.as_str(), missing.parameter), ("timeline.tracks[0]", "target"));
+        assert_eq!(missing.reason, "does not name a value in the scene");
+        assert_eq!(rejection("nodes/0", &[1.0]).reason, "does not name a value in the scene");
+        assert_eq!(rejection("/nodes/0/Primitive/transform", &[1.0]).reason, "does not name a number or a list of numbers");
+        assert_eq!(rejection("/nodes/0/Primitive/transform/translation", &[1.0]).reason, "has another number of components than the keyframes");
+        assert_eq!(rejection("/nodes/0/Primitive/shape/Circle/radius", &[1.0, 2.0]).reason, "has another number of components than the keyframes");
+        assert_eq!(rejection("/timeline/tracks/0/keyframes/0/time", &[1.0]).reason, "cannot animate the timeline itself");
+    }
+
+    #[test]
+    fn bad_keyframes_are_rejected() {
+        let rejection = |keyframes| {
+            scene(vec![track(TrackTarget::Shader("exposure".to_string()), keyframes)]).validate().unwrap_err()
+        };
+
+        assert_eq!(rejection(Vec::new()).reason, "must not be empty");
+        let unordered = rejection(vec![keyframe(1.0, &[1.0], Interpolation::Linear), keyframe(1.0, &[2.0], Interpolation::Linear)]);
+        assert_eq!((unordered.location.as_str(), unordered.parameter), ("timeline.tracks[0].keyframes[1]", "time"));
+        let bezier = rejection(vec![keyframe(0.0, &[1.0], Interpolation::Bezier(1.5, 0.0, 0.5, 1.0))]);
+        assert_eq!(bezier.parameter, "interpolation");
+        let components = rejection(vec![keyframe(0.0, &[1.0; 5], Interpolation::Linear)]);
+        assert_eq!(components.reason, "must have one to four components");
+    }
+}
@@ -1,32 +1,321 @@
-use winit::event::{ElementState, KeyboardInput};
+use std::collections::{HashMap, HashSet};
+use winit::event::{ElementState, KeyboardInput, MouseButton, MouseScrollDelta};
+use winit::window::WindowId;
+use crate::input_bindings::{Action, InputBindings, KeyBinding};
+
+/// Pixel scroll deltas from touchpads are converted to lines of this height.
+const PIXELS_PER_SCROLL_LINE: f64 = 100.0;
+
+/// Left, right and middle, other buttons are ignored.
+const MOUSE_BUTTON_COUNT: usize = 3;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct MouseButtonState {
+    down: bool,
+    clicked: bool,
+    click_position: Option<(f64, f64)>,
+}
+
+/// Mouse state of a single window, positions are in window pixels with the origin at the top left.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MouseState {
+    cursor_position: (f64, f64),
+    /// Last position the cursor deltas continue from, `None` until the first sample after the
+    /// cursor entered the window or moved over the parameter panel.
+    tracked_position: Option<(f64, f64)>,
+    cursor_delta: (f64, f64),
+    drag_position: (f64, f64),
+    wheel_delta: f64,
+    wheel_total: f64,
+    buttons: [MouseButtonState; MOUSE_BUTTON_COUNT],
+}
+
+impl MouseState {
+    fn button_index(button: MouseButton) -> Option<usize> {
+        match button {
+            MouseButton::Left => Some(0),
+            MouseButton::Right => Some(1),
+            MouseButton::Middle => Some(2),
+            MouseButton::Other(_) => None,
+        }
+    }
+
+    pub fn cursor_position(&self) -> (f64, f64) {
+        self.cursor_position
+    }
+
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        Self::button_index(button).is_some_and(|index| self.buttons[index].down)
+    }
+
+    /// Where `button` was last pressed, if it ever was.
+    pub fn click_position(&self, button: MouseButton) -> Option<(f64, f64)> {
+        Self::button_index(button).and_then(|index| self.buttons[index].click_position)
+    }
+
+    /// How far the cursor moved with the left button down during this frame.
+    pub fn drag_delta(&self) -> (f64, f64) {
+        if self.buttons[0].down { self.cursor_delta } else { (0.0, 0.0) }
+    }
+
+    /// Scrolled lines during this frame, positive away from the user.
+    pub fn wheel_delta(&self) -> f64 {
+        self.wheel_delta
+    }
+
+    /// Mouse state in the Shadertoy `iMouse` layout with a bottom left origin.
+    /// xy is the drag position, zw the click position. z is negative while the button
+    /// is up and w is only positive on the first frame after a click. All zero before any click.
+    pub fn shadertoy_mouse(
+        &self,
+        height: f64
+    ) -> [f32; 4] {
+        let Some(click_position) = self.click_position(MouseButton::Left) else {
+            return [0.0; 4];
+        };
+
+        let click_x = click_position.0 as f32;
+        let click_y = (height - click_position.1) as f32;
+        [
+            self.drag_position.0 as f32,
+            (height - self.drag_position.1) as f32,
+            if self.is_button_down(MouseButton::Left) { click_x } else { -click_x },
+            if self.buttons[0].clicked { click_y } else { -click_y },
+        ]
+    }
+
+    /// `iMouseButtons`: left, right and middle as 1 while down, and the lines scrolled since start.
+    pub fn shader_buttons(&self) -> [f32; 4] {
+        let [left, right, middle] = [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+            .map(|button| if self.is_button_down(button) { 1.0 } else { 0.0 });
+        [left, right, middle, self.wheel_total as f32]
+    }
+
+    fn end_frame(&mut self) {
+        self.cursor_delta = (0.0, 0.0);
+        self.wheel_delta = 0.0;
+        for button in &mut self.buttons {
+            button.clicked = false;
+        }
+    }
+}
 
 pub struct UserIO {
-    keys_changed: Vec<(u32, ElementState)>,
-    mouse_motion_delta: (f64, f64)
+    bindings: InputBindings,
+    keys_held: HashSet<KeyBinding>,
+    keys_pressed: HashSet<KeyBinding>,
+    keys_released: HashSet<KeyBinding>,
+    mouse_motion_delta: (f64, f64),
+    mice: HashMap<WindowId, MouseState>,
 }
 
 impl UserIO {
-    pub fn new() -> UserIO {
+    pub fn new(bindings: InputBindings) -> UserIO {
         UserIO {
-            keys_changed: Vec::new(),
+            bindings,
+            keys_held: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            keys_released: HashSet::new(),
             mouse_motion_delta: (0.0, 0.0),
+            mice: HashMap::new(),
         }
     }
 
-    pub fn set_mouse_delta(
+    /// Raw mouse motion, not limited to a window or by the screen edges.
+    pub fn add_mouse_motion_delta(
         &mut self,
         delta_x: f64,
         delta_y: f64
     ) {
-        self.mouse_motion_delta = (delta_x, delta_y);
+        self.mouse_motion_delta.0 += delta_x;
+        self.mouse_motion_delta.1 += delta_y;
+    }
+
+    /// Raw mouse motion during this frame, in device units.
+    pub fn mouse_motion_delta(&self) -> (f64, f64) {
+        self.mouse_motion_delta
+    }
+
+    /// Moves the cursor, the first position after `forget_cursor_position` adds no delta.
+    pub fn set_cursor_position(
+        &mut self,
+        window_id: WindowId,
+        x: f64,
+        y: f64
+    ) {
+        let mouse = self.mice.entry(window_id).or_default();
+        if let Some((last_x, last_y)) = mouse.tracked_position {
+            mouse.cursor_delta.0 += x - last_x;
+            mouse.cursor_delta.1 += y - last_y;
+        }
+        mouse.tracked_position = Some((x, y));
+        mouse.cursor_position = (x, y);
+        if mouse.buttons[0].down {
+            mouse.drag_position = mouse.cursor_position;
+        }
+    }
+
+    /// For when the cursor left the window or moves where this doesn't see it, such as over
+    /// the parameter panel. Deltas start over from the next position instead of jumping.
+    pub fn forget_cursor_position(
+        &mut self,
+        window_id: WindowId
+    ) {
+        if let Some(mouse) = self.mice.get_mut(&window_id) {
+            mouse.tracked_position = None;
+        }
+    }
+
+    pub fn set_mouse_button(
+        &mut self,
+        window_id: WindowId,
+        button: MouseButton,
+        state: ElementState
+    ) {
+        let mouse = self.mice.entry(window_id).or_default();
+        let Some(index) = MouseState::button_index(button) else {
+            return;
+        };
+
+        let cursor_position = mouse.cursor_position;
+        let button = &mut mouse.buttons[index];
+        match state {
+            ElementState::Pressed => {
+                button.down = true;
+                button.clicked = true;
+                button.click_position = Some(cursor_position);
+                if index == 0 {
+                    mouse.drag_position = cursor_position;
+                }
+            }
+            ElementState::Released => button.down = false,
+        }
+    }
+
+    pub fn add_mouse_wheel_delta(
+        &mut self,
+        window_id: WindowId,
+        delta: &MouseScrollDelta
+    ) {
+        let lines = match delta {
+            MouseScrollDelta::LineDelta(_, lines) => *lines as f64,
+            MouseScrollDelta::PixelDelta(position) => position.y / PIXELS_PER_SCROLL_LINE,
+        };
+
+        let mouse = self.mice.entry(window_id).or_default();
+        mouse.wheel_delta += lines;
+        mouse.wheel_total += lines;
+    }
+
+    /// Mouse state of a window, idle if the pointer never entered it.
+    pub fn mouse(&self, window_id: WindowId) -> MouseState {
+        self.mice.get(&window_id).copied().unwrap_or_default()
     }
 
+    /// Drops the mouse state of a closed window.
+    pub fn remove_window(&mut self, window_id: WindowId) {
+        self.mice.remove(&window_id);
+    }
+
+    /// Every key is tracked by scancode and, if it has one, by virtual keycode, so bindings
+    /// can use either. Key repeats while held are not counted as new presses.
     pub fn set_keyboard_input(
         &mut self,
         keyboard_input: &KeyboardInput
     ) {
-        self.keys_changed.push((keyboard_input.scancode, keyboard_input.state));
+        let keys = [
+            Some(KeyBinding::Scancode(keyboard_input.scancode)),
+            keyboard_input.virtual_keycode.map(KeyBinding::Key),
+        ];
+
+        for key in keys.into_iter().flatten() {
+            match keyboard_input.state {
+                ElementState::Pressed => {
+                    if self.keys_held.insert(key) {
+                        self.keys_pressed.insert(key);
+                    }
+                }
+                ElementState::Released => {
+                    if self.keys_held.remove(&key) {
+                        self.keys_released.insert(key);
+                    }
+                }
+            }
+        }
     }
 
+    /// Releases every held key, for when the window loses focus and would miss the key up.
+    pub fn release_all_keys(&mut self) {
+        self.keys_released.extend(self.keys_held.drain());
+    }
 
-}
\ No newline at end of file
+    /// Whether a key bound to `action` went down during this frame.
+    pub fn is_action_pressed(
+        &self,
+        action: Action
+    ) -> bool {
+        self.bindings.keys(action).iter().any(|key| self.keys_pressed.contains(key))
+    }
+
+    /// Whether a key bound to `action` is down.
+    pub fn is_action_held(
+        &self,
+        action: Action
+    ) -> bool {
+        self.bindings.keys(action).iter().any(|key| self.keys_held.contains(key))
+    }
+
+    /// Whether a key bound to `action` went up during this frame.
+    pub fn is_action_released(
+        &self,
+        action: Action
+    ) -> bool {
+        self.bindings.keys(action).iter().any(|key| self.keys_released.contains(key))
+    }
+
+    /// Resets state that only lasts for a single rendered frame.
+    pub fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.mouse_motion_delta = (0.0, 0.0);
+        for mouse in self.mice.values_mut() {
+            mouse.end_frame();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dragging(window_id: WindowId) -> UserIO {
+        let mut user_io = UserIO::new(InputBindings::default());
+        user_io.set_mouse_button(window_id, MouseButton::Left, ElementState::Pressed);
+        user_io
+    }
+
+    #[test]
+    fn first_position_adds_no_delta() {
+        let window_id = unsafe { WindowId::dummy() };
+        let mut user_io = dragging(window_id);
+        user_io.set_cursor_position(window_id, 400.0, 300.0);
+        assert_eq!(user_io.mouse(window_id).drag_delta(), (0.0, 0.0));
+
+        user_io.set_cursor_position(window_id, 410.0, 295.0);
+        assert_eq!(user_io.mouse(window_id).drag_delta(), (10.0, -5.0));
+    }
+
+    #[test]
+    fn forgotten_position_starts_over() {
+        let window_id = unsafe { WindowId::dummy() };
+        let mut user_io = dragging(window_id);
+        user_io.set_cursor_position(window_id, 10.0, 10.0);
+        user_io.end_frame();
+
+        // The cursor left the window or crossed the panel and comes back far away.
+        user_io.forget_cursor_position(window_id);
+        user_io.set_cursor_position(window_id, 500.0, 20.0);
+        assert_eq!(user_io.mouse(window_id).drag_delta(), (0.0, 0.0));
+        assert_eq!(user_io.mouse(window_id).cursor_position(), (500.0, 20.0));
+    }
+}
@@ -0,0 +1,361 @@
+//! Renders the example scenes through the command line and compares them with the reference
+//! images in `tests/golden`. The references come from the CPU evaluator, run with
+//! `UPDATE_GOLDEN=1 cargo test --test golden_images` to write new ones after an intended change.
+//!
+//! The Vulkan tests compare the shaders with the same references and run whenever the Vulkan
+//! loader finds a device with compute support, lavapipe is enough. Without one they print a
+//! warning and pass, `REQUIRE_VULKAN=1` turns the skip into a failure, for CI machines that are
+//! set up with a device.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::Command;
+use vulkano::device::QueueFlags;
+use vulkano::instance::{Instance, InstanceCreateInfo};
+use vulkano::VulkanLibrary;
+
+const BINARY: &str = env!("CARGO_BIN_EXE_simple-sdf-compute-shader");
+const SIZE: u32 = 128;
+
+/// Every scene is rendered at frame 0 and 1, which is `0.0` and `1.25` seconds.
+const FRAME_COUNT: u32 = 2;
+const FRAME_RATE: &str = "0.8";
+
+/// Path traced references stay noisy, but a few samples already cover every material and
+/// keep the debug build fast.
+const PATH_SAMPLES: &str = "4";
+
+/// Scene, `--mode`, palette from `config/palettes.ron` and whether the shader parameters come
+/// from `config/parameters.ron`, of every reference. 3D references get a `_3d` or `_path`
+/// suffix, other palettes than `default` their name and parameter files `_parameters` as well.
+const RENDERS: [(&str, &str, &str, bool); 14] = [
+    ("triangle", "2d", "default", false),
+    ("csg", "2d", "default", false),
+    ("domain", "2d", "default", false),
+    ("primitives", "2d", "default", false),
+    ("materials", "2d", "default", false),
+    ("timeline", "2d", "default", false),
+    ("triangle", "2d", "sunrise", false),
+    ("triangle", "2d", "default", true),
+    ("triangle", "3d", "default", false),
+    ("csg", "3d", "default", false),
+    ("solids", "3d", "default", false),
+    ("materials", "3d", "default", false),
+    ("csg", "3d", "default", true),
+    ("materials", "path", "default", false),
+];
+
+/// Neither side is a multiple of `ODD_WORKGROUP_SIZE`, so the last workgroups of every row and
+/// column reach past the image.
+const ODD_SIZE: [u32; 2] = [1023, 577];
+const ODD_WORKGROUP_SIZE: [u32; 2] = [16, 4];
+
+#[derive(Clone, Copy, Debug)]
+enum Renderer {
+    Cpu,
+    Vulkan,
+}
+
+/// Both limits have to hold. The PSNR catches changes spread over the image, the channel
+/// error changes to a few pixels, such as a shading or palette change in one region.
+struct Tolerance {
+    min_psnr: f64,
+    max_channel_error: u8,
+}
+
+impl Renderer {
+    fn name(self) -> &'static str {
+        match self {
+            Renderer::Cpu => "cpu",
+            Renderer::Vulkan => "vulkan",
+        }
+    }
+
+    fn tolerance(self) -> Tolerance {
+        match self {
+            // Only differences in the platform `libm` are expected.
+            Renderer::Cpu => Tolerance { min_psnr: 50.0, max_channel_error: 8 },
+            // GPU `sin`, `exp` and `pow` are less precise than `libm`, which moves the thin
+            // lines by a fraction of a pixel.
+            Renderer::Vulkan => Tolerance { min_psnr: 42.0, max_channel_error: 16 },
+        }
+    }
+}
+
+struct Image {
+    size: [u32; 2],
+    pixels: Vec<u8>,
+}
+
+#[test]
+fn cpu_evaluator_matches_golden_images() {
+    check_scenes(Renderer::Cpu);
+}
+
+#[test]
+fn vulkan_matches_golden_images() {
+    if vulkan_device_or_skip() {
+        check_scenes(Renderer::Vulkan);
+    }
+}
+
+/// Renders at `ODD_SIZE` with another workgroup size than the default and compares with the
+/// CPU evaluator at the same size. The strips the partial workgroups cover are compared on
+/// their own, a few wrong columns would hardly move the PSNR of the whole image.
+#[test]
+fn vulkan_covers_odd_sizes_completely() {
+    if !vulkan_device_or_skip() {
+        return;
+    }
+
+    let manifest_directory = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output_directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden").join("odd_size");
+    fs::create_dir_all(&output_directory).unwrap();
+    let scene = manifest_directory.join("scenes").join("csg.ron");
+    let [width, height] = ODD_SIZE;
+
+    let render = |renderer: Renderer| {
+        let output = output_directory.join(format!("csg_{}.png", renderer.name()));
+        let mut command = Command::new(BINARY);
+        command
+            .arg("--scene").arg(&scene)
+            .arg("--output").arg(&output)
+            .args(["--width", &width.to_string(), "--height", &height.to_string()]);
+        match renderer {
+            Renderer::Cpu => command.arg("--cpu"),
+            Renderer::Vulkan => command.args(["--workgroup-size", &format!("{}x{}", ODD_WORKGROUP_SIZE[0], ODD_WORKGROUP_SIZE[1])]),
+        };
+        let result = command.output().unwrap();
+        assert!(result.status.success(), "rendering at {width}x{height} failed:\n{}", String::from_utf8_lossy(&result.stderr));
+        read_png(&output)
+    };
+    let expected = render(Renderer::Cpu);
+    let actual = render(Renderer::Vulkan);
+    assert_eq!(actual.size, ODD_SIZE);
+
+    let tolerance = Renderer::Vulkan.tolerance();
+    let [strip_width, strip_height] = [width % ODD_WORKGROUP_SIZE[0], height % ODD_WORKGROUP_SIZE[1]];
+    let regions = [
+        ("whole image", [0, 0], ODD_SIZE),
+        ("right strip", [width - strip_width, 0], [strip_width, height]),
+        // Files store the top row first, the partial workgroups cover the top rows of the image.
+        ("top strip", [0, 0], [width, strip_height]),
+    ];
+    for (name, origin, size) in regions {
+        let (psnr, max_channel_error) = compare(&crop(&actual, origin, size), &crop(&expected, origin, size));
+        assert!(
+            psnr >= tolerance.min_psnr && max_channel_error <= tolerance.max_channel_error,
+            "{name} of the {width}x{height} image: PSNR {psnr:.2} dB (min {}), max channel error {max_channel_error} (max {})",
+            tolerance.min_psnr,
+            tolerance.max_channel_error,
+        );
+    }
+}
+
+/// Whether a Vulkan device was found. Without one the calling test is skipped, unless
+/// `REQUIRE_VULKAN` is set.
+fn vulkan_device_or_skip() -> bool {
+    if vulkan_compute_device_available() {
+        return true;
+    }
+
+    assert!(
+        std::env::var_os("REQUIRE_VULKAN").is_none(),
+        "REQUIRE_VULKAN is set but no Vulkan device with compute support was found",
+    );
+    // `eprintln!` is captured for passing tests, writing to the handle directly shows the skip
+    // in every run, so a green run doesn't look like the shaders were checked.
+    let _ = writeln!(
+        std::io::stderr(),
+        "warning: skipping the Vulkan comparison, no Vulkan device with compute support was found, set REQUIRE_VULKAN=1 to fail instead",
+    );
+    false
+}
+
+/// Whether headless rendering can find a device, the same way `Application::headless` looks for
+/// one. Vulkan guarantees compute on devices with a graphics queue.
+fn vulkan_compute_device_available() -> bool {
+    let Ok(library) = VulkanLibrary::new() else {
+        return false;
+    };
+    let create_info = InstanceCreateInfo { enumerate_portability: true, ..Default::default() };
+    let Ok(instance) = Instance::new(library, create_info) else {
+        return false;
+    };
+
+    instance.enumerate_physical_devices().is_ok_and(|mut devices| {
+        devices.any(|device| {
+            device.queue_family_properties().iter().any(|family| family.queue_flags.intersects(QueueFlags::GRAPHICS))
+        })
+    })
+}
+
+/// Renders every scene, compares all frames and fails once at the end, so a single run
+/// writes the diff images of every mismatch.
+fn check_scenes(renderer: Renderer) {
+    let manifest_directory = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let golden_directory = manifest_directory.join("tests/golden");
+    let output_directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden").join(renderer.name());
+    fs::create_dir_all(&output_directory).unwrap();
+
+    // References always come from the CPU evaluator, both tests run with `UPDATE_GOLDEN` set.
+    let update = matches!(renderer, Renderer::Cpu) && std::env::var_os("UPDATE_GOLDEN").is_some();
+    let tolerance = renderer.tolerance();
+    let mut failures = Vec::new();
+
+    for (scene, mode, palette, parameters) in RENDERS {
+        let mut name = if mode == "2d" { scene.to_string() } else { format!("{scene}_{mode}") };
+        if palette != "default" {
+            name = format!("{name}_{palette}");
+        }
+        if parameters {
+            name = format!("{name}_parameters");
+        }
+        let scene_path = manifest_directory.join("scenes").join(format!("{scene}.ron"));
+        render_scene(renderer, &scene_path, mode, palette, parameters, &output_directory.join(format!("{name}.png")));
+
+        for frame in 0..FRAME_COUNT {
+            let file_name = format!("{name}_{frame:04}.png");
+            let actual_path = output_directory.join(&file_name);
+            let golden_path = golden_directory.join(&file_name);
+            let actual = read_png(&actual_path);
+
+            if update {
+                fs::create_dir_all(&golden_directory).unwrap();
+                fs::copy(&actual_path, &golden_path).unwrap();
+                continue;
+            }
+
+            let golden = read_png(&golden_path);
+            assert_eq!(actual.size, golden.size, "{file_name} has a different size than its reference");
+
+            // Diff images of an earlier failed run would look like a current failure.
+            let diff_path = output_directory.join(format!("{name}_{frame:04}_diff.png"));
+            let _ = fs::remove_file(&diff_path);
+
+            let (psnr, max_channel_error) = compare(&actual, &golden);
+            if psnr < tolerance.min_psnr || max_channel_error > tolerance.max_channel_error {
+                write_png(&diff_path, &diff_image(&actual, &golden));
+                failures.push(format!(
+                    "{file_name}: PSNR {psnr:.2} dB (min {}), max channel error {max_channel_error} (max {})\n  actual: {}\n  diff:   {}",
+                    tolerance.min_psnr,
+                    tolerance.max_channel_error,
+                    actual_path.display(),
+                    diff_path.display(),
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{} frames differ from their reference:\n{}", failures.len(), failures.join("\n"));
+}
+
+fn render_scene(renderer: Renderer, scene: &Path, mode: &str, palette: &str, parameters: bool, output: &Path) {
+    let config_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("config");
+    let palettes = config_directory.join("palettes.ron");
+    let mut command = Command::new(BINARY);
+    command
+        .arg("--scene").arg(scene)
+        .arg("--palettes").arg(palettes)
+        .args(["--palette", palette])
+        .arg("--output").arg(output)
+        .args(["--mode", mode])
+        .args(["--width", &SIZE.to_string(), "--height", &SIZE.to_string()])
+        .args(["--frames", &FRAME_COUNT.to_string(), "--fps", FRAME_RATE])
+        .args(["--samples", PATH_SAMPLES]);
+    if parameters {
+        command.arg("--parameters").arg(config_directory.join("parameters.ron"));
+    }
+    if let Renderer::Cpu = renderer {
+        command.arg("--cpu");
+    }
+
+    let result = command.output().unwrap();
+    assert!(
+        result.status.success(),
+        "rendering {} failed:\n{}",
+        scene.display(),
+        String::from_utf8_lossy(&result.stderr),
+    );
+}
+
+/// PSNR over the colour channels and the largest difference of any single channel.
+fn compare(actual: &Image, golden: &Image) -> (f64, u8) {
+    let mut squared_error_sum = 0.0;
+    let mut max_channel_error = 0;
+    let mut channel_count = 0;
+    for (a, b) in actual.pixels.chunks_exact(4).zip(golden.pixels.chunks_exact(4)) {
+        for channel in 0..3 {
+            let error = a[channel].abs_diff(b[channel]);
+            squared_error_sum += (error as f64).powi(2);
+            max_channel_error = max_channel_error.max(error);
+            channel_count += 1;
+        }
+    }
+
+    let mean_squared_error = squared_error_sum / channel_count as f64;
+    let psnr = if mean_squared_error == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0 * 255.0 / mean_squared_error).log10()
+    };
+    (psnr, max_channel_error)
+}
+
+/// Absolute channel differences, scaled up so small errors are still visible.
+fn diff_image(actual: &Image, golden: &Image) -> Image {
+    let pixels = actual.pixels
+        .chunks_exact(4)
+        .zip(golden.pixels.chunks_exact(4))
+        .flat_map(|(a, b)| {
+            let channel = |index: usize| a[index].abs_diff(b[index]).saturating_mul(8);
+            [channel(0), channel(1), channel(2), 255]
+        })
+        .collect();
+
+    Image {
+        size: actual.size,
+        pixels,
+    }
+}
+
+fn crop(image: &Image, origin: [u32; 2], size: [u32; 2]) -> Image {
+    let row_length = image.size[0] as usize * 4;
+    let pixels = image.pixels
+        .chunks_exact(row_length)
+        .skip(origin[1] as usize)
+        .take(size[1] as usize)
+        .flat_map(|row| &row[origin[0] as usize * 4..(origin[0] + size[0]) as usize * 4])
+        .copied()
+        .collect();
+
+    Image {
+        size,
+        pixels,
+    }
+}
+
+fn read_png(path: &Path) -> Image {
+    let file = File::open(path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(info.color_type, png::ColorType::Rgba, "{} is not RGBA", path.display());
+    pixels.truncate(info.buffer_size());
+
+    Image {
+        size: [info.width, info.height],
+        pixels,
+    }
+}
+
+fn write_png(path: &Path, image: &Image) {
+    let file = File::create(path).unwrap();
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.size[0], image.size[1]);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().unwrap().write_image_data(&image.pixels).unwrap();
+}